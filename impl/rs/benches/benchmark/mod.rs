@@ -1 +1,2 @@
 pub mod dummy;
+pub mod value_table;
@@ -0,0 +1,120 @@
+use criterion::{criterion_group, Criterion};
+use jeff::jeff_capnp;
+use jeff::reader::{Function, ReadJeff};
+use jeff::types::Type;
+use jeff::Jeff;
+
+use crate::helper::*;
+
+// -----------------------------------------------------------------------------
+// Benchmark functions
+// -----------------------------------------------------------------------------
+
+/// Encode a module with a single function containing `size` values.
+fn synthetic_module(size: usize) -> Vec<u8> {
+    let mut message = capnp::message::Builder::new_default();
+    let mut module = message.init_root::<jeff_capnp::module::Builder<'_>>();
+    module.set_version(jeff::SCHEMA_VERSION.major as u32);
+    module.set_version_minor(jeff::SCHEMA_VERSION.minor as u32);
+    module.set_version_patch(jeff::SCHEMA_VERSION.patch as u32);
+    module.reborrow().init_strings(1).set(0, "main");
+
+    let mut function = module.init_functions(1).get(0);
+    function.set_name(0);
+    let mut definition = function.init_definition();
+    definition.reborrow().init_body();
+    let mut values = definition.init_values(size as u32);
+    for i in 0..size as u32 {
+        let mut value = values.reborrow().get(i);
+        value.reborrow().init_metadata(1).get(0).set_name(0);
+        let mut ty = value.init_type();
+        match i % 3 {
+            0 => ty.set_qubit(()),
+            1 => ty.set_int(32),
+            _ => ty.set_float(jeff_capnp::FloatPrecision::Float64),
+        }
+    }
+
+    let mut buffer = Vec::new();
+    capnp::serialize::write_message(&mut buffer, &message).unwrap();
+    buffer
+}
+
+/// Read a value table, decoding every [`jeff::reader::WireValue`].
+struct IterValues {
+    jeff: Jeff<'static>,
+}
+
+/// Read a value table, decoding only the value types.
+struct IterTypes {
+    jeff: Jeff<'static>,
+}
+
+impl SizedBenchmark for IterValues {
+    fn name() -> &'static str {
+        "value_table_iter"
+    }
+
+    fn setup(size: usize) -> Self {
+        let jeff = Jeff::read(synthetic_module(size).as_slice()).unwrap();
+        Self { jeff }
+    }
+
+    fn run(&self) -> impl Sized {
+        let Function::Definition(main) = self.jeff.module().function(0) else {
+            unreachable!()
+        };
+        main.values()
+            .iter()
+            .map(|(_, v)| v.ty())
+            .filter(|ty| matches!(ty, Type::Qubit))
+            .count()
+    }
+}
+
+impl SizedBenchmark for IterTypes {
+    fn name() -> &'static str {
+        "value_table_iter_types"
+    }
+
+    fn setup(size: usize) -> Self {
+        let jeff = Jeff::read(synthetic_module(size).as_slice()).unwrap();
+        Self { jeff }
+    }
+
+    fn run(&self) -> impl Sized {
+        let Function::Definition(main) = self.jeff.module().function(0) else {
+            unreachable!()
+        };
+        main.values()
+            .iter_types()
+            .filter(|(_, ty)| matches!(ty, Ok(Type::Qubit)))
+            .count()
+    }
+}
+
+// -----------------------------------------------------------------------------
+// iai_callgrind definitions
+// -----------------------------------------------------------------------------
+
+sized_iai_benchmark!(callgrind_iter_values, IterValues);
+sized_iai_benchmark!(callgrind_iter_types, IterTypes);
+
+iai_callgrind::library_benchmark_group!(
+    name = callgrind_group;
+    benchmarks =
+        callgrind_iter_values,
+        callgrind_iter_types,
+);
+
+// -----------------------------------------------------------------------------
+// Criterion definitions
+// -----------------------------------------------------------------------------
+
+criterion_group! {
+    name = criterion_group;
+    config = Criterion::default();
+    targets =
+        IterValues::criterion,
+        IterTypes::criterion,
+}
@@ -7,4 +7,5 @@ use criterion::criterion_main;
 
 criterion_main! {
     benchmark::dummy::criterion_group,
+    benchmark::value_table::criterion_group,
 }
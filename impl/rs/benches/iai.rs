@@ -6,5 +6,6 @@ mod helper;
 use iai_callgrind::main;
 
 use benchmark::dummy::callgrind_group as dummy;
+use benchmark::value_table::callgrind_group as value_table;
 
-main!(library_benchmark_groups = dummy,);
+main!(library_benchmark_groups = dummy, value_table);
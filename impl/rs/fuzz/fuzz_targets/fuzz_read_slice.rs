@@ -0,0 +1,14 @@
+//! Reading arbitrary bytes must fail with an error instead of panicking.
+#![no_main]
+
+use jeff::Jeff;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // `Jeff::read_slice` requires 8-byte aligned input.
+    let mut words = capnp::Word::allocate_zeroed_vec(data.len().div_ceil(8));
+    capnp::Word::words_to_bytes_mut(&mut words)[..data.len()].copy_from_slice(data);
+    let mut slice = capnp::Word::words_to_bytes(&words);
+
+    let _ = Jeff::read_slice(&mut slice);
+});
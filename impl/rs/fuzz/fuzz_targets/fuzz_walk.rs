@@ -0,0 +1,60 @@
+//! Traversing a successfully read module, including the nested regions of
+//! control flow operations and the metadata entries, must not panic.
+#![no_main]
+
+use jeff::reader::optype::OpType;
+use jeff::reader::{Function, HasMetadata, ReadJeff, Region};
+use jeff::Jeff;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // `Jeff::read_slice` requires 8-byte aligned input.
+    let mut words = capnp::Word::allocate_zeroed_vec(data.len().div_ceil(8));
+    capnp::Word::words_to_bytes_mut(&mut words)[..data.len()].copy_from_slice(data);
+    let mut slice = capnp::Word::words_to_bytes(&words);
+
+    let Ok(jeff) = Jeff::read_slice(&mut slice) else {
+        return;
+    };
+    let module = jeff.module();
+    if module.verify_strings().is_err() {
+        return;
+    }
+    walk_metadata(&module);
+
+    for idx in 0..module.function_count() as u32 {
+        let Some(function) = module.try_function(idx) else {
+            continue;
+        };
+        let _ = function.input_types().count();
+        let _ = function.output_types().count();
+        walk_metadata(&function);
+        if let Function::Definition(def) = function {
+            let _ = def.values().iter_types().count();
+            walk_region(def.body());
+        }
+    }
+});
+
+fn walk_region(region: Region<'_>) {
+    let _ = region.sources().count();
+    let _ = region.targets().count();
+    walk_metadata(&region);
+    for op in region.operations() {
+        let _ = op.inputs().count();
+        let _ = op.outputs().count();
+        walk_metadata(&op);
+        if let OpType::ControlFlowOp(cf_op) = op.op_type() {
+            for nested in cf_op.regions() {
+                walk_region(nested);
+            }
+        }
+    }
+}
+
+fn walk_metadata(item: &impl HasMetadata) {
+    for meta in item.metadata_entries() {
+        let _ = meta.name();
+        let _ = meta.to_owned_value();
+    }
+}
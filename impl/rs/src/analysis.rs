@@ -0,0 +1,257 @@
+//! Analysis reports stored alongside jeff programs.
+//!
+//! An [`AnalysisReport`] records the results of analysing each function in a
+//! module. Reports are encoded as a separate Cap'n Proto message defined in
+//! `analysis.capnp`, and refer to the module they describe by its
+//! [`Module::content_hash`].
+
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::reader::{Function, FunctionId, InteractionGraph, Module};
+#[cfg(feature = "std")]
+use crate::{capnp::analysis_capnp, reader::LogicalQubit, JeffError};
+
+/// Results of analysing the functions in a module.
+///
+/// See [`write_analysis`] and [`read_analysis`] for encoding reports.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AnalysisReport {
+    /// The [`Module::content_hash`] of the analysed module.
+    pub content_hash: u64,
+    /// Analysis results for each function in the module, in module order.
+    pub functions: Vec<FunctionAnalysis>,
+}
+
+/// Analysis results for a single function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FunctionAnalysis {
+    /// The name of the function.
+    pub name: String,
+    /// Number of operations in the function body, including operations in
+    /// nested control flow regions. Zero for declarations.
+    pub operation_count: usize,
+    /// Number of gate operations in the function body, including gates in
+    /// nested control flow regions. Zero for declarations.
+    pub gate_count: usize,
+    /// Number of operations on the longest dependency chain between the
+    /// top-level operations of the function body. Zero for declarations.
+    pub depth: usize,
+    /// Interactions between the logical qubits of the function. Empty for
+    /// declarations.
+    pub interactions: InteractionGraph,
+}
+
+impl AnalysisReport {
+    /// Analyses every function in `module`.
+    ///
+    /// See [`Module::function_summary`] and
+    /// [`FunctionDefinition::interaction_graph`] for the computed results.
+    ///
+    /// [`FunctionDefinition::interaction_graph`]: crate::reader::FunctionDefinition::interaction_graph
+    pub fn new(module: &Module<'_>) -> Self {
+        let functions = (0..module.function_count() as FunctionId)
+            .map(|id| {
+                let summary = module
+                    .function_summary(id)
+                    .expect("Function ids below the function count should be valid");
+                let interactions = match module.function(id) {
+                    Function::Definition(def) => def.interaction_graph(),
+                    Function::Declaration(_) => InteractionGraph::default(),
+                };
+                FunctionAnalysis {
+                    name: summary.name,
+                    operation_count: summary.operation_count,
+                    gate_count: summary.gate_count,
+                    depth: summary.depth,
+                    interactions,
+                }
+            })
+            .collect();
+        Self {
+            content_hash: module.content_hash(),
+            functions,
+        }
+    }
+
+    /// Returns `true` if this report was computed for `module`.
+    ///
+    /// Reports are matched to modules by their [`Module::content_hash`], so a
+    /// report becomes stale as soon as the module is modified.
+    pub fn is_for(&self, module: &Module<'_>) -> bool {
+        self.content_hash == module.content_hash()
+    }
+}
+
+/// Encodes an analysis report as a Cap'n Proto message.
+///
+/// # Errors
+///
+/// - [`JeffError::InvalidFile`] if the message could not be written.
+#[cfg(feature = "std")]
+pub fn write_analysis(report: &AnalysisReport, w: impl std::io::Write) -> Result<(), JeffError> {
+    let mut message = capnp::message::Builder::new_default();
+    let mut root = message.init_root::<analysis_capnp::analysis_report::Builder<'_>>();
+    root.set_content_hash(report.content_hash);
+    let mut functions = root.init_functions(report.functions.len() as u32);
+    for (i, function) in report.functions.iter().enumerate() {
+        write_function(functions.reborrow().get(i as u32), function);
+    }
+    capnp::serialize::write_message(w, &message)?;
+    Ok(())
+}
+
+/// Decodes an analysis report written by [`write_analysis`].
+///
+/// # Errors
+///
+/// - [`JeffError::InvalidFile`] if the data is not a valid analysis report.
+#[cfg(feature = "std")]
+pub fn read_analysis(r: impl std::io::Read) -> Result<AnalysisReport, JeffError> {
+    let message = capnp::serialize::read_message(r, capnp::message::ReaderOptions::new())?;
+    let root = message.get_root::<analysis_capnp::analysis_report::Reader<'_>>()?;
+    let functions = root
+        .get_functions()?
+        .iter()
+        .map(read_function)
+        .collect::<Result<_, _>>()?;
+    Ok(AnalysisReport {
+        content_hash: root.get_content_hash(),
+        functions,
+    })
+}
+
+/// Encodes the results for a single function.
+#[cfg(feature = "std")]
+fn write_function(
+    mut builder: analysis_capnp::function_analysis::Builder<'_>,
+    function: &FunctionAnalysis,
+) {
+    builder.set_name(function.name.as_str());
+    builder.set_operation_count(function.operation_count as u64);
+    builder.set_gate_count(function.gate_count as u64);
+    builder.set_depth(function.depth as u64);
+
+    let graph = &function.interactions;
+    let mut qubits = builder.reborrow().init_qubits(graph.qubits.len() as u32);
+    for (i, &qubit) in graph.qubits.iter().enumerate() {
+        write_qubit(qubits.reborrow().get(i as u32), qubit);
+    }
+    let mut interactions = builder.init_interactions(graph.edges.len() as u32);
+    for (i, (&(a, b), &count)) in graph.edges.iter().enumerate() {
+        let mut interaction = interactions.reborrow().get(i as u32);
+        write_qubit(interaction.reborrow().init_a(), a);
+        write_qubit(interaction.reborrow().init_b(), b);
+        interaction.set_count(count as u64);
+    }
+}
+
+/// Decodes the results for a single function.
+#[cfg(feature = "std")]
+fn read_function(
+    reader: analysis_capnp::function_analysis::Reader<'_>,
+) -> capnp::Result<FunctionAnalysis> {
+    let mut interactions = InteractionGraph::default();
+    for qubit in reader.get_qubits()? {
+        interactions.qubits.insert(read_qubit(qubit)?);
+    }
+    for interaction in reader.get_interactions()? {
+        let a = read_qubit(interaction.get_a()?)?;
+        let b = read_qubit(interaction.get_b()?)?;
+        interactions
+            .edges
+            .insert((a, b), interaction.get_count() as usize);
+    }
+    Ok(FunctionAnalysis {
+        name: reader.get_name()?.to_str()?.to_string(),
+        operation_count: reader.get_operation_count() as usize,
+        gate_count: reader.get_gate_count() as usize,
+        depth: reader.get_depth() as usize,
+        interactions,
+    })
+}
+
+/// Encodes a logical qubit.
+#[cfg(feature = "std")]
+fn write_qubit(mut builder: analysis_capnp::logical_qubit::Builder<'_>, qubit: LogicalQubit) {
+    let (kind, index) = match qubit {
+        LogicalQubit::Input(idx) => (analysis_capnp::QubitKind::Input, idx),
+        LogicalQubit::Alloc(idx) => (analysis_capnp::QubitKind::Alloc, idx),
+        LogicalQubit::Unknown => (analysis_capnp::QubitKind::Unknown, 0),
+    };
+    builder.set_kind(kind);
+    builder.set_index(index as u32);
+}
+
+/// Decodes a logical qubit.
+#[cfg(feature = "std")]
+fn read_qubit(reader: analysis_capnp::logical_qubit::Reader<'_>) -> capnp::Result<LogicalQubit> {
+    let index = reader.get_index() as usize;
+    Ok(match reader.get_kind()? {
+        analysis_capnp::QubitKind::Input => LogicalQubit::Input(index),
+        analysis_capnp::QubitKind::Alloc => LogicalQubit::Alloc(index),
+        analysis_capnp::QubitKind::Unknown => LogicalQubit::Unknown,
+    })
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use crate::reader::ReadJeff;
+    use crate::test::{catalyst_simple, edited, entangled_calls, entangled_qs};
+    use crate::Jeff;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::entangled_qs(entangled_qs())]
+    #[case::entangled_calls(entangled_calls())]
+    #[case::catalyst_simple(catalyst_simple())]
+    fn round_trip(#[case] jeff: Jeff<'static>) {
+        let module = jeff.module();
+        let report = AnalysisReport::new(&module);
+        assert_eq!(report.content_hash, module.content_hash());
+        assert_eq!(report.functions.len(), module.function_count());
+
+        let mut buffer = Vec::new();
+        write_analysis(&report, &mut buffer).unwrap();
+        let read = read_analysis(buffer.as_slice()).unwrap();
+        assert_eq!(read, report);
+        assert!(read.is_for(&module));
+    }
+
+    #[rstest]
+    fn entangled_report(entangled_qs: Jeff<'static>) {
+        let report = AnalysisReport::new(&entangled_qs.module());
+        let [main] = report.functions.as_slice() else {
+            panic!("Expected a single function");
+        };
+        let summary = entangled_qs.module().function_summary(0).unwrap();
+        assert_eq!(main.name, summary.name);
+        assert_eq!(main.operation_count, summary.operation_count);
+        assert_eq!(main.gate_count, summary.gate_count);
+        assert_eq!(main.depth, summary.depth);
+        assert_eq!(main.interactions.qubits.len(), 5);
+        assert!(!main.interactions.edges.is_empty());
+    }
+
+    #[rstest]
+    fn stale_report(entangled_qs: Jeff<'static>) {
+        let report = AnalysisReport::new(&entangled_qs.module());
+        let changed = edited(&entangled_qs, |mut module| module.set_entrypoint(1));
+        assert!(report.is_for(&entangled_qs.module()));
+        assert!(!report.is_for(&changed.module()));
+    }
+
+    #[test]
+    fn reject_invalid_report() {
+        assert!(matches!(
+            read_analysis(&b"not a capnp message"[..]),
+            Err(JeffError::InvalidFile(_))
+        ));
+    }
+}
@@ -0,0 +1,734 @@
+//! Helpers for writing jeff programs with the capnp builders.
+//!
+//! Like [`jeff_capnp`], these operate directly on the generated capnp types
+//! and provide no semver guarantees.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::capnp::jeff_capnp;
+use crate::reader::string_table::StringTable;
+use crate::reader::{Function, FunctionId, MetaScope, Metadata, MetadataValue, Module, ReadError};
+use crate::types::FunctionType;
+use crate::{JeffError, LinkError};
+
+/// Removes duplicate entries from the string table of a module.
+///
+/// Builders that intern strings naively may store the same string several
+/// times, e.g. the name of a custom gate for every application of the gate.
+/// This keeps the first occurrence of each string and rewrites every string
+/// index in the module (function names, metadata names, and custom gate names)
+/// to point at the deduplicated table.
+///
+/// The storage of the replaced table is zeroed but stays in the message.
+/// Copy the module into a new message to reclaim the space.
+///
+/// # Errors
+///
+/// - [`ReadError::StringNotUtf8`] if a string in the table is not valid utf8.
+/// - [`ReadError::StringOutOfBounds`] if a string index is out of bounds.
+/// - [`JeffError::InvalidFile`] if the module is malformed.
+pub fn dedup_strings(mut module: jeff_capnp::module::Builder<'_>) -> Result<(), JeffError> {
+    let mut unique: Vec<String> = Vec::new();
+    let mut positions: BTreeMap<String, u16> = BTreeMap::new();
+    let mut remap = Vec::new();
+    for (idx, string) in module
+        .reborrow_as_reader()
+        .get_strings()?
+        .iter()
+        .enumerate()
+    {
+        let string = string?.to_str().map_err(|e| ReadError::StringNotUtf8 {
+            context: "string table",
+            idx: idx as u32,
+            source: e,
+        })?;
+        let new_idx = *positions.entry(string.to_string()).or_insert_with(|| {
+            unique.push(string.to_string());
+            (unique.len() - 1) as u16
+        });
+        remap.push(new_idx);
+    }
+    if unique.len() == remap.len() {
+        return Ok(());
+    }
+    let remap = IndexRemap {
+        strings: remap,
+        functions: None,
+    };
+
+    remap.metadata(module.reborrow().get_metadata()?)?;
+    let mut functions = module.reborrow().get_functions()?;
+    for i in 0..functions.len() {
+        remap.function(functions.reborrow().get(i))?;
+    }
+
+    let mut strings = module.init_strings(unique.len() as u32);
+    for (i, string) in unique.iter().enumerate() {
+        strings.set(i as u32, string.as_str());
+    }
+    Ok(())
+}
+
+/// Links several modules into a single module, see [`Jeff::link`].
+///
+/// [`Jeff::link`]: crate::Jeff::link
+pub(crate) fn link(modules: &[Module<'_>]) -> Result<Vec<u8>, LinkError> {
+    let Some(first) = modules.first() else {
+        return Err(LinkError::NoModules);
+    };
+
+    // Resolve the functions by name, preferring definitions over declarations.
+    let mut linked: Vec<LinkedFunction> = Vec::new();
+    let mut by_name: BTreeMap<String, u16> = BTreeMap::new();
+    let mut function_remaps: Vec<Vec<u16>> = Vec::with_capacity(modules.len());
+    for (module_idx, module) in modules.iter().enumerate() {
+        let mut remap = Vec::with_capacity(module.function_count());
+        for (id, function) in module.functions_indexed() {
+            let name = module
+                .strings()
+                .get(function_name_idx(module, id), "function name")
+                .map_err(JeffError::from)?;
+            let signature = function.signature().map_err(JeffError::from)?;
+            let is_definition = matches!(function, Function::Definition(_));
+            let Some(&idx) = by_name.get(name) else {
+                let new_idx =
+                    u16::try_from(linked.len()).map_err(|_| LinkError::TooManyFunctions)?;
+                by_name.insert(name.to_string(), new_idx);
+                remap.push(new_idx);
+                linked.push(LinkedFunction {
+                    module: module_idx,
+                    id,
+                    signature,
+                    is_definition,
+                });
+                continue;
+            };
+            let entry = &mut linked[idx as usize];
+            if entry.signature != signature {
+                return Err(LinkError::SignatureMismatch {
+                    name: name.to_string(),
+                    first: entry.signature.clone(),
+                    second: signature,
+                });
+            }
+            if is_definition {
+                if entry.is_definition {
+                    return Err(LinkError::DuplicateDefinition {
+                        name: name.to_string(),
+                    });
+                }
+                entry.module = module_idx;
+                entry.id = id;
+                entry.is_definition = true;
+            }
+            remap.push(idx);
+        }
+        function_remaps.push(remap);
+    }
+
+    // Merge the string tables, dropping duplicates.
+    let mut strings: Vec<&str> = Vec::new();
+    let mut positions: BTreeMap<&str, u16> = BTreeMap::new();
+    let mut remaps: Vec<IndexRemap> = Vec::with_capacity(modules.len());
+    for (module, functions) in modules.iter().zip(function_remaps) {
+        let table = module.strings();
+        let mut remap = Vec::with_capacity(table.len());
+        for idx in 0..table.len() {
+            let string = table
+                .get(idx as u16, "string table")
+                .map_err(JeffError::from)?;
+            let new_idx = match positions.get(string) {
+                Some(&new_idx) => new_idx,
+                None => {
+                    let new_idx =
+                        u16::try_from(strings.len()).map_err(|_| LinkError::TooManyStrings)?;
+                    strings.push(string);
+                    positions.insert(string, new_idx);
+                    new_idx
+                }
+            };
+            remap.push(new_idx);
+        }
+        remaps.push(IndexRemap {
+            strings: remap,
+            functions: Some(functions),
+        });
+    }
+
+    // Start from a copy of the first module, to keep its version, tool, and
+    // metadata, and replace its tables.
+    let mut message = capnp::message::Builder::new_default();
+    message
+        .set_root(first.capnp_reader())
+        .map_err(JeffError::from)?;
+    let mut root: jeff_capnp::module::Builder<'_> = message.get_root().map_err(JeffError::from)?;
+    let entrypoint = remaps[0].function_id(first.capnp_reader().get_entrypoint())?;
+    root.set_entrypoint(entrypoint);
+    remaps[0].metadata(root.reborrow().get_metadata().map_err(JeffError::from)?)?;
+
+    let mut functions = root.reborrow().init_functions(linked.len() as u32);
+    for (i, entry) in linked.iter().enumerate() {
+        let source = modules[entry.module]
+            .capnp_reader()
+            .get_functions()
+            .map_err(JeffError::from)?
+            .get(entry.id);
+        functions
+            .set_with_caveats(i as u32, source)
+            .map_err(JeffError::from)?;
+        remaps[entry.module].function(functions.reborrow().get(i as u32))?;
+    }
+
+    let mut table = root.init_strings(strings.len() as u32);
+    for (i, string) in strings.into_iter().enumerate() {
+        table.set(i as u32, string);
+    }
+
+    // Copy the result into a new message to drop the replaced lists.
+    let mut compact = capnp::message::Builder::new_default();
+    compact
+        .set_root(
+            message
+                .get_root_as_reader::<jeff_capnp::module::Reader<'_>>()
+                .map_err(JeffError::from)?,
+        )
+        .map_err(JeffError::from)?;
+    Ok(capnp::serialize::write_message_to_words(&compact))
+}
+
+/// Function selected for a name while linking modules.
+struct LinkedFunction {
+    /// Index of the module containing the function.
+    module: usize,
+    /// Index of the function in its module.
+    id: FunctionId,
+    /// Signature shared by every function with this name.
+    signature: FunctionType,
+    /// Whether the function is a definition.
+    is_definition: bool,
+}
+
+/// Returns the string index of the name of a function.
+fn function_name_idx(module: &Module<'_>, id: FunctionId) -> u16 {
+    module
+        .capnp_reader()
+        .get_functions()
+        .expect("Functions should be present")
+        .get(id)
+        .get_name()
+}
+
+/// Mapping from the old string and function indices of a module to new ones.
+struct IndexRemap {
+    /// New index of each string.
+    strings: Vec<u16>,
+    /// New index of each function, or `None` to keep function calls as they
+    /// are.
+    functions: Option<Vec<u16>>,
+}
+
+impl IndexRemap {
+    /// Returns the new index of the string at `idx`.
+    fn get(&self, idx: u16, context: &'static str) -> Result<u16, ReadError> {
+        self.strings
+            .get(idx as usize)
+            .copied()
+            .ok_or(ReadError::StringOutOfBounds {
+                context,
+                idx: idx as u32,
+                count: self.strings.len(),
+            })
+    }
+
+    /// Returns the new index of the function at `id`.
+    fn function_id(&self, id: u16) -> Result<u16, JeffError> {
+        let Some(functions) = &self.functions else {
+            return Ok(id);
+        };
+        functions.get(id as usize).copied().ok_or_else(|| {
+            JeffError::InvalidFile(capnp::Error::failed(format!(
+                "function index {id} is out of bounds, only {} functions are available",
+                functions.len()
+            )))
+        })
+    }
+
+    fn function(&self, mut function: jeff_capnp::function::Builder<'_>) -> Result<(), JeffError> {
+        let name = self.get(function.reborrow().get_name(), "function name")?;
+        function.set_name(name);
+        self.metadata(function.reborrow().get_metadata()?)?;
+        match function.which().map_err(capnp::Error::from)? {
+            jeff_capnp::function::Which::Definition(mut def) => {
+                if def.has_body() {
+                    self.region(def.reborrow().get_body()?)?;
+                }
+                self.values(def.get_values()?)?;
+            }
+            jeff_capnp::function::Which::Declaration(mut decl) => {
+                self.values(decl.reborrow().get_inputs()?)?;
+                self.values(decl.get_outputs()?)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn region(&self, mut region: jeff_capnp::region::Builder<'_>) -> Result<(), JeffError> {
+        self.metadata(region.reborrow().get_metadata()?)?;
+        let mut operations = region.get_operations()?;
+        for i in 0..operations.len() {
+            self.operation(operations.reborrow().get(i))?;
+        }
+        Ok(())
+    }
+
+    fn operation(&self, mut op: jeff_capnp::op::Builder<'_>) -> Result<(), JeffError> {
+        self.metadata(op.reborrow().get_metadata()?)?;
+        match op.get_instruction().which().map_err(capnp::Error::from)? {
+            jeff_capnp::op::instruction::Which::Qubit(qubit) => {
+                if let jeff_capnp::qubit_op::Which::Gate(gate) =
+                    qubit?.which().map_err(capnp::Error::from)?
+                {
+                    if let jeff_capnp::qubit_gate::Which::Custom(mut custom) =
+                        gate?.which().map_err(capnp::Error::from)?
+                    {
+                        let name = self.get(custom.reborrow().get_name(), "gate name")?;
+                        custom.set_name(name);
+                    }
+                }
+            }
+            jeff_capnp::op::instruction::Which::Scf(scf) => {
+                match scf?.which().map_err(capnp::Error::from)? {
+                    jeff_capnp::scf_op::Which::Switch(mut switch) => {
+                        let mut branches = switch.reborrow().get_branches()?;
+                        for i in 0..branches.len() {
+                            self.region(branches.reborrow().get(i))?;
+                        }
+                        if switch.has_default() {
+                            self.region(switch.get_default()?)?;
+                        }
+                    }
+                    jeff_capnp::scf_op::Which::For(region) => self.region(region?)?,
+                    jeff_capnp::scf_op::Which::While(mut while_op) => {
+                        if while_op.has_before() {
+                            self.region(while_op.reborrow().get_before()?)?;
+                        }
+                        if while_op.has_after() {
+                            self.region(while_op.get_after()?)?;
+                        }
+                    }
+                }
+            }
+            jeff_capnp::op::instruction::Which::Func(func) => {
+                let mut func = func?;
+                let id = self.function_id(func.reborrow().get_func_call())?;
+                func.set_func_call(id);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn values(
+        &self,
+        mut values: capnp::struct_list::Builder<'_, jeff_capnp::value::Owned>,
+    ) -> Result<(), JeffError> {
+        for i in 0..values.len() {
+            self.metadata(values.reborrow().get(i).get_metadata()?)?;
+        }
+        Ok(())
+    }
+
+    fn metadata(
+        &self,
+        mut metadata: capnp::struct_list::Builder<'_, jeff_capnp::meta::Owned>,
+    ) -> Result<(), JeffError> {
+        for i in 0..metadata.len() {
+            let mut meta = metadata.reborrow().get(i);
+            let name = self.get(meta.reborrow().get_name(), "metadata name")?;
+            meta.set_name(name);
+        }
+        Ok(())
+    }
+}
+
+/// Applies `f` to every metadata entry of `module`, a copy of `source`.
+///
+/// Entries are dropped when `f` returns `None`. Values that `f` leaves
+/// unchanged keep their original encoding, and other values are written as
+/// text. [`MetadataValue::Opaque`] replacements are written as empty values.
+///
+/// See [`Jeff::rewrite`](crate::Jeff::rewrite).
+pub(crate) fn rewrite_metadata<F>(
+    source: jeff_capnp::module::Reader<'_>,
+    mut module: jeff_capnp::module::Builder<'_>,
+    f: &mut F,
+) -> Result<(), JeffError>
+where
+    F: FnMut(MetaScope, &str, MetadataValue) -> Option<MetadataValue>,
+{
+    let mut rewriter = MetadataRewriter {
+        strings: StringTable::read_capnp(source.get_strings()?),
+        f,
+    };
+    if let Some(entries) = rewriter.entries(MetaScope::Module, source.get_metadata()?)? {
+        write_metadata(
+            module.reborrow().init_metadata(entries.len() as u32),
+            entries,
+        )?;
+    }
+
+    let mut functions = module.get_functions()?;
+    for (i, function) in source.get_functions()?.iter().enumerate() {
+        rewriter.function(function, functions.reborrow().get(i as u32))?;
+    }
+    Ok(())
+}
+
+/// A metadata entry kept by [`rewrite_metadata`], with its name index,
+/// original value, and replacement value if it changed.
+type MetadataEntry<'s> = (u16, capnp::any_pointer::Reader<'s>, Option<MetadataValue>);
+
+/// Walks a module reader and a copy of it in parallel, rewriting metadata.
+struct MetadataRewriter<'s, 'f, F> {
+    strings: StringTable<'s>,
+    f: &'f mut F,
+}
+
+impl<'s, F> MetadataRewriter<'s, '_, F>
+where
+    F: FnMut(MetaScope, &str, MetadataValue) -> Option<MetadataValue>,
+{
+    /// Applies the rewrite function to a list of metadata entries.
+    ///
+    /// Returns `None` if the list is left unchanged.
+    fn entries(
+        &mut self,
+        scope: MetaScope,
+        metadata: capnp::struct_list::Reader<'s, jeff_capnp::meta::Owned>,
+    ) -> Result<Option<Vec<MetadataEntry<'s>>>, JeffError> {
+        let mut entries = Vec::new();
+        for meta in metadata.iter() {
+            let parsed = Metadata::try_read_capnp(meta, self.strings)?;
+            let original = parsed.to_owned_value();
+            if let Some(value) = (self.f)(scope, parsed.name(), original.clone()) {
+                let changed = (value != original).then_some(value);
+                entries.push((meta.get_name(), meta.get_value(), changed));
+            }
+        }
+        let unchanged = entries.len() == metadata.len() as usize
+            && entries.iter().all(|(_, _, changed)| changed.is_none());
+        Ok((!unchanged).then_some(entries))
+    }
+
+    fn function(
+        &mut self,
+        source: jeff_capnp::function::Reader<'s>,
+        mut function: jeff_capnp::function::Builder<'_>,
+    ) -> Result<(), JeffError> {
+        if let Some(entries) = self.entries(MetaScope::Function, source.get_metadata()?)? {
+            write_metadata(
+                function.reborrow().init_metadata(entries.len() as u32),
+                entries,
+            )?;
+        }
+        match (
+            source.which().map_err(capnp::Error::from)?,
+            function.which().map_err(capnp::Error::from)?,
+        ) {
+            (
+                jeff_capnp::function::Which::Definition(source),
+                jeff_capnp::function::Which::Definition(mut def),
+            ) => {
+                if source.has_body() {
+                    self.region(source.get_body()?, def.reborrow().get_body()?)?;
+                }
+                self.values(source.get_values()?, def.get_values()?)?;
+            }
+            (
+                jeff_capnp::function::Which::Declaration(source),
+                jeff_capnp::function::Which::Declaration(mut decl),
+            ) => {
+                self.values(source.get_inputs()?, decl.reborrow().get_inputs()?)?;
+                self.values(source.get_outputs()?, decl.get_outputs()?)?;
+            }
+            _ => unreachable!("The module should be a copy of the source"),
+        }
+        Ok(())
+    }
+
+    fn region(
+        &mut self,
+        source: jeff_capnp::region::Reader<'s>,
+        mut region: jeff_capnp::region::Builder<'_>,
+    ) -> Result<(), JeffError> {
+        if let Some(entries) = self.entries(MetaScope::Region, source.get_metadata()?)? {
+            write_metadata(
+                region.reborrow().init_metadata(entries.len() as u32),
+                entries,
+            )?;
+        }
+        let mut operations = region.get_operations()?;
+        for (i, op) in source.get_operations()?.iter().enumerate() {
+            self.operation(op, operations.reborrow().get(i as u32))?;
+        }
+        Ok(())
+    }
+
+    fn operation(
+        &mut self,
+        source: jeff_capnp::op::Reader<'s>,
+        mut op: jeff_capnp::op::Builder<'_>,
+    ) -> Result<(), JeffError> {
+        if let Some(entries) = self.entries(MetaScope::Operation, source.get_metadata()?)? {
+            write_metadata(op.reborrow().init_metadata(entries.len() as u32), entries)?;
+        }
+        let (
+            jeff_capnp::op::instruction::Which::Scf(source),
+            jeff_capnp::op::instruction::Which::Scf(scf),
+        ) = (
+            source
+                .get_instruction()
+                .which()
+                .map_err(capnp::Error::from)?,
+            op.get_instruction().which().map_err(capnp::Error::from)?,
+        )
+        else {
+            return Ok(());
+        };
+        match (
+            source?.which().map_err(capnp::Error::from)?,
+            scf?.which().map_err(capnp::Error::from)?,
+        ) {
+            (
+                jeff_capnp::scf_op::Which::Switch(source),
+                jeff_capnp::scf_op::Which::Switch(mut switch),
+            ) => {
+                let mut branches = switch.reborrow().get_branches()?;
+                for (i, branch) in source.get_branches()?.iter().enumerate() {
+                    self.region(branch, branches.reborrow().get(i as u32))?;
+                }
+                if source.has_default() {
+                    self.region(source.get_default()?, switch.get_default()?)?;
+                }
+            }
+            (jeff_capnp::scf_op::Which::For(source), jeff_capnp::scf_op::Which::For(region)) => {
+                self.region(source?, region?)?;
+            }
+            (
+                jeff_capnp::scf_op::Which::While(source),
+                jeff_capnp::scf_op::Which::While(mut while_op),
+            ) => {
+                if source.has_before() {
+                    self.region(source.get_before()?, while_op.reborrow().get_before()?)?;
+                }
+                if source.has_after() {
+                    self.region(source.get_after()?, while_op.get_after()?)?;
+                }
+            }
+            _ => unreachable!("The module should be a copy of the source"),
+        }
+        Ok(())
+    }
+
+    fn values(
+        &mut self,
+        source: capnp::struct_list::Reader<'s, jeff_capnp::value::Owned>,
+        mut values: capnp::struct_list::Builder<'_, jeff_capnp::value::Owned>,
+    ) -> Result<(), JeffError> {
+        for (i, value) in source.iter().enumerate() {
+            if let Some(entries) = self.entries(MetaScope::Value, value.get_metadata()?)? {
+                let metadata = values
+                    .reborrow()
+                    .get(i as u32)
+                    .init_metadata(entries.len() as u32);
+                write_metadata(metadata, entries)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the entries kept by [`MetadataRewriter::entries`] into a new list.
+fn write_metadata(
+    mut metadata: capnp::struct_list::Builder<'_, jeff_capnp::meta::Owned>,
+    entries: Vec<MetadataEntry<'_>>,
+) -> Result<(), JeffError> {
+    for (i, (name, original, changed)) in entries.into_iter().enumerate() {
+        let mut meta = metadata.reborrow().get(i as u32);
+        meta.set_name(name);
+        match changed {
+            None => meta
+                .init_value()
+                .set_as::<capnp::any_pointer::Owned>(original)?,
+            Some(value) => {
+                if let Some(text) = value.to_text() {
+                    meta.init_value()
+                        .set_as::<capnp::text::Owned>(text.as_str())?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::optype::OpType;
+    use crate::reader::{Function, HasMetadata, ReadJeff};
+    use crate::test::{build_program, definition};
+    use crate::Jeff;
+
+    /// Builds a module whose string table repeats the gate and metadata names.
+    fn fill(mut module: jeff_capnp::module::Builder<'_>) {
+        let mut strings = module.reborrow().init_strings(5);
+        for (i, s) in ["main", "H", "H", "opt", "opt"].into_iter().enumerate() {
+            strings.set(i as u32, s);
+        }
+        module.reborrow().init_metadata(1).get(0).set_name(3);
+
+        let mut function = module.init_functions(1).get(0);
+        function.set_name(0);
+        let mut def = function.init_definition();
+        def.reborrow().init_values(3);
+        let mut ops = def.init_body().init_operations(3);
+        {
+            let mut op = ops.reborrow().get(0);
+            op.reborrow().init_outputs(1).set(0, 0);
+            op.init_instruction().init_qubit().set_alloc(());
+        }
+        for (i, name) in [(1, 1), (2, 2)] {
+            let mut op = ops.reborrow().get(i);
+            op.reborrow().init_inputs(1).set(0, i - 1);
+            op.reborrow().init_outputs(1).set(0, i);
+            op.reborrow().init_metadata(1).get(0).set_name(4);
+            let mut gate = op.init_instruction().init_qubit().init_gate();
+            gate.set_power(1);
+            let mut custom = gate.init_custom();
+            custom.set_name(name as u16);
+            custom.set_num_qubits(1);
+        }
+    }
+
+    /// Summarizes the string references of a program.
+    fn summary(program: &Jeff<'_>) -> Vec<String> {
+        let module = program.module();
+        let mut summary: Vec<String> = module
+            .metadata_entries()
+            .map(|m| m.name().to_string())
+            .collect();
+        let main = definition(program, 0);
+        summary.push(main.name().to_string());
+        for op in main.body().operations() {
+            if let OpType::QubitOp(qubit_op) = op.op_type() {
+                summary.push(qubit_op.to_string());
+            }
+            summary.extend(op.metadata_entries().map(|m| m.name().to_string()));
+        }
+        summary
+    }
+
+    /// Builds a module with the given function names, where each function is
+    /// either a declaration or a definition calling another function.
+    fn linkable(functions: &[(&str, Option<Option<u16>>)]) -> Jeff<'static> {
+        build_program(|mut module| {
+            let mut strings = module.reborrow().init_strings(functions.len() as u32);
+            for (i, (name, _)) in functions.iter().enumerate() {
+                strings.set(i as u32, *name);
+            }
+            let mut list = module.init_functions(functions.len() as u32);
+            for (i, (_, body)) in functions.iter().enumerate() {
+                let mut function = list.reborrow().get(i as u32);
+                function.set_name(i as u16);
+                match body {
+                    None => {
+                        function.init_declaration();
+                    }
+                    Some(callee) => {
+                        let body = function.init_definition().init_body();
+                        if let Some(callee) = callee {
+                            let op = body.init_operations(1).get(0);
+                            op.init_instruction().init_func().set_func_call(*callee);
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Returns the name of the function called by the first operation of
+    /// `function`, if any.
+    fn called_name(module: &crate::reader::Module<'_>, function: FunctionId) -> Option<String> {
+        let Function::Definition(def) = module.function(function) else {
+            return None;
+        };
+        let op = def.body().operations().next()?;
+        let OpType::FuncOp(call) = op.op_type() else {
+            return None;
+        };
+        Some(
+            module
+                .function(call.func_idx as FunctionId)
+                .name()
+                .to_string(),
+        )
+    }
+
+    #[test]
+    fn link() {
+        // `main` calls the declared `f`, which the second module defines.
+        let caller = linkable(&[("main", Some(Some(1))), ("f", None)]);
+        let callee = linkable(&[("helper", Some(Some(1))), ("f", Some(None))]);
+
+        let bytes = Jeff::link(&[caller.module(), callee.module()]).unwrap();
+        let linked = Jeff::read_slice(&mut bytes.as_slice()).unwrap();
+        let module = linked.module();
+        let names: Vec<_> = module.functions().map(|f| f.name().to_string()).collect();
+        assert_eq!(names, ["main", "f", "helper"]);
+        assert_eq!(module.strings().len(), 3);
+        assert_eq!(module.entrypoint_id(), 0);
+        assert!(matches!(module.function(1), Function::Definition(_)));
+        assert_eq!(called_name(&module, 0).as_deref(), Some("f"));
+        assert_eq!(called_name(&module, 2).as_deref(), Some("f"));
+
+        assert!(matches!(
+            Jeff::link(&[callee.module(), callee.module()]),
+            Err(LinkError::DuplicateDefinition { name }) if name == "helper"
+        ));
+        assert!(matches!(Jeff::link(&[]), Err(LinkError::NoModules)));
+    }
+
+    #[test]
+    fn link_signature_mismatch() {
+        let caller = linkable(&[("main", Some(Some(1))), ("f", None)]);
+        let callee = build_program(|mut module| {
+            module.reborrow().init_strings(1).set(0, "f");
+            let mut function = module.init_functions(1).get(0);
+            function.set_name(0);
+            let decl = function.init_declaration();
+            decl.init_inputs(1).get(0).init_type().set_qubit(());
+        });
+
+        assert!(matches!(
+            Jeff::link(&[caller.module(), callee.module()]),
+            Err(LinkError::SignatureMismatch { name, .. }) if name == "f"
+        ));
+    }
+
+    #[test]
+    fn dedup_strings() {
+        let original = build_program(fill);
+        let deduped = build_program(|mut module| {
+            fill(module.reborrow());
+            super::dedup_strings(module).unwrap();
+        });
+
+        assert_eq!(original.module().strings().len(), 5);
+        assert_eq!(deduped.module().strings().len(), 3);
+        assert_eq!(summary(&deduped), summary(&original));
+        assert!(original.diff(&deduped.module()).is_empty());
+    }
+}
@@ -1,11 +1,17 @@
 //! This module contains the Cap'n Proto encoding and decoding functions
 //! generated from the `.capn` schema.
 //!
-//! The `jeff_capnp.rs` file **SHOULD NOT** be edited manually.
-//! Instead, use `just update-capnp` to regenerate it.
+//! The `jeff_capnp.rs` and `analysis_capnp.rs` files **SHOULD NOT** be edited
+//! manually. Instead, use `just update-capnp` to regenerate them.
 
 #[allow(warnings)]
 #[allow(unknown_lints)]
 #[allow(clippy::all)]
 #[rustfmt::skip]
 pub mod jeff_capnp;
+
+#[allow(warnings)]
+#[allow(unknown_lints)]
+#[allow(clippy::all)]
+#[rustfmt::skip]
+pub mod analysis_capnp;
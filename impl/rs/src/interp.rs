@@ -0,0 +1,812 @@
+//! Evaluation of the classical parts of a jeff program.
+//!
+//! The interpreter executes integer and floating point operations, their array
+//! counterparts, and structured control flow. It does not simulate quantum
+//! operations, and does not follow calls to other functions.
+//!
+//! This module requires the `std` feature.
+
+use std::collections::BTreeMap;
+
+use derive_more::derive::{Display, Error, From};
+
+use crate::reader::optype::{ControlFlowOp, FloatArrayOp, FloatOp, IntArrayOp, IntOp, OpType};
+use crate::reader::{FunctionDefinition, FunctionId, Operation, ReadError, Region, ValueId};
+use crate::types::{FloatPrecision, Type};
+
+/// An owned classical value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClassicalValue {
+    /// An integer of the given bitwidth.
+    ///
+    /// The value is stored zero-extended, with the bits above `bits` cleared.
+    Int {
+        /// Bitwidth of the integer.
+        bits: u8,
+        /// The value of the integer.
+        value: u64,
+    },
+    /// An array of integers of the given bitwidth.
+    IntArray {
+        /// Bitwidth of the integers.
+        bits: u8,
+        /// The values of the integers, stored as in [`ClassicalValue::Int`].
+        values: Vec<u64>,
+    },
+    /// A floating point number.
+    ///
+    /// Values of [`FloatPrecision::Float32`] are stored widened to `f64`.
+    Float {
+        /// Precision of the number.
+        precision: FloatPrecision,
+        /// The value of the number.
+        value: f64,
+    },
+    /// An array of floating point numbers.
+    FloatArray {
+        /// Precision of the numbers.
+        precision: FloatPrecision,
+        /// The values of the numbers, stored as in [`ClassicalValue::Float`].
+        values: Vec<f64>,
+    },
+}
+
+impl ClassicalValue {
+    /// Create a new integer value, truncating `value` to `bits` bits.
+    pub fn int(bits: u8, value: u64) -> Self {
+        Self::Int {
+            bits,
+            value: truncate(bits, value),
+        }
+    }
+
+    /// Create a new floating point value, rounding `value` to `precision`.
+    pub fn float(precision: FloatPrecision, value: f64) -> Self {
+        Self::Float {
+            precision,
+            value: round(precision, value),
+        }
+    }
+
+    /// Returns `true` if the value can be passed where a value of type `ty`
+    /// is expected.
+    ///
+    /// Arrays match array types of any length.
+    pub fn has_type(&self, ty: &Type) -> bool {
+        match (self, ty) {
+            (Self::Int { bits, .. }, Type::Int { bits: ty_bits })
+            | (Self::IntArray { bits, .. }, Type::IntArray { bits: ty_bits, .. }) => {
+                bits == ty_bits
+            }
+            (
+                Self::Float { precision, .. },
+                Type::Float {
+                    precision: ty_precision,
+                },
+            )
+            | (
+                Self::FloatArray { precision, .. },
+                Type::FloatArray {
+                    precision: ty_precision,
+                    ..
+                },
+            ) => precision == ty_precision,
+            _ => false,
+        }
+    }
+}
+
+/// Maximum length of the arrays created by the interpreter.
+///
+/// Zero-initialized arrays with a longer length are rejected with
+/// [`InterpError::ArrayTooLarge`] instead of being allocated.
+pub const MAX_ARRAY_LENGTH: usize = 1 << 24;
+
+/// Errors that can occur when evaluating a jeff function.
+#[derive(Debug, Display, From, Error)]
+#[non_exhaustive]
+pub enum InterpError {
+    /// Error while reading the program.
+    #[from]
+    ReadError(ReadError),
+    /// The function contains a quantum operation.
+    #[display("Cannot evaluate quantum operations")]
+    QuantumOp,
+    /// The function calls another function.
+    #[display("Cannot evaluate the call to function {func}")]
+    Call {
+        /// The called function.
+        func: FunctionId,
+    },
+    /// The wrong number of arguments was passed to the function.
+    #[display("Expected {expected} arguments, but found {found}")]
+    WrongArgumentCount {
+        /// The number of function inputs.
+        expected: usize,
+        /// The number of arguments passed.
+        found: usize,
+    },
+    /// An argument does not match the type of the function input.
+    #[display("Argument {idx} does not match the function input type")]
+    InvalidArgument {
+        /// The position of the offending argument.
+        idx: usize,
+    },
+    /// An operation consumed a value that has not been computed.
+    #[display("Value {value} is used before it is defined")]
+    UndefinedValue {
+        /// The undefined value.
+        value: ValueId,
+    },
+    /// An operation received inputs of the wrong number or kind.
+    #[display("Invalid inputs for {operation} operation")]
+    InvalidInputs {
+        /// The name of the operation.
+        operation: &'static str,
+    },
+    /// An operation is undefined for its inputs, e.g. a division by zero or a
+    /// loop with a zero step.
+    #[display("The {operation} operation is undefined for its inputs")]
+    Undefined {
+        /// The name of the operation.
+        operation: &'static str,
+    },
+    /// An array was indexed out of bounds.
+    #[display("Index {idx} is out of bounds for an array of length {len}")]
+    IndexOutOfBounds {
+        /// The requested index.
+        idx: u64,
+        /// The length of the array.
+        len: usize,
+    },
+    /// An array was created with a length above [`MAX_ARRAY_LENGTH`].
+    #[display("Array length {len} exceeds the limit of {max} elements")]
+    ArrayTooLarge {
+        /// The requested length.
+        len: u64,
+        /// The maximum allowed length.
+        max: usize,
+    },
+    /// A switch selector does not match any branch, and there is no default
+    /// branch.
+    #[display("Switch selector {selector} does not match any branch")]
+    NoMatchingBranch {
+        /// The value of the selector.
+        selector: u64,
+    },
+}
+
+/// Evaluates a function definition on the given arguments, and returns its
+/// outputs.
+///
+/// Integer arithmetic wraps on overflow, and results are truncated to the
+/// bitwidth of the inputs. Signed operations interpret their inputs as two's
+/// complement.
+///
+/// # Errors
+///
+/// - [`InterpError::WrongArgumentCount`] or [`InterpError::InvalidArgument`]
+///   if the arguments do not match the function inputs.
+/// - [`InterpError::QuantumOp`] if a quantum operation is encountered.
+/// - [`InterpError::Call`] if a function call is encountered.
+/// - Any other [`InterpError`] if an operation cannot be evaluated.
+pub fn eval_classical(
+    function: &FunctionDefinition<'_>,
+    args: &[ClassicalValue],
+) -> Result<Vec<ClassicalValue>, InterpError> {
+    let body = function.body();
+    if args.len() != body.source_count() {
+        return Err(InterpError::WrongArgumentCount {
+            expected: body.source_count(),
+            found: args.len(),
+        });
+    }
+    for (idx, (arg, source)) in args.iter().zip(body.sources()).enumerate() {
+        if !arg.has_type(&source?.ty()) {
+            return Err(InterpError::InvalidArgument { idx });
+        }
+    }
+
+    Interpreter::default().eval_region(body, args.to_vec())
+}
+
+/// Values computed so far in a function.
+#[derive(Default)]
+struct Interpreter {
+    values: BTreeMap<ValueId, ClassicalValue>,
+}
+
+impl Interpreter {
+    /// Evaluates a region with the given sources, and returns its targets.
+    fn eval_region(
+        &mut self,
+        region: Region<'_>,
+        args: Vec<ClassicalValue>,
+    ) -> Result<Vec<ClassicalValue>, InterpError> {
+        if args.len() != region.source_count() {
+            return Err(InterpError::InvalidInputs {
+                operation: "region",
+            });
+        }
+        for (source, arg) in region.sources().zip(args) {
+            self.values.insert(source?.id(), arg);
+        }
+        for op in region.operations() {
+            self.eval_op(&op)?;
+        }
+        region
+            .targets()
+            .map(|target| self.get(target?.id()))
+            .collect()
+    }
+
+    fn get(&self, value: ValueId) -> Result<ClassicalValue, InterpError> {
+        self.values
+            .get(&value)
+            .cloned()
+            .ok_or(InterpError::UndefinedValue { value })
+    }
+
+    fn eval_op(&mut self, op: &Operation<'_>) -> Result<(), InterpError> {
+        let inputs = op
+            .inputs()
+            .map(|input| self.get(input?.id()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let outputs = match op.op_type() {
+            OpType::QubitOp(_) | OpType::QubitRegisterOp(_) => {
+                return Err(InterpError::QuantumOp);
+            }
+            OpType::FuncOp(func_op) => {
+                return Err(InterpError::Call {
+                    func: func_op.func_idx as FunctionId,
+                });
+            }
+            OpType::IntOp(int_op) => vec![eval_int(int_op, &inputs)?],
+            OpType::FloatOp(float_op) => vec![eval_float(float_op, &inputs)?],
+            OpType::IntArrayOp(array_op) => vec![eval_int_array(op, array_op, inputs)?],
+            OpType::FloatArrayOp(array_op) => vec![eval_float_array(op, array_op, inputs)?],
+            OpType::ControlFlowOp(cf_op) => self.eval_control_flow(&cf_op, inputs)?,
+        };
+
+        if outputs.len() != op.output_count() {
+            return Err(InterpError::InvalidInputs {
+                operation: "output",
+            });
+        }
+        for (output, value) in op.outputs().zip(outputs) {
+            self.values.insert(output?.id(), value);
+        }
+        Ok(())
+    }
+
+    fn eval_control_flow(
+        &mut self,
+        cf_op: &ControlFlowOp<'_>,
+        inputs: Vec<ClassicalValue>,
+    ) -> Result<Vec<ClassicalValue>, InterpError> {
+        match cf_op {
+            ControlFlowOp::For { region } => {
+                const OPERATION: &str = "for";
+                let bound = |n: usize| inputs.get(n).and_then(int_input);
+                let (Some((bits, start)), Some(stop), Some(step)) = (bound(0), bound(1), bound(2))
+                else {
+                    return Err(InterpError::InvalidInputs {
+                        operation: OPERATION,
+                    });
+                };
+                let start = sign_extend(bits, start);
+                let stop = sign_extend(stop.0, stop.1);
+                let step = sign_extend(step.0, step.1);
+                if step == 0 {
+                    return Err(InterpError::Undefined {
+                        operation: OPERATION,
+                    });
+                }
+
+                let mut state = inputs[3..].to_vec();
+                let mut i = start;
+                while (step > 0 && i < stop) || (step < 0 && i > stop) {
+                    let mut args = vec![ClassicalValue::int(bits, i as u64)];
+                    args.extend(state);
+                    state = self.eval_region(*region, args)?;
+                    let Some(next) = i.checked_add(step) else {
+                        break;
+                    };
+                    i = next;
+                }
+                Ok(state)
+            }
+            ControlFlowOp::While { before, after } => {
+                let mut state = inputs;
+                loop {
+                    let mut outputs = self.eval_region(*before, state)?;
+                    let condition = match outputs.first() {
+                        Some(ClassicalValue::Int { bits: 1, value }) => *value != 0,
+                        _ => {
+                            return Err(InterpError::InvalidInputs { operation: "while" });
+                        }
+                    };
+                    outputs.remove(0);
+                    if !condition {
+                        return Ok(outputs);
+                    }
+                    state = self.eval_region(*after, outputs)?;
+                }
+            }
+            ControlFlowOp::Switch(switch_op) => {
+                let Some((_, selector)) = inputs.first().and_then(int_input) else {
+                    return Err(InterpError::InvalidInputs {
+                        operation: "switch",
+                    });
+                };
+                let branch = usize::try_from(selector)
+                    .ok()
+                    .and_then(|idx| switch_op.branches().nth(idx))
+                    .or_else(|| switch_op.default_branch())
+                    .ok_or(InterpError::NoMatchingBranch { selector })?;
+                self.eval_region(branch, inputs[1..].to_vec())
+            }
+        }
+    }
+}
+
+/// Truncates `value` to its lowest `bits` bits.
+fn truncate(bits: u8, value: u64) -> u64 {
+    if bits >= 64 {
+        value
+    } else {
+        value & ((1 << bits) - 1)
+    }
+}
+
+/// Interprets the lowest `bits` bits of `value` as a two's complement integer.
+fn sign_extend(bits: u8, value: u64) -> i64 {
+    if bits == 0 || bits >= 64 {
+        value as i64
+    } else {
+        let shift = 64 - bits as u32;
+        ((value << shift) as i64) >> shift
+    }
+}
+
+/// Rounds `value` to the given precision.
+fn round(precision: FloatPrecision, value: f64) -> f64 {
+    match precision {
+        FloatPrecision::Float32 => value as f32 as f64,
+        FloatPrecision::Float64 => value,
+    }
+}
+
+fn int_input(value: &ClassicalValue) -> Option<(u8, u64)> {
+    match *value {
+        ClassicalValue::Int { bits, value } => Some((bits, value)),
+        _ => None,
+    }
+}
+
+fn float_input(value: &ClassicalValue) -> Option<(FloatPrecision, f64)> {
+    match *value {
+        ClassicalValue::Float { precision, value } => Some((precision, value)),
+        _ => None,
+    }
+}
+
+/// Reads an array index from an integer input.
+fn index_input(value: &ClassicalValue, len: usize) -> Result<usize, InterpError> {
+    let (_, idx) = int_input(value).ok_or(InterpError::InvalidInputs {
+        operation: "array index",
+    })?;
+    usize::try_from(idx)
+        .ok()
+        .filter(|&i| i < len)
+        .ok_or(InterpError::IndexOutOfBounds { idx, len })
+}
+
+/// Reads an array length from an integer input, up to [`MAX_ARRAY_LENGTH`].
+fn length_input(value: &ClassicalValue) -> Result<usize, InterpError> {
+    let (_, len) = int_input(value).ok_or(InterpError::InvalidInputs {
+        operation: "array length",
+    })?;
+    usize::try_from(len)
+        .ok()
+        .filter(|&len| len <= MAX_ARRAY_LENGTH)
+        .ok_or(InterpError::ArrayTooLarge {
+            len,
+            max: MAX_ARRAY_LENGTH,
+        })
+}
+
+/// Returns the type of the `n`-th output of an operation.
+fn output_type(op: &Operation<'_>, n: usize) -> Result<Type, InterpError> {
+    match op.output(n) {
+        Some(output) => Ok(output?.ty()),
+        None => Err(InterpError::InvalidInputs {
+            operation: "output",
+        }),
+    }
+}
+
+fn eval_int(op: IntOp, inputs: &[ClassicalValue]) -> Result<ClassicalValue, InterpError> {
+    const OPERATION: &str = "integer";
+    if let (Some(bits), Some(value)) = (op.const_width(), op.const_value_u64()) {
+        return Ok(ClassicalValue::int(bits, value));
+    }
+    let invalid = || InterpError::InvalidInputs {
+        operation: OPERATION,
+    };
+    let undefined = || InterpError::Undefined {
+        operation: OPERATION,
+    };
+
+    match *inputs {
+        [ClassicalValue::Int { bits, value }] => {
+            let x = sign_extend(bits, value);
+            let res = op.eval_unary(x).ok_or_else(invalid)?;
+            Ok(ClassicalValue::int(bits, res as u64))
+        }
+        [ClassicalValue::Int { bits, value: a }, ClassicalValue::Int {
+            bits: b_bits,
+            value: b,
+        }] => {
+            if bits != b_bits {
+                return Err(invalid());
+            }
+            let (sa, sb) = (sign_extend(bits, a), sign_extend(bits, b));
+            let compare = |res: bool| Ok(ClassicalValue::int(1, res as u64));
+            match op {
+                IntOp::Eq => return compare(a == b),
+                IntOp::LtS => return compare(sa < sb),
+                IntOp::LteS => return compare(sa <= sb),
+                IntOp::LtU => return compare(a < b),
+                IntOp::LteU => return compare(a <= b),
+                _ => {}
+            }
+            // Signed operations see sign-extended inputs, all other operations
+            // see the zero-extended inputs.
+            let (a, b) = if matches!(op, IntOp::DivS | IntOp::RemS | IntOp::MinS | IntOp::MaxS) {
+                (sa, sb)
+            } else {
+                (a as i64, b as i64)
+            };
+            // Shifting by the full bitwidth or more is undefined.
+            if matches!(op, IntOp::Shl | IntOp::Shr) && b as u64 >= bits as u64 {
+                return Err(undefined());
+            }
+            match op.eval_binary(a, b) {
+                Some(res) => Ok(ClassicalValue::int(bits, res as u64)),
+                None if matches!(
+                    op,
+                    IntOp::DivS | IntOp::DivU | IntOp::RemS | IntOp::RemU | IntOp::Pow
+                ) =>
+                {
+                    Err(undefined())
+                }
+                None => Err(invalid()),
+            }
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn eval_float(op: FloatOp, inputs: &[ClassicalValue]) -> Result<ClassicalValue, InterpError> {
+    if let Some((precision, value)) = op.const_with_precision() {
+        return Ok(ClassicalValue::float(precision, value));
+    }
+    let invalid = || InterpError::InvalidInputs { operation: "float" };
+
+    match inputs {
+        [x] => {
+            let (precision, x) = float_input(x).ok_or_else(invalid)?;
+            let predicate = |res: bool| Ok(ClassicalValue::int(1, res as u64));
+            match op {
+                FloatOp::IsNan => predicate(x.is_nan()),
+                FloatOp::IsInf => predicate(x.is_infinite()),
+                _ => {
+                    let res = op.eval_unary(x).ok_or_else(invalid)?;
+                    Ok(ClassicalValue::float(precision, res))
+                }
+            }
+        }
+        [a, b] => {
+            let ((precision, a), (b_precision, b)) =
+                float_input(a).zip(float_input(b)).ok_or_else(invalid)?;
+            if precision != b_precision {
+                return Err(invalid());
+            }
+            let compare = |res: bool| Ok(ClassicalValue::int(1, res as u64));
+            match op {
+                FloatOp::Eq => compare(a == b),
+                FloatOp::Lt => compare(a < b),
+                FloatOp::Lte => compare(a <= b),
+                _ => {
+                    let res = op.eval_binary(a, b).ok_or_else(invalid)?;
+                    Ok(ClassicalValue::float(precision, res))
+                }
+            }
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn eval_int_array(
+    op: &Operation<'_>,
+    array_op: IntArrayOp<'_>,
+    mut inputs: Vec<ClassicalValue>,
+) -> Result<ClassicalValue, InterpError> {
+    let invalid = || InterpError::InvalidInputs {
+        operation: "integer array",
+    };
+
+    if let Some(values) = array_op.widen_to_u128() {
+        let bits = match array_op {
+            IntArrayOp::ConstArray1(_) => 1,
+            IntArrayOp::ConstArray8(_) => 8,
+            IntArrayOp::ConstArray16(_) => 16,
+            IntArrayOp::ConstArray32(_) => 32,
+            _ => 64,
+        };
+        let values = values.into_iter().map(|v| v as u64).collect();
+        return Ok(ClassicalValue::IntArray { bits, values });
+    }
+
+    match (array_op, inputs.as_mut_slice()) {
+        (IntArrayOp::Zero { bits }, [len]) => Ok(ClassicalValue::IntArray {
+            bits,
+            values: vec![0; length_input(len)?],
+        }),
+        (IntArrayOp::GetIndex, [ClassicalValue::IntArray { bits, values }, idx]) => {
+            let idx = index_input(idx, values.len())?;
+            Ok(ClassicalValue::int(*bits, values[idx]))
+        }
+        (
+            IntArrayOp::SetIndex,
+            [ClassicalValue::IntArray { bits, values }, idx, ClassicalValue::Int {
+                bits: value_bits,
+                value,
+            }],
+        ) if bits == value_bits => {
+            let idx = index_input(idx, values.len())?;
+            values[idx] = *value;
+            Ok(inputs.swap_remove(0))
+        }
+        (IntArrayOp::Length, [ClassicalValue::IntArray { values, .. }]) => {
+            Ok(ClassicalValue::int(32, values.len() as u64))
+        }
+        (IntArrayOp::Create, _) => {
+            let Type::IntArray { bits, .. } = output_type(op, 0)? else {
+                return Err(invalid());
+            };
+            let values = inputs
+                .iter()
+                .map(|input| match int_input(input) {
+                    Some((value_bits, value)) if value_bits == bits => Ok(value),
+                    _ => Err(invalid()),
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(ClassicalValue::IntArray { bits, values })
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn eval_float_array(
+    op: &Operation<'_>,
+    array_op: FloatArrayOp<'_>,
+    mut inputs: Vec<ClassicalValue>,
+) -> Result<ClassicalValue, InterpError> {
+    let invalid = || InterpError::InvalidInputs {
+        operation: "float array",
+    };
+
+    match (array_op, inputs.as_mut_slice()) {
+        (FloatArrayOp::Const32(array), []) => Ok(ClassicalValue::FloatArray {
+            precision: FloatPrecision::Float32,
+            values: array.values().map(|v| v as f64).collect(),
+        }),
+        (FloatArrayOp::Const64(array), []) => Ok(ClassicalValue::FloatArray {
+            precision: FloatPrecision::Float64,
+            values: array.values().collect(),
+        }),
+        (FloatArrayOp::Zero { precision }, [len]) => Ok(ClassicalValue::FloatArray {
+            precision,
+            values: vec![0.0; length_input(len)?],
+        }),
+        (FloatArrayOp::GetIndex, [ClassicalValue::FloatArray { precision, values }, idx]) => {
+            let idx = index_input(idx, values.len())?;
+            Ok(ClassicalValue::float(*precision, values[idx]))
+        }
+        (
+            FloatArrayOp::SetIndex,
+            [ClassicalValue::FloatArray { precision, values }, idx, ClassicalValue::Float {
+                precision: value_precision,
+                value,
+            }],
+        ) if precision == value_precision => {
+            let idx = index_input(idx, values.len())?;
+            values[idx] = *value;
+            Ok(inputs.swap_remove(0))
+        }
+        (FloatArrayOp::Length, [ClassicalValue::FloatArray { values, .. }]) => {
+            Ok(ClassicalValue::int(32, values.len() as u64))
+        }
+        (FloatArrayOp::Create, _) => {
+            let Type::FloatArray { precision, .. } = output_type(op, 0)? else {
+                return Err(invalid());
+            };
+            let values = inputs
+                .iter()
+                .map(|input| match float_input(input) {
+                    Some((value_precision, value)) if value_precision == precision => Ok(value),
+                    _ => Err(invalid()),
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(ClassicalValue::FloatArray { precision, values })
+        }
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::jeff_capnp;
+    use crate::test::{build_program, definition};
+    use crate::Jeff;
+
+    /// Builds a program with a single function whose values are all 32 bit
+    /// integers.
+    fn int32_program(
+        value_count: u32,
+        init: impl FnOnce(jeff_capnp::region::Builder<'_>),
+    ) -> Jeff<'static> {
+        build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(value_count);
+            for i in 0..value_count {
+                values.reborrow().get(i).init_type().set_int(32);
+            }
+            init(def.init_body());
+        })
+    }
+
+    fn eval(program: &Jeff<'_>, args: &[u64]) -> Result<Vec<ClassicalValue>, InterpError> {
+        let main = definition(program, 0);
+        let args: Vec<_> = args.iter().map(|&v| ClassicalValue::int(32, v)).collect();
+        eval_classical(&main, &args)
+    }
+
+    #[test]
+    fn int_arithmetic() {
+        // f(a, b) = a * b - 3
+        let program = int32_program(5, |mut body| {
+            let mut sources = body.reborrow().init_sources(2);
+            sources.set(0, 0);
+            sources.set(1, 1);
+            body.reborrow().init_targets(1).set(0, 4);
+
+            let mut ops = body.init_operations(3);
+            let mut mul = ops.reborrow().get(0);
+            let mut inputs = mul.reborrow().init_inputs(2);
+            inputs.set(0, 0);
+            inputs.set(1, 1);
+            mul.reborrow().init_outputs(1).set(0, 2);
+            mul.init_instruction().init_int().set_mul(());
+
+            let mut three = ops.reborrow().get(1);
+            three.reborrow().init_outputs(1).set(0, 3);
+            three.init_instruction().init_int().set_const32(3);
+
+            let mut sub = ops.get(2);
+            let mut inputs = sub.reborrow().init_inputs(2);
+            inputs.set(0, 2);
+            inputs.set(1, 3);
+            sub.reborrow().init_outputs(1).set(0, 4);
+            sub.init_instruction().init_int().set_sub(());
+        });
+
+        assert_eq!(
+            eval(&program, &[6, 7]).unwrap(),
+            [ClassicalValue::int(32, 39)]
+        );
+        // Results wrap around at the bitwidth of the values.
+        assert_eq!(
+            eval(&program, &[0, 0]).unwrap(),
+            [ClassicalValue::int(32, (-3i32) as u32 as u64)]
+        );
+        assert!(matches!(
+            eval(&program, &[1]),
+            Err(InterpError::WrongArgumentCount {
+                expected: 2,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn for_loop_sum() {
+        // f(n) = sum(0..n)
+        let program = int32_program(7, |mut body| {
+            body.reborrow().init_sources(1).set(0, 0);
+            body.reborrow().init_targets(1).set(0, 6);
+
+            let mut ops = body.init_operations(3);
+            for (i, (output, value)) in [(1, 0), (2, 1)].into_iter().enumerate() {
+                let mut op = ops.reborrow().get(i as u32);
+                op.reborrow().init_outputs(1).set(0, output);
+                op.init_instruction().init_int().set_const32(value);
+            }
+
+            // for i in 0..n step 1, with the accumulator starting at 0.
+            let mut for_op = ops.get(2);
+            let mut inputs = for_op.reborrow().init_inputs(4);
+            for (i, value) in [1, 0, 2, 1].into_iter().enumerate() {
+                inputs.set(i as u32, value);
+            }
+            for_op.reborrow().init_outputs(1).set(0, 6);
+            let mut region = for_op.init_instruction().init_scf().init_for();
+            let mut sources = region.reborrow().init_sources(2);
+            sources.set(0, 3);
+            sources.set(1, 4);
+            region.reborrow().init_targets(1).set(0, 5);
+            let mut add = region.init_operations(1).get(0);
+            let mut inputs = add.reborrow().init_inputs(2);
+            inputs.set(0, 3);
+            inputs.set(1, 4);
+            add.reborrow().init_outputs(1).set(0, 5);
+            add.init_instruction().init_int().set_add(());
+        });
+
+        assert_eq!(eval(&program, &[5]).unwrap(), [ClassicalValue::int(32, 10)]);
+        assert_eq!(eval(&program, &[0]).unwrap(), [ClassicalValue::int(32, 0)]);
+    }
+
+    #[test]
+    fn zero_array_length_limit() {
+        // f(n) = zero(n)
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(2);
+            values.reborrow().get(0).init_type().set_int(32);
+            values.get(1).init_type().init_int_array().set_bitwidth(32);
+            let mut body = def.init_body();
+            body.reborrow().init_sources(1).set(0, 0);
+            body.reborrow().init_targets(1).set(0, 1);
+            let mut zero = body.init_operations(1).get(0);
+            zero.reborrow().init_inputs(1).set(0, 0);
+            zero.reborrow().init_outputs(1).set(0, 1);
+            zero.init_instruction().init_int_array().set_zero(32);
+        });
+
+        assert_eq!(
+            eval(&program, &[3]).unwrap(),
+            [ClassicalValue::IntArray {
+                bits: 32,
+                values: vec![0; 3]
+            }]
+        );
+        let len = MAX_ARRAY_LENGTH as u64 + 1;
+        assert!(matches!(
+            eval(&program, &[len]),
+            Err(InterpError::ArrayTooLarge { len: found, max: MAX_ARRAY_LENGTH }) if found == len
+        ));
+    }
+
+    #[test]
+    fn quantum_op() {
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            def.reborrow()
+                .init_values(1)
+                .get(0)
+                .init_type()
+                .set_qubit(());
+            let mut op = def.init_body().init_operations(1).get(0);
+            op.reborrow().init_outputs(1).set(0, 0);
+            op.init_instruction().init_qubit().set_alloc(());
+        });
+        assert!(matches!(eval(&program, &[]), Err(InterpError::QuantumOp)));
+    }
+}
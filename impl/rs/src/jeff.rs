@@ -4,12 +4,18 @@
 //! interface to load and store jeff files, converting old versions to the
 //! current one as needed.
 
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
 use capnp::message::TypedReader;
-use capnp::serialize::{BufferSegments, OwnedSegments};
+use capnp::serialize::BufferSegments;
+use capnp::serialize::OwnedSegments;
 
 use crate::capnp::jeff_capnp;
-use crate::reader::{Module, ReadJeff};
-use crate::JeffError;
+use crate::reader::{Function, MetaScope, MetadataValue, Module, ModuleDiff, ReadError, ReadJeff};
+use crate::{JeffError, LinkError};
 
 /// Copy-on-write representation of jeff programs.
 ///
@@ -26,9 +32,17 @@ pub struct Jeff<'a> {
 /// encoded in an owned buffer.
 enum JeffCow<'a> {
     /// A borrowed jeff program.
-    Borrowed(TypedReader<BufferSegments<&'a [u8]>, jeff_capnp::module::Owned>),
+    Borrowed {
+        /// Reader over the borrowed message.
+        reader: TypedReader<BufferSegments<&'a [u8]>, jeff_capnp::module::Owned>,
+        /// The encoded message, including its segment table.
+        bytes: &'a [u8],
+    },
     /// An owned jeff program.
     Owned(TypedReader<OwnedSegments, jeff_capnp::module::Owned>),
+    /// A jeff program backed by a memory-mapped file.
+    #[cfg(feature = "mmap")]
+    Mapped(TypedReader<BufferSegments<memmap2::Mmap>, jeff_capnp::module::Owned>),
 }
 
 impl<'a> Jeff<'a> {
@@ -47,7 +61,10 @@ impl<'a> Jeff<'a> {
     ///
     /// The data is not copied, but the buffer must outlive the jeff object.
     /// After this call, the slice will be advanced to the end of the jeff data.
+    ///
+    /// The slice must be 8-byte aligned.
     pub fn read_slice(slice: &mut &'a [u8]) -> Result<Self, JeffError> {
+        let input: &'a [u8] = slice;
         let reader = capnp::serialize::read_message_from_flat_slice(
             slice,
             capnp::message::ReaderOptions::new(),
@@ -57,9 +74,14 @@ impl<'a> Jeff<'a> {
         // Ensure the root type is correct.
         module.get()?;
 
+        let bytes = &input[..input.len() - slice.len()];
         let slf = Self {
-            module: JeffCow::Borrowed(module),
+            module: JeffCow::Borrowed {
+                reader: module,
+                bytes,
+            },
         };
+        slf.check_structure()?;
         slf.check_version()?;
         Ok(slf)
     }
@@ -70,8 +92,64 @@ impl<'a> Jeff<'a> {
     /// For a zero-copy version, use [`Jeff::read_slice`].
     ///
     /// For optimal performance, `reader` should be a buffered reader type.
+    #[cfg(feature = "std")]
     pub fn read(reader: impl std::io::Read) -> Result<Self, JeffError> {
         let reader = capnp::serialize::read_message(reader, capnp::message::ReaderOptions::new())?;
+        Self::from_message(reader)
+    }
+
+    /// Load a jeff program from a reader, rejecting messages larger than
+    /// `max_bytes`.
+    ///
+    /// The size declared in the message's segment table is checked before the
+    /// segments are read, so oversized inputs are rejected without allocating
+    /// their contents. Once read, the program is traversed with the same
+    /// limits as [`Jeff::read`].
+    ///
+    /// # Errors
+    ///
+    /// - [`JeffError::TooLarge`] if the message is larger than `max_bytes`.
+    /// - [`JeffError::InvalidFile`] if the data is not a valid jeff program.
+    #[cfg(feature = "std")]
+    pub fn read_with_limit(
+        mut reader: impl std::io::Read,
+        max_bytes: u64,
+    ) -> Result<Self, JeffError> {
+        let too_large = |size| JeffError::TooLarge { size, max_bytes };
+        let read_exact = |reader: &mut dyn std::io::Read, buf: &mut [u8]| {
+            reader
+                .read_exact(buf)
+                .map_err(|e| JeffError::InvalidFile(e.into()))
+        };
+
+        // The segment table holds the number of segments minus one, followed
+        // by the size in words of each segment, padded to a whole word.
+        let mut header = vec![0; 4];
+        read_exact(&mut reader, &mut header)?;
+        let segment_count = u32::from_le_bytes(header[..4].try_into().unwrap()) as u64 + 1;
+        let header_len = (4 + 4 * segment_count).next_multiple_of(8);
+        if header_len > max_bytes {
+            return Err(too_large(header_len));
+        }
+        header.resize(header_len as usize, 0);
+        read_exact(&mut reader, &mut header[4..])?;
+        let size = header[4..4 + 4 * segment_count as usize]
+            .chunks_exact(4)
+            .map(|words| u32::from_le_bytes(words.try_into().unwrap()) as u64 * 8)
+            .sum::<u64>()
+            + header_len;
+        if size > max_bytes {
+            return Err(too_large(size));
+        }
+
+        Self::read(std::io::Read::chain(header.as_slice(), reader))
+    }
+
+    /// Wrap an owned capnp message, checking its root type and version.
+    #[cfg(any(feature = "std", test))]
+    pub(crate) fn from_message(
+        reader: capnp::message::Reader<OwnedSegments>,
+    ) -> Result<Self, JeffError> {
         let module = reader.into_typed::<jeff_capnp::module::Owned>();
 
         // Ensure the root type is correct.
@@ -80,10 +158,193 @@ impl<'a> Jeff<'a> {
         let slf = Self {
             module: JeffCow::Owned(module),
         };
+        slf.check_structure()?;
         slf.check_version()?;
         Ok(slf)
     }
 
+    /// Load a jeff program from a memory-mapped file.
+    ///
+    /// The file contents are not copied; the returned object owns the mapping
+    /// and reads directly from it.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while the returned object is
+    /// alive, whether by this process or another one. Doing so is undefined
+    /// behaviour, and will typically result in a `SIGBUS` when reading past the
+    /// new end of a truncated file.
+    ///
+    /// # Errors
+    ///
+    /// - [`JeffError::Io`] if the file cannot be opened or mapped.
+    /// - [`JeffError::InvalidFile`] if the file is not a valid jeff program.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn read_mmap(path: impl AsRef<std::path::Path>) -> Result<Jeff<'static>, JeffError> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: Upheld by the caller. Mappings are page-aligned, which
+        // satisfies the 8-byte alignment required by `BufferSegments`.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+
+        let options = capnp::message::ReaderOptions::new();
+        let segments = BufferSegments::new(mmap, options)?;
+        let module = capnp::message::Reader::new(segments, options)
+            .into_typed::<jeff_capnp::module::Owned>();
+
+        // Ensure the root type is correct.
+        module.get()?;
+
+        let slf = Jeff {
+            module: JeffCow::Mapped(module),
+        };
+        slf.check_structure()?;
+        slf.check_version()?;
+        Ok(slf)
+    }
+
+    /// Returns the encoded jeff message, as read from the input.
+    ///
+    /// For programs loaded with [`Jeff::read_slice`] this returns the original
+    /// input bytes without copying. Otherwise the message segments are
+    /// serialized into a new buffer.
+    pub fn as_bytes(&self) -> Cow<'_, [u8]> {
+        match &self.module {
+            JeffCow::Borrowed { bytes, .. } => Cow::Borrowed(bytes),
+            JeffCow::Owned(module) => Cow::Owned(
+                capnp::serialize::write_message_segments_to_words(module.get_segments()),
+            ),
+            #[cfg(feature = "mmap")]
+            JeffCow::Mapped(module) => Cow::Owned(
+                capnp::serialize::write_message_segments_to_words(module.get_segments()),
+            ),
+        }
+    }
+
+    /// Returns an iterator over the functions defined in the program.
+    ///
+    /// Shorthand for `self.module().functions()`.
+    pub fn functions(&self) -> impl Iterator<Item = Function<'_>> {
+        self.module().functions()
+    }
+
+    /// Returns the number of functions defined in the program.
+    ///
+    /// Shorthand for `self.module().function_count()`.
+    pub fn function_count(&self) -> usize {
+        self.module().function_count()
+    }
+
+    /// Returns the entrypoint function of the program.
+    ///
+    /// Shorthand for `self.module().entrypoint()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entrypoint id in the jeff definition is out of range.
+    pub fn entrypoint(&self) -> Function<'_> {
+        self.module().entrypoint()
+    }
+
+    /// Returns the structural differences between this program and `other`.
+    ///
+    /// Shorthand for `self.module().diff(other)`. See [`Module::diff`].
+    pub fn diff(&self, other: &Module<'_>) -> ModuleDiff {
+        self.module().diff(other)
+    }
+
+    /// Re-encodes the program after applying `f` to every metadata entry.
+    ///
+    /// `f` receives the kind of element the entry is attached to, the entry
+    /// name, and its value. The entry is dropped if `f` returns `None`, and
+    /// its value is replaced otherwise. Unchanged values keep their original
+    /// encoding, while new values are written as text. Returning
+    /// [`MetadataValue::Opaque`] for a value that was not opaque clears it.
+    ///
+    /// Returns the encoded program. This can be used, for example, to strip
+    /// debug information before publishing a program.
+    ///
+    /// # Errors
+    ///
+    /// - [`JeffError::ReadError`] if a metadata name is not in the string table.
+    /// - [`JeffError::InvalidFile`] if the program is malformed.
+    pub fn rewrite<F>(&self, mut f: F) -> Result<Vec<u8>, JeffError>
+    where
+        F: FnMut(MetaScope, &str, MetadataValue) -> Option<MetadataValue>,
+    {
+        let source = self.module.module();
+        let mut message = capnp::message::Builder::new_default();
+        message.set_root(source)?;
+        crate::builder::rewrite_metadata(source, message.get_root()?, &mut f)?;
+
+        // Copy the result into a new message to drop the replaced lists.
+        let mut compact = capnp::message::Builder::new_default();
+        compact.set_root(message.get_root_as_reader::<jeff_capnp::module::Reader<'_>>()?)?;
+        Ok(capnp::serialize::write_message_to_words(&compact))
+    }
+
+    /// Links several modules into a single program.
+    ///
+    /// Functions are matched by name across the modules. Each name becomes a
+    /// single function in the linked module, using its definition if one of
+    /// the modules provides it, and function calls are redirected
+    /// accordingly. The string tables are merged, and the version, tool,
+    /// metadata, and entrypoint of the first module are kept.
+    ///
+    /// Returns the encoded program.
+    ///
+    /// # Errors
+    ///
+    /// - [`LinkError::NoModules`] if `modules` is empty.
+    /// - [`LinkError::DuplicateDefinition`] if a function is defined in more
+    ///   than one place.
+    /// - [`LinkError::SignatureMismatch`] if two functions with the same name
+    ///   have different signatures.
+    /// - [`LinkError::TooManyFunctions`] or [`LinkError::TooManyStrings`] if
+    ///   the linked module has too many functions or strings.
+    /// - [`LinkError::Invalid`] if a module is malformed.
+    pub fn link(modules: &[Module<'_>]) -> Result<Vec<u8>, LinkError> {
+        crate::builder::link(modules)
+    }
+
+    /// Check that the message looks like a jeff module beyond its root type.
+    ///
+    /// Any capnp message can be read as a module, so an unrelated message is
+    /// only detected when its fields are accessed. This checks that the
+    /// `functions`, `strings`, and `metadata` fields of the module hold lists
+    /// of the expected kind, so that such messages are rejected before they
+    /// are used.
+    ///
+    /// The `functions` and `strings` lists are required. The `metadata` list
+    /// is optional, as writers omit it when the module has no metadata.
+    ///
+    /// # Errors
+    ///
+    /// - [`JeffError::ReadError`] if the `functions` or `strings` list is
+    ///   missing.
+    /// - [`JeffError::InvalidFile`] if one of the lists cannot be read.
+    fn check_structure(&self) -> Result<(), JeffError> {
+        let module = self.module.module();
+        let invalid = |field: &str, e: capnp::Error| {
+            JeffError::InvalidFile(capnp::Error::failed(format!(
+                "the module `{field}` field is not a valid list, the message may not be a jeff program: {e}"
+            )))
+        };
+        module
+            .get_functions()
+            .map_err(|e| invalid("functions", e))?;
+        module.get_strings().map_err(|e| invalid("strings", e))?;
+        module.get_metadata().map_err(|e| invalid("metadata", e))?;
+        for (field, present) in [
+            ("functions", module.has_functions()),
+            ("strings", module.has_strings()),
+        ] {
+            if !present {
+                return Err(ReadError::MissingModuleField { field }.into());
+            }
+        }
+        Ok(())
+    }
+
     /// Check if the schema version is compatible with the current version.
     ///
     /// The version must be between [`Self::MIN_COMPATIBLE_VERSION`] and [`Self::MAX_COMPATIBLE_VERSION`].
@@ -116,6 +377,72 @@ impl<'a> Jeff<'a> {
     }
 }
 
+/// Iterator over a stream of framed jeff messages.
+///
+/// Each item is read with the standard Cap'n Proto stream framing, waiting for
+/// the rest of a message when the underlying reader returns partial data. This
+/// makes it suitable for long-lived connections such as sockets.
+///
+/// Iteration ends when the stream is closed between two messages. After the
+/// first error, no further messages are read.
+///
+/// For optimal performance, `reader` should be a buffered reader type.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct JeffFramedReader<R> {
+    /// The underlying stream.
+    reader: R,
+    /// Whether the stream has ended or failed.
+    done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> JeffFramedReader<R> {
+    /// Create a new framed reader over a stream of jeff messages.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            done: false,
+        }
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Iterator for JeffFramedReader<R> {
+    type Item = Result<Jeff<'static>, JeffError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let options = capnp::message::ReaderOptions::new();
+        let result = match capnp::serialize::try_read_message(&mut self.reader, options) {
+            Ok(Some(message)) => Jeff::from_message(message),
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Err(e) => Err(e.into()),
+        };
+        self.done = result.is_err();
+        Some(result)
+    }
+}
+
+/// Programs are compared structurally with [`Module::structurally_eq`], not
+/// byte by byte. Programs with different encodings can compare equal, but
+/// differences in metadata are significant.
+impl PartialEq for Jeff<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.module().structurally_eq(&other.module())
+    }
+}
+
 impl ReadJeff for Jeff<'_> {
     fn module(&self) -> Module<'_> {
         Module::read_capnp(self.module.module())
@@ -126,23 +453,54 @@ impl JeffCow<'_> {
     /// Get a reference to the internal jeff module.
     pub fn module(&self) -> jeff_capnp::module::Reader<'_> {
         match self {
-            Self::Borrowed(module) => module.get().expect("Root type should be correct"),
+            Self::Borrowed { reader, .. } => reader.get().expect("Root type should be correct"),
             Self::Owned(module) => module.get().expect("Root type should be correct"),
+            #[cfg(feature = "mmap")]
+            Self::Mapped(module) => module.get().expect("Root type should be correct"),
         }
     }
 }
 
 impl Clone for JeffCow<'_> {
+    /// Borrowed programs are cloned without copying the data. Owned and
+    /// memory-mapped programs are copied into a new owned buffer.
     fn clone(&self) -> Self {
-        todo!()
+        let options = capnp::message::ReaderOptions::new();
+        match self {
+            Self::Borrowed { bytes, .. } => {
+                let mut slice = *bytes;
+                let reader = capnp::serialize::read_message_from_flat_slice(&mut slice, options)
+                    .expect("Borrowed message should be readable");
+                Self::Borrowed {
+                    reader: reader.into_typed(),
+                    bytes,
+                }
+            }
+            Self::Owned(module) => Self::Owned(copy_message(module.get_segments(), options)),
+            #[cfg(feature = "mmap")]
+            Self::Mapped(module) => Self::Owned(copy_message(module.get_segments(), options)),
+        }
     }
 }
 
-impl std::fmt::Debug for JeffCow<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// Copies the segments of a message into a new owned message.
+fn copy_message(
+    segments: &impl capnp::message::ReaderSegments,
+    options: capnp::message::ReaderOptions,
+) -> TypedReader<OwnedSegments, jeff_capnp::module::Owned> {
+    let words = capnp::serialize::write_message_segments_to_words(segments);
+    capnp::serialize::read_message(words.as_slice(), options)
+        .expect("Copied message should be readable")
+        .into_typed()
+}
+
+impl core::fmt::Debug for JeffCow<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
-            Self::Borrowed(_) => f.debug_tuple("JeffCow::Borrowed").finish_non_exhaustive(),
+            Self::Borrowed { .. } => f.debug_tuple("JeffCow::Borrowed").finish_non_exhaustive(),
             Self::Owned(_) => f.debug_tuple("JeffCow::Owned").finish_non_exhaustive(),
+            #[cfg(feature = "mmap")]
+            Self::Mapped(_) => f.debug_tuple("JeffCow::Mapped").finish_non_exhaustive(),
         }
     }
 }
@@ -150,11 +508,373 @@ impl std::fmt::Debug for JeffCow<'_> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::test::entangled_qs;
+    use crate::reader::HasMetadata;
+    use crate::test::{
+        build_program, definition, edited, entangled_calls, entangled_qs, qubits, read_owned,
+        set_gate_power,
+    };
     use rstest::rstest;
 
+    /// A program with one metadata entry on the module, the function, an
+    /// operation, and a value.
+    fn annotated() -> Jeff<'static> {
+        build_program(|mut module| {
+            let mut strings = module.reborrow().init_strings(5);
+            for (i, s) in ["main", "source", "debug.line", "opt.level", "debug.name"]
+                .into_iter()
+                .enumerate()
+            {
+                strings.set(i as u32, s);
+            }
+            let set_meta = |mut meta: jeff_capnp::meta::Builder<'_>, name: u16, value: &str| {
+                meta.set_name(name);
+                meta.init_value()
+                    .set_as::<capnp::text::Owned>(value)
+                    .unwrap();
+            };
+            set_meta(module.reborrow().init_metadata(1).get(0), 1, "kernel.py");
+
+            let mut function = module.init_functions(1).get(0);
+            set_meta(function.reborrow().init_metadata(1).get(0), 2, "12");
+            let mut def = function.init_definition();
+            let value = def.reborrow().init_values(1).get(0);
+            set_meta(value.init_metadata(1).get(0), 4, "q");
+            let mut body = def.init_body();
+            body.reborrow().init_targets(1).set(0, 0);
+            let mut op = body.init_operations(1).get(0);
+            op.reborrow().init_outputs(1).set(0, 0);
+            set_meta(op.reborrow().init_metadata(1).get(0), 3, "3");
+            op.init_instruction().init_qubit().set_alloc(());
+        })
+    }
+
     #[rstest]
     fn simple_jeff(entangled_qs: Jeff<'static>) {
         entangled_qs.check_version().unwrap();
     }
+
+    #[rstest]
+    fn structural_eq(entangled_qs: Jeff<'static>) {
+        assert_eq!(entangled_qs, entangled_qs.clone());
+        let round_trip = read_owned(&entangled_qs.as_bytes()).unwrap();
+        assert_eq!(entangled_qs, round_trip);
+
+        let other = edited(&entangled_qs, |module| set_gate_power(module, 6, 2));
+        assert_ne!(entangled_qs, other);
+    }
+
+    #[rstest]
+    fn structural_ne_entrypoint(entangled_calls: Jeff<'static>) {
+        let other = edited(&entangled_calls, |mut module| module.set_entrypoint(1));
+        assert_ne!(entangled_calls, other);
+    }
+
+    #[rstest]
+    fn structural_ne_body_targets(qubits: Jeff<'static>) {
+        let other = edited(&qubits, |module| {
+            let function = module.get_functions().unwrap().get(0);
+            let jeff_capnp::function::Which::Definition(def) = function.which().unwrap() else {
+                panic!("Expected a function definition");
+            };
+            let mut targets = def.get_body().unwrap().get_targets().unwrap();
+            let (first, second) = (targets.get(0), targets.get(1));
+            targets.set(0, second);
+            targets.set(1, first);
+        });
+        assert_ne!(qubits, other);
+    }
+
+    #[rstest]
+    fn function_passthrough(entangled_calls: Jeff<'static>) {
+        let module = entangled_calls.module();
+        assert_eq!(entangled_calls.function_count(), module.function_count());
+        assert_eq!(
+            entangled_calls.functions().count(),
+            entangled_calls.function_count()
+        );
+        assert_eq!(
+            entangled_calls.entrypoint().name(),
+            module.entrypoint().name()
+        );
+    }
+
+    #[test]
+    fn as_bytes() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../examples/entangled_qs/entangled_qs.jeff"
+        );
+        let bytes = std::fs::read(path).unwrap();
+        let mut words = capnp::Word::allocate_zeroed_vec(bytes.len().div_ceil(8));
+        capnp::Word::words_to_bytes_mut(&mut words)[..bytes.len()].copy_from_slice(&bytes);
+        let mut slice = capnp::Word::words_to_bytes(&words);
+
+        let borrowed = Jeff::read_slice(&mut slice).unwrap();
+        assert!(matches!(borrowed.as_bytes(), Cow::Borrowed(b) if b == bytes));
+
+        let owned = read_owned(&bytes).unwrap();
+        assert_eq!(owned.as_bytes(), bytes);
+    }
+
+    /// A reader that returns at most `chunk` bytes per call.
+    #[cfg(feature = "std")]
+    struct ChunkedReader<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    #[cfg(feature = "std")]
+    impl std::io::Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn rewrite_strip_metadata() {
+        let program = annotated();
+        let mut seen = Vec::new();
+        let bytes = program
+            .rewrite(|scope, name, _| {
+                seen.push((scope, name.to_string()));
+                None
+            })
+            .unwrap();
+        assert_eq!(
+            seen,
+            [
+                (MetaScope::Module, "source".to_string()),
+                (MetaScope::Function, "debug.line".to_string()),
+                (MetaScope::Operation, "opt.level".to_string()),
+                (MetaScope::Value, "debug.name".to_string()),
+            ]
+        );
+        assert!(bytes.len() < program.as_bytes().len());
+
+        let stripped = Jeff::read_slice(&mut bytes.as_slice()).unwrap();
+        let module = stripped.module();
+        assert_eq!(module.metadata_count(), 0);
+        let main = definition(&stripped, 0);
+        assert_eq!(main.metadata_count(), 0);
+        assert_eq!(main.body().operation(0).metadata_count(), 0);
+        assert_eq!(main.values().get(0).unwrap().metadata_count(), 0);
+        assert!(program.diff(&module).is_empty());
+    }
+
+    #[test]
+    fn rewrite_metadata_values() {
+        let program = annotated();
+        let bytes = program
+            .rewrite(|_, name, value| match (name, value) {
+                (name, _) if name.starts_with("debug.") => None,
+                ("opt.level", MetadataValue::Int(level)) => Some(MetadataValue::Int(level + 1)),
+                (_, value) => Some(value),
+            })
+            .unwrap();
+
+        let rewritten = Jeff::read_slice(&mut bytes.as_slice()).unwrap();
+        let module = rewritten.module();
+        assert_eq!(module.metadata(0).value_str(), Some("kernel.py"));
+        let main = definition(&rewritten, 0);
+        assert_eq!(main.metadata_count(), 0);
+        let op = main.body().operation(0);
+        assert_eq!(op.metadata(0).name(), "opt.level");
+        assert_eq!(op.metadata(0).to_owned_value(), MetadataValue::Int(4));
+    }
+
+    #[cfg(feature = "std")]
+    #[rstest]
+    fn framed_reader(entangled_qs: Jeff<'static>, entangled_calls: Jeff<'static>) {
+        let mut stream = entangled_qs.as_bytes().into_owned();
+        stream.extend_from_slice(&entangled_calls.as_bytes());
+
+        let reader = ChunkedReader {
+            data: &stream,
+            chunk: 7,
+        };
+        let programs: Vec<_> = JeffFramedReader::new(reader)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(programs.len(), 2);
+        assert_eq!(programs[0].function_count(), entangled_qs.function_count());
+        assert_eq!(
+            programs[1].function_count(),
+            entangled_calls.function_count()
+        );
+
+        // A message truncated by the end of the stream is an error.
+        let reader = ChunkedReader {
+            data: &stream[..stream.len() - 8],
+            chunk: 7,
+        };
+        let mut framed = JeffFramedReader::new(reader);
+        assert!(framed.next().unwrap().is_ok());
+        assert!(framed.next().unwrap().is_err());
+        assert!(framed.next().is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn read_with_limit() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../examples/entangled_qs/entangled_qs.jeff"
+        );
+        let bytes = std::fs::read(path).unwrap();
+
+        let jeff = Jeff::read_with_limit(bytes.as_slice(), 1 << 20).unwrap();
+        assert_eq!(jeff.module().function_count(), 1);
+
+        let err = Jeff::read_with_limit(bytes.as_slice(), 64).unwrap_err();
+        assert!(
+            matches!(err, JeffError::TooLarge { max_bytes: 64, size } if size == bytes.len() as u64),
+            "unexpected error: {err}"
+        );
+        let err = Jeff::read_with_limit(bytes.as_slice(), 4).unwrap_err();
+        assert!(
+            matches!(err, JeffError::TooLarge { max_bytes: 4, .. }),
+            "{err}"
+        );
+        let err = Jeff::read_with_limit(&bytes[..6], 1 << 20).unwrap_err();
+        assert!(matches!(err, JeffError::InvalidFile(_)), "{err}");
+    }
+
+    /// The limit only applies to the message size, not to later reads of the
+    /// program.
+    #[cfg(feature = "std")]
+    #[test]
+    fn read_with_limit_traversal() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../examples/entangled_calls/entangled_calls.jeff"
+        );
+        let bytes = std::fs::read(path).unwrap();
+
+        let jeff = Jeff::read_with_limit(bytes.as_slice(), 2 * bytes.len() as u64).unwrap();
+        let expected = jeff.module().total_operation_count();
+        for _ in 0..16 {
+            let count: usize = jeff
+                .functions()
+                .filter_map(|f| match f {
+                    Function::Definition(def) => Some(def.body().operations().count()),
+                    Function::Declaration(_) => None,
+                })
+                .sum();
+            assert!(count > 0);
+            assert_eq!(jeff.module().total_operation_count(), expected);
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_jeff() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../examples/entangled_qs/entangled_qs.jeff"
+        );
+        // SAFETY: The example file is not modified during the test.
+        let jeff = unsafe { Jeff::read_mmap(path) }.unwrap();
+        let module = jeff.module();
+        assert_eq!(module.function_count(), 1);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_missing_file() {
+        // SAFETY: The file does not exist, so it cannot be mapped.
+        let err = unsafe { Jeff::read_mmap("does/not/exist.jeff") }.unwrap_err();
+        assert!(matches!(err, JeffError::Io(_)));
+    }
+
+    #[test]
+    fn unrelated_message() {
+        // A function definition, whose body pointer is read as the module
+        // functions list.
+        let mut message = capnp::message::Builder::new_default();
+        let mut function = message.init_root::<jeff_capnp::function::Builder<'_>>();
+        function.set_name(3);
+        function
+            .init_definition()
+            .init_body()
+            .init_operations(1)
+            .get(0)
+            .init_instruction()
+            .init_qubit()
+            .set_alloc(());
+        let mut buffer = Vec::new();
+        capnp::serialize::write_message(&mut buffer, &message).unwrap();
+
+        let Err(err) = read_owned(&buffer) else {
+            panic!("Expected an unrelated message to be rejected");
+        };
+        assert!(matches!(err, JeffError::InvalidFile(_)), "{err}");
+        assert!(err.to_string().contains("`functions`"), "{err}");
+    }
+    /// Builds a module with the given lists, leaving the others unset.
+    fn module_with_lists(functions: bool, strings: bool, metadata: bool) -> Vec<u8> {
+        let mut message = capnp::message::Builder::new_default();
+        let mut module = message.init_root::<jeff_capnp::module::Builder<'_>>();
+        module.set_version(crate::SCHEMA_VERSION.major as u32);
+        module.set_version_minor(crate::SCHEMA_VERSION.minor as u32);
+        if functions {
+            module.reborrow().init_functions(0);
+        }
+        if strings {
+            module.reborrow().init_strings(0);
+        }
+        if metadata {
+            module.reborrow().init_metadata(0);
+        }
+        let mut buffer = Vec::new();
+        capnp::serialize::write_message(&mut buffer, &message).unwrap();
+        buffer
+    }
+
+    #[rstest]
+    #[case::functions(module_with_lists(false, true, true), "functions")]
+    #[case::strings(module_with_lists(true, false, true), "strings")]
+    fn missing_module_list(#[case] buffer: Vec<u8>, #[case] field: &str) {
+        let Err(err) = read_owned(&buffer) else {
+            panic!("Expected a module without `{field}` to be rejected");
+        };
+        assert!(
+            matches!(
+                err,
+                JeffError::ReadError(ReadError::MissingModuleField { field: f }) if f == field
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn missing_metadata_list() {
+        let jeff = read_owned(&module_with_lists(true, true, false)).unwrap();
+        assert_eq!(jeff.module().metadata_count(), 0);
+    }
+
+    #[test]
+    fn invalid_metadata_list() {
+        // Rewrite the metadata pointer of the module into a list of bits,
+        // which cannot be read as a list of metadata structs.
+        let mut buffer = module_with_lists(true, true, true);
+        let word = |buffer: &[u8], i: usize| {
+            let offset = 8 + 8 * i;
+            u64::from_le_bytes(buffer[offset..offset + 8].try_into().unwrap())
+        };
+        let root = word(&buffer, 0);
+        let data_words = (root >> 32) & 0xffff;
+        let struct_start = 1 + ((root as u32 as i32) >> 2) as u64;
+        let metadata = (struct_start + data_words + 2) as usize;
+        let bit_list = (word(&buffer, metadata) & 0xffff_ffff) | (1 << 32);
+        buffer[8 + 8 * metadata..16 + 8 * metadata].copy_from_slice(&bit_list.to_le_bytes());
+
+        let Err(err) = read_owned(&buffer) else {
+            panic!("Expected an invalid metadata list to be rejected");
+        };
+        assert!(matches!(err, JeffError::InvalidFile(_)), "{err}");
+        assert!(err.to_string().contains("`metadata`"), "{err}");
+    }
 }
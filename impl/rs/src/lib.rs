@@ -1,15 +1,29 @@
 //! The data model of the jeff representation.
 //!
 //! This crate defines data structures for zero-copy decoding of jeff files.
+//!
+//! The crate is `no_std` compatible when the default `std` feature is
+//! disabled. In that case programs can only be loaded from byte slices with
+//! [`Jeff::read_slice`].
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 mod capnp;
 mod jeff;
 
 #[cfg(test)]
 mod test;
 
+pub mod analysis;
+pub mod builder;
+#[cfg(feature = "std")]
+pub mod interp;
 pub mod reader;
 pub mod types;
 pub use jeff::Jeff;
+#[cfg(feature = "std")]
+pub use jeff::JeffFramedReader;
 
 // The capnp-generated code is re-exported here, but in general it should not be
 // used directly.
@@ -17,7 +31,10 @@ pub use jeff::Jeff;
 // No semver guarantees are provided for this module.
 #[doc(hidden)]
 pub use capnp::jeff_capnp;
+// The generated analysis schema code refers to itself from the crate root.
+use capnp::analysis_capnp;
 
+use alloc::string::String;
 use derive_more::derive::{Display, Error, From};
 
 /// Latest version of the jeff schema.
@@ -54,6 +71,57 @@ pub enum JeffError {
     /// Error while reading the internal structure.
     #[from]
     ReadError(reader::ReadError),
+    /// The jeff message is larger than the allowed limit.
+    #[display("Jeff message of {size} bytes exceeds the limit of {max_bytes} bytes")]
+    TooLarge {
+        /// The size of the message, in bytes.
+        size: u64,
+        /// The maximum allowed size, in bytes.
+        max_bytes: u64,
+    },
+    /// Error while accessing a jeff file.
+    #[cfg(feature = "std")]
+    #[display("Could not access jeff file: {_0}")]
+    #[from]
+    Io(std::io::Error),
+}
+
+/// Errors that can occur when linking jeff modules.
+///
+/// See [`Jeff::link`].
+#[derive(Debug, Display, From, Error)]
+#[non_exhaustive]
+pub enum LinkError {
+    /// No modules were given.
+    #[display("No modules to link")]
+    NoModules,
+    /// More than one module defines a function with the same name.
+    #[display("Function `{name}` is defined more than once")]
+    DuplicateDefinition {
+        /// Name of the function.
+        name: String,
+    },
+    /// Two functions with the same name have different signatures.
+    #[display("Function `{name}` has conflicting signatures {first} and {second}")]
+    SignatureMismatch {
+        /// Name of the function.
+        name: String,
+        /// Signature of the first function with this name.
+        first: types::FunctionType,
+        /// Signature of the conflicting function.
+        second: types::FunctionType,
+    },
+    /// The linked module has more distinct strings than a string table can
+    /// index.
+    #[display("Linked module has more than {} distinct strings", u16::MAX as usize + 1)]
+    TooManyStrings,
+    /// The linked module has more distinct functions than a function index
+    /// can refer to.
+    #[display("Linked module has more than {} distinct functions", u16::MAX as usize + 1)]
+    TooManyFunctions,
+    /// One of the modules is invalid.
+    #[from]
+    Invalid(JeffError),
 }
 
 /// Direction of a port.
@@ -2,21 +2,27 @@
 //!
 //! Programs are composed of a top-level [`Module`] that contains a list of [`Function`]s.
 
+mod diff;
 mod function;
 mod metadata;
 mod module;
 mod op;
 mod region;
-mod string_table;
+pub(crate) mod string_table;
 pub mod value;
 
 pub mod optype;
 
-pub use function::{Function, FunctionDeclaration, FunctionDefinition, FunctionId};
-pub use metadata::{HasMetadata, Metadata};
-pub use module::Module;
-pub use op::Operation;
-pub use region::Region;
+pub use diff::{FunctionDiff, ModuleDiff};
+pub use function::{
+    CategoryCounts, Function, FunctionDeclaration, FunctionDefinition, FunctionId,
+    InteractionGraph, LogicalQubit,
+};
+pub use metadata::{HasMetadata, MetaScope, Metadata, MetadataValue};
+pub use module::{FunctionSummary, Module, ModuleOpPath, ToolInfo};
+pub use op::{Operation, ResolvedOp, SourceLoc};
+pub use region::{NestedOpPath, OpPath, RebuildPlan, Region};
+pub use string_table::StringTable;
 pub use value::{FunctionIOValue, ValueId, ValueTable, WireValue};
 
 use derive_more::derive::{Display, Error, From};
@@ -59,4 +65,28 @@ pub enum ReadError {
         /// The total number of entries in the function values.
         count: usize,
     },
+    /// A value in the function's value table could not be decoded.
+    #[display("Function value {idx} could not be decoded: {source}")]
+    InvalidValue {
+        /// The index of the value in the function values.
+        idx: u32,
+        /// The decoding error.
+        source: capnp::Error,
+    },
+    /// The module does not contain one of its required lists.
+    #[display("The module has no `{field}` list, the message may not be a jeff program")]
+    MissingModuleField {
+        /// The name of the missing field.
+        field: &'static str,
+    },
+    /// The inputs of an array creation operation have different types.
+    #[display("Array element {idx} has type {found}, but the first element has type {expected}")]
+    MismatchedElementType {
+        /// The position of the offending input.
+        idx: usize,
+        /// The type of the first input.
+        expected: crate::types::Type,
+        /// The type of the offending input.
+        found: crate::types::Type,
+    },
 }
@@ -0,0 +1,794 @@
+//! Structural differences between jeff modules.
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+use crate::reader::optype::{ControlFlowOp, FloatArrayOp, FloatOp, OpType};
+use crate::types::Type;
+
+use super::value::ValueId;
+use super::{
+    Function, FunctionId, HasMetadata, Metadata, Module, ModuleOpPath, Operation, ReadError,
+    Region, WireValue,
+};
+
+/// Structural differences between two jeff modules.
+///
+/// Functions are matched by name, and functions sharing a name are matched in
+/// module order. Within matched function definitions, operations are compared
+/// by position in their region.
+///
+/// See [`Module::diff`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ModuleDiff {
+    /// Whether the modules use different versions of the jeff format.
+    pub version_changed: bool,
+    /// Whether the entrypoints of the modules have different names.
+    pub entrypoint_changed: bool,
+    /// Names of the functions only present in the other module.
+    pub added: Vec<String>,
+    /// Names of the functions only present in this module.
+    pub removed: Vec<String>,
+    /// Functions present in both modules with different contents.
+    pub changed: Vec<FunctionDiff>,
+}
+
+/// Structural differences between two functions with the same name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FunctionDiff {
+    /// Name of the function.
+    pub name: String,
+    /// Whether the function is a definition in one module and a declaration in
+    /// the other.
+    pub kind_changed: bool,
+    /// Whether the input or output types of the function differ.
+    pub signature_changed: bool,
+    /// Whether the sources or targets of the function bodies differ, by value
+    /// id or type.
+    pub boundary_changed: bool,
+    /// Paths to the operations that differ, using the function ids of the
+    /// module [`Module::diff`] was called on.
+    ///
+    /// Changes inside nested control flow regions are reported at the nested
+    /// operation. Control flow operations are reported themselves when their
+    /// inputs, outputs, kind, number of regions, or region sources and targets
+    /// differ. Operations past the end of the shorter region are always
+    /// included.
+    pub operations: Vec<ModuleOpPath>,
+}
+
+impl ModuleDiff {
+    /// Returns `true` if the modules are structurally equal.
+    pub fn is_empty(&self) -> bool {
+        !self.version_changed
+            && !self.entrypoint_changed
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+impl FunctionDiff {
+    /// Returns `true` if the functions are structurally equal.
+    pub fn is_empty(&self) -> bool {
+        !self.kind_changed
+            && !self.signature_changed
+            && !self.boundary_changed
+            && self.operations.is_empty()
+    }
+}
+
+impl Module<'_> {
+    /// Returns the structural differences between this module and `other`.
+    ///
+    /// Operations are equal when they have the same operation type, the same
+    /// input and output values, and structurally equal nested regions. Value
+    /// types and ids are compared, but metadata and the encoding layout are
+    /// ignored. The format version and the name of the entrypoint are also
+    /// compared.
+    pub fn diff(&self, other: &Module<'_>) -> ModuleDiff {
+        let ours = by_name(self);
+        let theirs = by_name(other);
+
+        let mut diff = ModuleDiff {
+            version_changed: self.version() != other.version(),
+            entrypoint_changed: entrypoint_name(self) != entrypoint_name(other),
+            ..Default::default()
+        };
+        for (key @ (name, _), (id, function)) in &ours {
+            match theirs.get(key) {
+                None => diff.removed.push(name.to_string()),
+                Some((_, other_function)) => {
+                    let function_diff = diff_functions(*id, name, function, other_function);
+                    if !function_diff.is_empty() {
+                        diff.changed.push(function_diff);
+                    }
+                }
+            }
+        }
+        diff.added = theirs
+            .keys()
+            .filter(|key| !ours.contains_key(*key))
+            .map(|(name, _)| name.to_string())
+            .collect();
+        diff
+    }
+
+    /// Returns `true` if this module is structurally equal to `other`,
+    /// including the metadata attached to every element.
+    ///
+    /// Metadata entries are compared in order, by name and by the canonical
+    /// encoding of their values, so values that are not stored as text are
+    /// compared too. The encoding layout is ignored.
+    ///
+    /// See [`Module::eq_ignoring_metadata`] to skip the metadata.
+    pub fn structurally_eq(&self, other: &Module<'_>) -> bool {
+        self.eq_ignoring_metadata(other) && module_metadata_eq(self, other)
+    }
+
+    /// Returns `true` if this module is structurally equal to `other`,
+    /// ignoring all metadata.
+    ///
+    /// This holds when [`Module::diff`] reports no differences. Unlike
+    /// [`Module::structurally_eq`], modules that only differ in their
+    /// metadata, such as debug annotations, compare equal.
+    pub fn eq_ignoring_metadata(&self, other: &Module<'_>) -> bool {
+        self.diff(other).is_empty()
+    }
+}
+
+/// Returns the name of the entrypoint of a module, or `None` if the
+/// entrypoint id is out of range.
+fn entrypoint_name(module: &Module<'_>) -> Option<String> {
+    let id = module.entrypoint_id() as usize;
+    module.functions().nth(id).map(|f| f.name().to_string())
+}
+
+/// Returns the input and output types of a function, with `None` for values
+/// that cannot be read.
+fn io_types(f: &Function<'_>) -> (Vec<Option<Type>>, Vec<Option<Type>>) {
+    (
+        f.input_types().map(|v| v.ok().map(|v| v.ty())).collect(),
+        f.output_types().map(|v| v.ok().map(|v| v.ty())).collect(),
+    )
+}
+
+/// Indexes the functions in a module and their ids by name.
+///
+/// Functions sharing a name are keyed by their position among those
+/// functions, so that they are matched in module order.
+fn by_name<'a>(module: &Module<'a>) -> BTreeMap<(String, usize), (FunctionId, Function<'a>)> {
+    let mut occurrences: BTreeMap<String, usize> = BTreeMap::new();
+    let mut functions = BTreeMap::new();
+    for (id, function) in module.functions().enumerate() {
+        let name = function.name().to_string();
+        let occurrence = occurrences.entry(name.clone()).or_default();
+        functions.insert((name, *occurrence), (id as FunctionId, function));
+        *occurrence += 1;
+    }
+    functions
+}
+
+fn diff_functions(id: FunctionId, name: &str, a: &Function<'_>, b: &Function<'_>) -> FunctionDiff {
+    let mut diff = FunctionDiff {
+        name: name.to_string(),
+        signature_changed: io_types(a) != io_types(b),
+        ..Default::default()
+    };
+    match (a, b) {
+        (Function::Definition(a), Function::Definition(b)) => {
+            let (a, b) = (a.body(), b.body());
+            diff.boundary_changed = !boundaries_eq(&a, &b);
+            diff_regions(id, &a, &b, &mut Vec::new(), &mut diff.operations);
+        }
+        (Function::Declaration(_), Function::Declaration(_)) => {}
+        _ => diff.kind_changed = true,
+    }
+    diff
+}
+
+/// Compares the metadata entries of two elements, by name and by the
+/// canonical encoding of their values.
+///
+/// Values that cannot be canonicalized compare equal to each other.
+fn metadata_eq(a: &impl HasMetadata, b: &impl HasMetadata) -> bool {
+    let key = |m: Metadata<'_>| (m.name().to_string(), canonical_words(m.value_any_pointer()));
+    a.metadata_entries()
+        .map(key)
+        .eq(b.metadata_entries().map(key))
+}
+
+/// Returns the canonical encoding of a pointer's target, or `None` if it
+/// cannot be read.
+fn canonical_words(value: capnp::any_pointer::Reader<'_>) -> Option<Vec<u8>> {
+    let mut message = capnp::message::Builder::new_default();
+    message.set_root_canonical(value).ok()?;
+    Some(message.get_segments_for_output().concat())
+}
+
+/// Compares the metadata of two lists of values, skipping values that cannot
+/// be read.
+fn values_metadata_eq<V: HasMetadata>(
+    a: impl Iterator<Item = Result<V, ReadError>>,
+    b: impl Iterator<Item = Result<V, ReadError>>,
+) -> bool {
+    a.zip(b).all(|(a, b)| match (a, b) {
+        (Ok(a), Ok(b)) => metadata_eq(&a, &b),
+        _ => true,
+    })
+}
+
+/// Compares the metadata of every element in two modules.
+///
+/// The modules must already be equal when ignoring metadata.
+fn module_metadata_eq(a: &Module<'_>, b: &Module<'_>) -> bool {
+    let theirs = by_name(b);
+    metadata_eq(a, b)
+        && by_name(a).iter().all(|(key, (_, fa))| {
+            let (_, fb) = &theirs[key];
+            metadata_eq(fa, fb)
+                && values_metadata_eq(fa.input_types(), fb.input_types())
+                && values_metadata_eq(fa.output_types(), fb.output_types())
+                && match (fa, fb) {
+                    (Function::Definition(a), Function::Definition(b)) => {
+                        region_metadata_eq(&a.body(), &b.body())
+                    }
+                    _ => true,
+                }
+        })
+}
+
+fn region_metadata_eq(a: &Region<'_>, b: &Region<'_>) -> bool {
+    metadata_eq(a, b)
+        && values_metadata_eq(a.sources(), b.sources())
+        && values_metadata_eq(a.targets(), b.targets())
+        && a.operations().zip(b.operations()).all(|(op_a, op_b)| {
+            metadata_eq(&op_a, &op_b)
+                && values_metadata_eq(op_a.inputs(), op_b.inputs())
+                && values_metadata_eq(op_a.outputs(), op_b.outputs())
+                && nested_regions(&op_a)
+                    .iter()
+                    .zip(&nested_regions(&op_b))
+                    .all(|(a, b)| region_metadata_eq(a, b))
+        })
+}
+
+/// Returns the regions nested in an operation, if it is a control flow
+/// operation.
+fn nested_regions<'a>(op: &Operation<'a>) -> Vec<Region<'a>> {
+    match op.op_type() {
+        OpType::ControlFlowOp(cf_op) => cf_op.regions(),
+        _ => Vec::new(),
+    }
+}
+
+/// Key used to compare and hash wire values, by id and type.
+fn value_key(value: Result<WireValue<'_>, ReadError>) -> Option<(ValueId, Type)> {
+    value.ok().map(|v| (v.id(), v.ty()))
+}
+
+/// Compares a list of wire values by id and type.
+fn values_eq<'a>(
+    a: impl Iterator<Item = Result<WireValue<'a>, ReadError>>,
+    b: impl Iterator<Item = Result<WireValue<'a>, ReadError>>,
+) -> bool {
+    a.map(value_key).eq(b.map(value_key))
+}
+
+/// Hashes a list of wire values by id and type, consistently with
+/// [`values_eq`].
+fn hash_values<'a>(
+    values: impl Iterator<Item = Result<WireValue<'a>, ReadError>>,
+    state: &mut impl Hasher,
+) {
+    values.map(value_key).collect::<Vec<_>>().hash(state);
+}
+
+/// Compares the sources and targets of two regions.
+fn boundaries_eq(a: &Region<'_>, b: &Region<'_>) -> bool {
+    values_eq(a.sources(), b.sources()) && values_eq(a.targets(), b.targets())
+}
+
+/// Collects the paths to the operations that differ between two regions.
+///
+/// `regions` is the path from the function body to the compared regions.
+fn diff_regions(
+    function: FunctionId,
+    a: &Region<'_>,
+    b: &Region<'_>,
+    regions: &mut Vec<(usize, usize)>,
+    changed: &mut Vec<ModuleOpPath>,
+) {
+    for n in 0..a.operation_count().max(b.operation_count()) {
+        let path = |regions: &Vec<(usize, usize)>| ModuleOpPath {
+            function,
+            regions: regions.clone(),
+            op: n,
+        };
+        let (Some(op_a), Some(op_b)) = (
+            (n < a.operation_count()).then(|| a.operation(n)),
+            (n < b.operation_count()).then(|| b.operation(n)),
+        ) else {
+            changed.push(path(regions));
+            continue;
+        };
+        if !values_eq(op_a.inputs(), op_b.inputs()) || !values_eq(op_a.outputs(), op_b.outputs()) {
+            changed.push(path(regions));
+            continue;
+        }
+        match (op_a.op_type(), op_b.op_type()) {
+            (OpType::ControlFlowOp(cf_a), OpType::ControlFlowOp(cf_b)) => {
+                if !same_control_flow_kind(&cf_a, &cf_b) {
+                    changed.push(path(regions));
+                    continue;
+                }
+                let (regions_a, regions_b) = (cf_a.regions(), cf_b.regions());
+                if !regions_a
+                    .iter()
+                    .zip(&regions_b)
+                    .all(|(a, b)| boundaries_eq(a, b))
+                {
+                    changed.push(path(regions));
+                }
+                for (idx, (a, b)) in regions_a.iter().zip(&regions_b).enumerate() {
+                    regions.push((n, idx));
+                    diff_regions(function, a, b, regions, changed);
+                    regions.pop();
+                }
+            }
+            (op_type_a, op_type_b) => {
+                if !op_types_eq(&op_type_a, &op_type_b) {
+                    changed.push(path(regions));
+                }
+            }
+        }
+    }
+}
+
+/// Returns `true` if two control flow operations are of the same kind and
+/// have matching regions, ignoring the contents of the regions.
+fn same_control_flow_kind(a: &ControlFlowOp<'_>, b: &ControlFlowOp<'_>) -> bool {
+    match (a, b) {
+        (ControlFlowOp::For { .. }, ControlFlowOp::For { .. })
+        | (ControlFlowOp::While { .. }, ControlFlowOp::While { .. }) => true,
+        (ControlFlowOp::Switch(a), ControlFlowOp::Switch(b)) => {
+            a.branch_count() == b.branch_count()
+                && a.default_branch().is_some() == b.default_branch().is_some()
+        }
+        _ => false,
+    }
+}
+
+fn regions_eq(a: &Region<'_>, b: &Region<'_>) -> bool {
+    boundaries_eq(a, b)
+        && a.operation_count() == b.operation_count()
+        && a.operations()
+            .zip(b.operations())
+            .all(|(op_a, op_b)| operations_eq(&op_a, &op_b))
+}
+
+fn operations_eq(a: &Operation<'_>, b: &Operation<'_>) -> bool {
+    values_eq(a.inputs(), b.inputs())
+        && values_eq(a.outputs(), b.outputs())
+        && op_types_eq(&a.op_type(), &b.op_type())
+}
+
+/// Compares two operation types, including the nested regions of control flow
+/// operations.
+pub(crate) fn op_types_eq(a: &OpType<'_>, b: &OpType<'_>) -> bool {
+    match (a, b) {
+        (OpType::QubitOp(a), OpType::QubitOp(b)) => a == b,
+        (OpType::QubitRegisterOp(a), OpType::QubitRegisterOp(b)) => a == b,
+        (OpType::IntOp(a), OpType::IntOp(b)) => a == b,
+        (OpType::IntArrayOp(a), OpType::IntArrayOp(b)) => a == b,
+        (OpType::FloatOp(a), OpType::FloatOp(b)) => match (a, b) {
+            (FloatOp::Const32(a), FloatOp::Const32(b)) => f32_bits(*a) == f32_bits(*b),
+            (FloatOp::Const64(a), FloatOp::Const64(b)) => f64_bits(*a) == f64_bits(*b),
+            _ => a == b,
+        },
+        (OpType::FloatArrayOp(a), OpType::FloatArrayOp(b)) => match (a, b) {
+            (FloatArrayOp::Const32(a), FloatArrayOp::Const32(b)) => {
+                a.len() == b.len() && a.values().map(f32_bits).eq(b.values().map(f32_bits))
+            }
+            (FloatArrayOp::Const64(a), FloatArrayOp::Const64(b)) => {
+                a.len() == b.len() && a.values().map(f64_bits).eq(b.values().map(f64_bits))
+            }
+            _ => a == b,
+        },
+        (OpType::FuncOp(a), OpType::FuncOp(b)) => a == b,
+        (OpType::ControlFlowOp(a), OpType::ControlFlowOp(b)) => match (a.as_ref(), b.as_ref()) {
+            (ControlFlowOp::For { region: a }, ControlFlowOp::For { region: b }) => {
+                regions_eq(a, b)
+            }
+            (
+                ControlFlowOp::While {
+                    before: a_before,
+                    after: a_after,
+                },
+                ControlFlowOp::While {
+                    before: b_before,
+                    after: b_after,
+                },
+            ) => regions_eq(a_before, b_before) && regions_eq(a_after, b_after),
+            (ControlFlowOp::Switch(a), ControlFlowOp::Switch(b)) => {
+                let branches_a: Vec<_> = a.branches().collect();
+                let branches_b: Vec<_> = b.branches().collect();
+                branches_a.len() == branches_b.len()
+                    && branches_a
+                        .iter()
+                        .zip(&branches_b)
+                        .all(|(a, b)| regions_eq(a, b))
+                    && match (a.default_branch(), b.default_branch()) {
+                        (Some(a), Some(b)) => regions_eq(&a, &b),
+                        (None, None) => true,
+                        _ => false,
+                    }
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns the bits of a float constant, with negative zero mapped to positive
+/// zero.
+///
+/// Float constants are compared and hashed by their bits, so that NaN
+/// constants are equal to themselves.
+fn f32_bits(value: f32) -> u32 {
+    if value == 0.0 { 0.0f32 } else { value }.to_bits()
+}
+
+/// Returns the bits of a float constant, with negative zero mapped to positive
+/// zero.
+///
+/// See [`f32_bits`].
+fn f64_bits(value: f64) -> u64 {
+    if value == 0.0 { 0.0f64 } else { value }.to_bits()
+}
+
+/// 64-bit FNV-1a hasher.
+///
+/// Integers are hashed in little-endian order, and `usize` as a `u64`, so
+/// that hashes do not depend on the platform.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// Hashes the structural contents of a module, consistently with
+/// [`Module::eq_ignoring_metadata`].
+///
+/// See [`Module::content_hash`].
+pub(crate) fn structural_hash(module: &Module<'_>) -> u64 {
+    let mut state = Fnv1a::new();
+    module.version().hash(&mut state);
+    entrypoint_name(module).hash(&mut state);
+    for ((name, _), (_, function)) in by_name(module) {
+        name.hash(&mut state);
+        io_types(&function).hash(&mut state);
+        match function {
+            Function::Definition(def) => {
+                true.hash(&mut state);
+                let body = def.body();
+                hash_values(body.sources(), &mut state);
+                hash_values(body.targets(), &mut state);
+                hash_operations(&body, &mut state);
+            }
+            Function::Declaration(_) => false.hash(&mut state),
+        }
+    }
+    state.finish()
+}
+
+/// Hashes a region, consistently with [`regions_eq`].
+fn hash_region(region: &Region<'_>, state: &mut impl Hasher) {
+    hash_values(region.sources(), state);
+    hash_values(region.targets(), state);
+    hash_operations(region, state);
+}
+
+/// Hashes the operations of a region, consistently with [`operations_eq`].
+fn hash_operations(region: &Region<'_>, state: &mut impl Hasher) {
+    region.operation_count().hash(state);
+    for op in region.operations() {
+        hash_values(op.inputs(), state);
+        hash_values(op.outputs(), state);
+        hash_op_type(&op.op_type(), state);
+    }
+}
+
+/// Hashes an operation type, consistently with [`op_types_eq`].
+fn hash_op_type(op_type: &OpType<'_>, state: &mut impl Hasher) {
+    let hash_f32 = |value: f32, state: &mut _| Hasher::write_u32(state, f32_bits(value));
+    let hash_f64 = |value: f64, state: &mut _| Hasher::write_u64(state, f64_bits(value));
+
+    op_type.kind().hash(state);
+    match op_type {
+        OpType::QubitOp(op) => op.hash(state),
+        OpType::QubitRegisterOp(op) => op.hash(state),
+        OpType::IntOp(op) => op.hash(state),
+        OpType::IntArrayOp(op) => op.hash(state),
+        OpType::FuncOp(op) => op.hash(state),
+        OpType::FloatOp(op) => {
+            core::mem::discriminant(op).hash(state);
+            match *op {
+                FloatOp::Const32(value) => hash_f32(value, state),
+                FloatOp::Const64(value) => hash_f64(value, state),
+                _ => {}
+            }
+        }
+        OpType::FloatArrayOp(op) => {
+            core::mem::discriminant(op).hash(state);
+            match op {
+                FloatArrayOp::Const32(values) => {
+                    values.len().hash(state);
+                    values.values().for_each(|value| hash_f32(value, state));
+                }
+                FloatArrayOp::Const64(values) => {
+                    values.len().hash(state);
+                    values.values().for_each(|value| hash_f64(value, state));
+                }
+                FloatArrayOp::Zero { precision } => precision.hash(state),
+                _ => {}
+            }
+        }
+        OpType::ControlFlowOp(cf_op) => {
+            core::mem::discriminant(cf_op.as_ref()).hash(state);
+            match cf_op.as_ref() {
+                ControlFlowOp::For { region } => hash_region(region, state),
+                ControlFlowOp::While { before, after } => {
+                    hash_region(before, state);
+                    hash_region(after, state);
+                }
+                ControlFlowOp::Switch(switch_op) => {
+                    switch_op.branch_count().hash(state);
+                    switch_op
+                        .branches()
+                        .for_each(|branch| hash_region(&branch, state));
+                    switch_op.default_branch().is_some().hash(state);
+                    if let Some(default) = switch_op.default_branch() {
+                        hash_region(&default, state);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec;
+
+    use crate::reader::{ModuleOpPath, ReadJeff};
+    use crate::test::{
+        build_program, edited, entangled_calls, entangled_qs, nested_loop, qubits, set_gate_power,
+    };
+    use crate::{jeff_capnp, Jeff};
+    use rstest::rstest;
+
+    /// Returns the body of the first function of a module being edited.
+    fn first_body(module: jeff_capnp::module::Builder<'_>) -> jeff_capnp::region::Builder<'_> {
+        let function = module.get_functions().unwrap().get(0);
+        let jeff_capnp::function::Which::Definition(def) = function.which().unwrap() else {
+            panic!("Expected a function definition");
+        };
+        def.get_body().unwrap()
+    }
+
+    #[rstest]
+    fn gate_power_changed(entangled_qs: Jeff<'static>) {
+        assert!(entangled_qs.diff(&entangled_qs.module()).is_empty());
+
+        let other = edited(&entangled_qs, |module| set_gate_power(module, 6, 2));
+        assert!(!entangled_qs.module().structurally_eq(&other.module()));
+
+        let diff = entangled_qs.diff(&other.module());
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(
+            diff.changed[0].operations,
+            [ModuleOpPath {
+                function: 0,
+                regions: vec![],
+                op: 6
+            }]
+        );
+        assert!(!diff.changed[0].signature_changed);
+        assert!(!diff.changed[0].boundary_changed);
+    }
+
+    #[test]
+    fn nested_operation_changed() {
+        let (a, b) = (nested_loop(1, 1), nested_loop(1, 2));
+        assert!(a.diff(&nested_loop(1, 1).module()).is_empty());
+
+        let diff = a.diff(&b.module());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(
+            diff.changed[0].operations,
+            [ModuleOpPath {
+                function: 0,
+                regions: vec![(0, 0)],
+                op: 0
+            }]
+        );
+    }
+
+    #[rstest]
+    fn body_targets_swapped(qubits: Jeff<'static>) {
+        let other = edited(&qubits, |module| {
+            let mut targets = first_body(module).get_targets().unwrap();
+            let (first, second) = (targets.get(0), targets.get(1));
+            targets.set(0, second);
+            targets.set(1, first);
+        });
+
+        let diff = qubits.diff(&other.module());
+        assert_eq!(diff.changed.len(), 1);
+        assert!(diff.changed[0].boundary_changed);
+        assert!(diff.changed[0].operations.is_empty());
+        assert!(!qubits.module().eq_ignoring_metadata(&other.module()));
+    }
+
+    #[rstest]
+    fn entrypoint_and_version_changed(entangled_calls: Jeff<'static>) {
+        let other = edited(&entangled_calls, |mut module| module.set_entrypoint(1));
+        let diff = entangled_calls.diff(&other.module());
+        assert!(diff.entrypoint_changed);
+        assert!(!diff.version_changed);
+        assert!(diff.changed.is_empty());
+
+        let other = edited(&entangled_calls, |mut module| {
+            let patch = module.reborrow().get_version_patch();
+            module.set_version_patch(patch + 1);
+        });
+        let diff = entangled_calls.diff(&other.module());
+        assert!(diff.version_changed);
+        assert!(!diff.is_empty());
+    }
+
+    #[rstest]
+    fn metadata_only_changed(entangled_qs: Jeff<'static>) {
+        let other = edited(&entangled_qs, |module| {
+            let function = module.get_functions().unwrap().get(0);
+            let jeff_capnp::function::Which::Definition(def) = function.which().unwrap() else {
+                panic!("Expected a function definition");
+            };
+            let op = def.get_body().unwrap().get_operations().unwrap().get(5);
+            op.init_metadata(1)
+                .get(0)
+                .init_value()
+                .set_as::<capnp::text::Owned>("debug")
+                .unwrap();
+        });
+
+        let (module, other) = (entangled_qs.module(), other.module());
+        assert!(module.eq_ignoring_metadata(&other));
+        assert!(!module.structurally_eq(&other));
+        assert!(module.structurally_eq(&module));
+    }
+
+    #[rstest]
+    fn opaque_metadata_changed(entangled_qs: Jeff<'static>) {
+        let with_type = |width: u8| {
+            edited(&entangled_qs, |module| {
+                first_body(module)
+                    .init_metadata(1)
+                    .get(0)
+                    .init_value()
+                    .init_as::<jeff_capnp::value::Builder<'_>>()
+                    .init_type()
+                    .set_int(width);
+            })
+        };
+        let (a, b) = (with_type(32), with_type(64));
+        assert!(a.module().eq_ignoring_metadata(&b.module()));
+        assert!(a.module().structurally_eq(&with_type(32).module()));
+        assert!(!a.module().structurally_eq(&b.module()));
+    }
+
+    #[test]
+    fn nan_constant_equal() {
+        let nan_const = || {
+            build_program(|mut module| {
+                module.reborrow().init_strings(1).set(0, "main");
+                let function = module.init_functions(1).get(0);
+                let body = function.init_definition().init_body();
+                let op = body.init_operations(1).get(0);
+                op.init_instruction().init_float().set_const64(f64::NAN);
+            })
+        };
+        let (a, b) = (nan_const(), nan_const());
+        assert!(a.diff(&b.module()).is_empty());
+        assert!(a.module().structurally_eq(&b.module()));
+        assert_eq!(a.module().content_hash(), b.module().content_hash());
+    }
+
+    #[test]
+    fn duplicate_names_matched_in_order() {
+        // Two functions named `main`, each with a single Hadamard gate of the
+        // given power.
+        let twins = |powers: [u8; 2]| {
+            build_program(|mut module| {
+                module.reborrow().init_strings(1).set(0, "main");
+                let mut functions = module.init_functions(2);
+                for (idx, power) in powers.into_iter().enumerate() {
+                    let body = functions
+                        .reborrow()
+                        .get(idx as u32)
+                        .init_definition()
+                        .init_body();
+                    let op = body.init_operations(1).get(0);
+                    let mut gate = op.init_instruction().init_qubit().init_gate();
+                    gate.set_power(power);
+                    gate.set_well_known(jeff_capnp::WellKnownGate::H);
+                }
+            })
+        };
+        let (a, b) = (twins([1, 1]), twins([1, 2]));
+        assert!(a.diff(&twins([1, 1]).module()).is_empty());
+
+        let diff = a.diff(&b.module());
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(
+            diff.changed[0].operations,
+            [ModuleOpPath {
+                function: 1,
+                regions: vec![],
+                op: 0
+            }]
+        );
+        assert_ne!(a.module().content_hash(), b.module().content_hash());
+    }
+
+    #[rstest]
+    fn functions_added_and_removed(entangled_qs: Jeff<'static>, entangled_calls: Jeff<'static>) {
+        let diff = entangled_qs.diff(&entangled_calls.module());
+        assert_eq!(diff.removed.len(), entangled_qs.function_count());
+        assert_eq!(diff.added.len(), entangled_calls.function_count());
+        assert!(diff.changed.is_empty());
+    }
+}
@@ -1,10 +1,21 @@
 //! Function definition in a jeff program.
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::Itertools;
+
 use crate::capnp::jeff_capnp;
-use crate::reader::value::{FunctionIOValue, ValueTable};
+use crate::reader::optype::{
+    ControlFlowOp, FloatArrayOp, GateOp, GateOpType, IntArrayOp, OpKind, OpType, QubitOp,
+    QubitRegisterOp, WellKnownGate,
+};
+use crate::reader::value::{FunctionIOValue, ValueId, ValueTable, WireValue};
+use crate::types::{FunctionType, Type};
 
 use super::metadata::sealed::HasMetadataSealed;
 use super::string_table::StringTable;
-use super::{ReadError, Region};
+use super::{NestedOpPath, Operation, ReadError, Region};
 
 /// Function index into the module's function table.
 pub type FunctionId = u32;
@@ -108,6 +119,18 @@ impl<'a> Function<'a> {
             Function::Definition(def) => itertools::Either::Right(def.output_types()),
         }
     }
+
+    /// Returns the input and output types of this function.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::ValueOutOfBounds`] if a function boundary value references an invalid index in the value table.
+    pub fn signature(&self) -> Result<FunctionType, ReadError> {
+        Ok(FunctionType {
+            inputs: self.input_types().map_ok(|v| v.ty()).try_collect()?,
+            outputs: self.output_types().map_ok(|v| v.ty()).try_collect()?,
+        })
+    }
 }
 
 impl<'a> FunctionDefinition<'a> {
@@ -143,6 +166,629 @@ impl<'a> FunctionDefinition<'a> {
     ) -> impl Iterator<Item = Result<FunctionIOValue<'a>, ReadError>> + 'a {
         self.body().targets().map(|v| Ok(v?.into()))
     }
+
+    /// Returns the wire values received as inputs by this function.
+    ///
+    /// Unlike [`FunctionDefinition::input_types`], this keeps the value ids
+    /// of the body's sources.
+    pub fn input_values(&self) -> impl Iterator<Item = Result<WireValue<'a>, ReadError>> + 'a {
+        self.body().sources()
+    }
+
+    /// Returns the wire values returned as outputs by this function.
+    ///
+    /// Unlike [`FunctionDefinition::output_types`], this keeps the value ids
+    /// of the body's targets.
+    pub fn output_values(&self) -> impl Iterator<Item = Result<WireValue<'a>, ReadError>> + 'a {
+        self.body().targets()
+    }
+
+    /// Returns the peak number of qubits that are simultaneously live in this
+    /// function.
+    ///
+    /// This performs a forward pass over the body, starting from the qubits
+    /// received as inputs. Qubits are added by [`QubitOp::Alloc`] and
+    /// [`QubitRegisterOp::Alloc`], and released by frees and destructive
+    /// measurements. Nested regions contribute their own peak on top of the
+    /// qubits live at the enclosing operation. Calls to other functions are not
+    /// followed.
+    ///
+    /// Returns `None` if the size of a qubit register cannot be determined
+    /// statically, e.g. when it is allocated with a non-constant size.
+    pub fn max_live_qubits(&self) -> Option<usize> {
+        let mut budget = QubitBudget::default();
+
+        let mut initial = 0;
+        for value in self.body().sources().filter_map(|v| v.ok()) {
+            match value.ty() {
+                Type::Qubit => initial += 1,
+                Type::QubitRegister { .. } => initial += budget.filled(value)?,
+                _ => {}
+            }
+        }
+
+        let (peak, _) = budget.walk(self.body())?;
+        Some((initial as isize + peak).max(0) as usize)
+    }
+
+    /// Returns the largest number of classical arrays that are live at the
+    /// same time in the function body.
+    ///
+    /// Arrays are created by the constant, `Zero`, and `Create` operations of
+    /// [`IntArrayOp`] and [`FloatArrayOp`]. The output of a `SetIndex`
+    /// operation continues the lifetime of its input array. An array is live
+    /// from the operation creating it up to its last use, inclusive, or up to
+    /// the end of the body if it is returned. Array lengths may be dynamic, so
+    /// this counts arrays rather than bytes.
+    ///
+    /// Only the top-level operations of the body are considered. Arrays passed
+    /// into nested control flow regions are used by the enclosing operation,
+    /// and arrays created inside nested regions are not counted.
+    pub fn peak_classical_array_allocs(&self) -> usize {
+        let body = self.body();
+        let end = body.operation_count();
+        // Live range of each array, and the array behind each value id.
+        let mut lifetimes: Vec<(usize, usize)> = Vec::new();
+        let mut arrays: BTreeMap<ValueId, usize> = BTreeMap::new();
+        for (idx, op) in body.operations().enumerate() {
+            let inputs: Vec<Option<usize>> = op
+                .inputs()
+                .map(|value| arrays.get(&value.ok()?.id()).copied())
+                .collect();
+            for &array in inputs.iter().flatten() {
+                lifetimes[array].1 = idx;
+            }
+
+            let array = match op.op_type() {
+                OpType::IntArrayOp(IntArrayOp::SetIndex)
+                | OpType::FloatArrayOp(FloatArrayOp::SetIndex) => inputs.first().copied().flatten(),
+                OpType::IntArrayOp(IntArrayOp::GetIndex | IntArrayOp::Length)
+                | OpType::FloatArrayOp(FloatArrayOp::GetIndex | FloatArrayOp::Length) => None,
+                OpType::IntArrayOp(_) | OpType::FloatArrayOp(_) => {
+                    lifetimes.push((idx, idx));
+                    Some(lifetimes.len() - 1)
+                }
+                _ => None,
+            };
+            if let (Some(array), Some(Ok(output))) = (array, op.output(0)) {
+                arrays.insert(output.id(), array);
+            }
+        }
+        for value in body.targets().filter_map(Result::ok) {
+            if let Some(&array) = arrays.get(&value.id()) {
+                lifetimes[array].1 = end;
+            }
+        }
+
+        (0..=end)
+            .map(|idx| {
+                lifetimes
+                    .iter()
+                    .filter(|&&(start, stop)| start <= idx && idx <= stop)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of operations in this function by category.
+    ///
+    /// Operations inside nested control flow regions are included. Calls to
+    /// other functions are counted but not followed.
+    pub fn op_category_counts(&self) -> CategoryCounts {
+        let mut counts = CategoryCounts::default();
+        counts.add_region(self.body());
+        counts
+    }
+
+    /// Returns `true` if this function contains no qubit or qubit register
+    /// operations, so that it can be evaluated entirely classically.
+    ///
+    /// Operations inside nested control flow regions are included. Calls to
+    /// other functions are not followed, so a function calling a quantum
+    /// function is still reported as classical. Use
+    /// [`Region::called_functions_recursive`] to check the callees.
+    pub fn is_classical(&self) -> bool {
+        self.body().fold_operations(true, |classical, op| {
+            classical
+                && !matches!(
+                    op.op_type(),
+                    OpType::QubitOp(_) | OpType::QubitRegisterOp(_)
+                )
+        })
+    }
+
+    /// Returns the number of gates in this function acting on at least
+    /// `min_qubits` qubits, including control qubits.
+    ///
+    /// Gates inside nested control flow regions are counted once, regardless
+    /// of how many times the region is executed. Calls to other functions are
+    /// not followed. With `min_qubits = 2` this is the two-qubit gate count.
+    pub fn multi_qubit_gate_count(&self, min_qubits: usize) -> usize {
+        self.body()
+            .fold_operations(0, |count, op| match op.op_type() {
+                OpType::QubitOp(QubitOp::Gate(gate)) if gate.num_qubits() >= min_qubits => {
+                    count + 1
+                }
+                _ => count,
+            })
+    }
+
+    /// Returns the constant parameters of the gates in this function, such as
+    /// rotation angles.
+    ///
+    /// Gates are visited in the order of [`Region::fold_operations`], so gates
+    /// inside nested regions are included. Each parameter produced by a float
+    /// constant contributes its value, while parameters computed at runtime
+    /// are skipped. The adjoint and power modifiers of the gates are not
+    /// applied.
+    pub fn rotation_angles(&self) -> Vec<f64> {
+        let body = self.body();
+        let producers: BTreeMap<ValueId, Operation<'_>> =
+            body.fold_operations(BTreeMap::new(), |mut producers, op| {
+                for output in op.outputs().filter_map(Result::ok) {
+                    producers.entry(output.id()).or_insert(*op);
+                }
+                producers
+            });
+        body.fold_operations(Vec::new(), |mut angles, op| {
+            let OpType::QubitOp(QubitOp::Gate(gate)) = op.op_type() else {
+                return angles;
+            };
+            let params = gate.num_qubits()..gate.num_qubits() + gate.num_params();
+            angles.extend(params.filter_map(|idx| {
+                let value = op.input(idx)?.ok()?.id();
+                let OpType::FloatOp(float_op) = producers.get(&value)?.op_type() else {
+                    return None;
+                };
+                float_op.const_with_precision().map(|(_, angle)| angle)
+            }));
+            angles
+        })
+    }
+
+    /// Returns the indices of the pairs of adjacent gates in the function body
+    /// that cancel each other out.
+    ///
+    /// A pair is reported when every qubit output of the first gate is
+    /// consumed by the second gate at the same qubit port, so no other
+    /// operation acts on those qubits in between, and the second gate is the
+    /// adjoint of the first with the same parameter values. Self-inverse gates
+    /// such as `H` followed by `H` are included.
+    ///
+    /// Only the top-level operations of the body are considered, so each pair
+    /// holds the indices of the two gates in the body. Pairs may overlap, as
+    /// in a sequence of three `H` gates on the same qubit. Returns an empty
+    /// list if the body references an invalid value.
+    pub fn find_inverse_pairs(&self) -> Vec<(usize, usize)> {
+        let Ok(ops) = self
+            .body()
+            .operations_with_wires()
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            return Vec::new();
+        };
+        let consumers: BTreeMap<ValueId, (usize, usize)> = ops
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, op)| {
+                op.inputs
+                    .iter()
+                    .enumerate()
+                    .map(move |(port, value)| (value.id(), (idx, port)))
+            })
+            .collect();
+
+        let mut pairs = Vec::new();
+        for (idx, first) in ops.iter().enumerate() {
+            let OpType::QubitOp(QubitOp::Gate(gate)) = first.operation.op_type() else {
+                continue;
+            };
+            let Some(&(next, 0)) = first.outputs.first().and_then(|v| consumers.get(&v.id()))
+            else {
+                continue;
+            };
+            let second = &ops[next];
+            let OpType::QubitOp(QubitOp::Gate(next_gate)) = second.operation.op_type() else {
+                continue;
+            };
+            if !is_inverse_gate(&gate, &next_gate) {
+                continue;
+            }
+
+            let qubits = gate.num_qubits();
+            let (Some(params), Some(next_params)) =
+                (first.inputs.get(qubits..), second.inputs.get(qubits..))
+            else {
+                continue;
+            };
+            let wires_match = first.outputs.len() == qubits
+                && first
+                    .outputs
+                    .iter()
+                    .zip(&second.inputs)
+                    .all(|(out, inp)| out.id() == inp.id());
+            let params_match = params
+                .iter()
+                .map(WireValue::id)
+                .eq(next_params.iter().map(WireValue::id));
+            if wires_match && params_match {
+                pairs.push((idx, next));
+            }
+        }
+        pairs
+    }
+
+    /// Returns the graph of interactions between the logical qubits of this
+    /// function.
+    ///
+    /// Qubits are traced through the top-level operations of the body,
+    /// starting from the qubit inputs of the function and from
+    /// [`QubitOp::Alloc`] operations, and following gates, resets, and
+    /// non-destructive measurements. Qubits produced by any other operation,
+    /// such as qubit register extractions, control flow, or function calls,
+    /// cannot be traced statically and are grouped as
+    /// [`LogicalQubit::Unknown`]. Gates inside nested control flow regions are
+    /// not counted.
+    pub fn interaction_graph(&self) -> InteractionGraph {
+        let mut graph = InteractionGraph::default();
+        let mut qubits: BTreeMap<ValueId, LogicalQubit> = BTreeMap::new();
+        for (idx, value) in self.input_values().enumerate() {
+            if let Some(value) = value.ok().filter(|v| v.ty() == Type::Qubit) {
+                qubits.insert(value.id(), LogicalQubit::Input(idx));
+                graph.qubits.insert(LogicalQubit::Input(idx));
+            }
+        }
+
+        for (idx, op) in self.body().operations().enumerate() {
+            let inputs: Vec<LogicalQubit> = op
+                .inputs()
+                .filter_map(|v| qubits.get(&v.ok()?.id()).copied())
+                .collect();
+            let outputs = match op.op_type() {
+                OpType::QubitOp(QubitOp::Alloc) => vec![LogicalQubit::Alloc(idx)],
+                OpType::QubitOp(QubitOp::Reset | QubitOp::MeasureNd) => inputs.clone(),
+                OpType::QubitOp(QubitOp::Gate(gate)) => {
+                    if gate.num_qubits() >= 2 {
+                        graph.add_interactions(&inputs);
+                    }
+                    inputs.clone()
+                }
+                _ => Vec::new(),
+            };
+
+            let mut outputs = outputs.into_iter();
+            for value in op.outputs().filter_map(Result::ok) {
+                if value.ty() != Type::Qubit {
+                    continue;
+                }
+                let qubit = outputs.next().unwrap_or(LogicalQubit::Unknown);
+                graph.qubits.insert(qubit);
+                qubits.insert(value.id(), qubit);
+            }
+        }
+        graph
+    }
+
+    /// Returns the operations in this function grouped by their [`OpKind`].
+    ///
+    /// Operations inside nested control flow regions are included, at their
+    /// own path. Each group lists its operations in the order of
+    /// [`Region::nested_operations`].
+    ///
+    /// This returns a [`BTreeMap`] rather than a `HashMap` so that it is
+    /// available without the `std` feature.
+    pub fn group_by_kind(&self) -> BTreeMap<OpKind, Vec<NestedOpPath>> {
+        let mut groups: BTreeMap<OpKind, Vec<NestedOpPath>> = BTreeMap::new();
+        for (path, op) in self.body().nested_operations() {
+            groups.entry(op.op_type().kind()).or_default().push(path);
+        }
+        groups
+    }
+
+    /// Returns the indices of the function inputs that are never used.
+    ///
+    /// An input is used if it is consumed by an operation in the body, or in
+    /// any nested region, or if it is forwarded directly to an output. Unused
+    /// qubit inputs usually indicate a bug, as qubits are linear values.
+    ///
+    /// Inputs that reference an invalid value are ignored.
+    pub fn unused_inputs(&self) -> Vec<usize> {
+        let mut used = BTreeSet::new();
+        collect_used_values(self.body(), &mut used);
+        self.input_values()
+            .enumerate()
+            .filter_map(|(idx, value)| {
+                let value = value.ok()?;
+                (!used.contains(&value.id())).then_some(idx)
+            })
+            .collect()
+    }
+
+    /// Returns the operation that defines `value`, along with the index of
+    /// the output port producing it.
+    ///
+    /// Operations in nested control flow regions are searched too. Returns
+    /// `None` if no operation produces the value, i.e. when it is a function
+    /// input, a source of a nested region, or not used in this function.
+    pub fn value_producer(&self, value: ValueId) -> Option<(NestedOpPath, usize)> {
+        self.body().nested_operations().find_map(|(path, op)| {
+            let port = op
+                .outputs()
+                .position(|output| output.is_ok_and(|output| output.id() == value))?;
+            Some((path, port))
+        })
+    }
+
+    /// Returns the operations of the function body between `from` and `to`,
+    /// inclusive, in region order.
+    ///
+    /// Both paths must be in the same region, which may be nested in control
+    /// flow operations. Returns `None` if they are in different regions, if
+    /// either does not refer to an operation, or if `from` comes after `to`.
+    pub fn slice_between(
+        &self,
+        from: &NestedOpPath,
+        to: &NestedOpPath,
+    ) -> Option<Vec<NestedOpPath>> {
+        if from.regions != to.regions || from.op > to.op {
+            return None;
+        }
+        let region = self.body().nested_region(&from.regions)?;
+        if to.op >= region.operation_count() {
+            return None;
+        }
+        let slice = (from.op..=to.op)
+            .map(|op| NestedOpPath {
+                regions: from.regions.clone(),
+                op,
+            })
+            .collect();
+        Some(slice)
+    }
+
+    /// Returns the measurements in the function body, with the output port
+    /// and type of the classical result they produce.
+    ///
+    /// Both destructive and non-destructive measurements are included, in the
+    /// order of [`Region::nested_operations`], so measurements inside nested
+    /// control flow regions are reported at their own path. Measurements
+    /// whose outputs cannot be read are skipped.
+    pub fn measurement_targets(&self) -> Vec<(NestedOpPath, usize, Type)> {
+        self.body()
+            .nested_operations()
+            .filter(|(_, op)| matches!(op.op_type(), OpType::QubitOp(q) if q.is_measurement()))
+            .filter_map(|(path, op)| {
+                op.outputs()
+                    .enumerate()
+                    .find_map(|(port, value)| match value.ok()?.ty() {
+                        Type::Qubit => None,
+                        ty => Some((path.clone(), port, ty)),
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the measurement feedforward dependencies in the function body.
+    ///
+    /// Each pair links a measurement, as returned by
+    /// [`FunctionDefinition::measurement_targets`], to a
+    /// [`ControlFlowOp::Switch`] whose selector depends on its classical
+    /// result, either directly or transitively through integer and float
+    /// operations. Both are identified by their path in the body.
+    ///
+    /// Operations in nested control flow regions are included, but results
+    /// are not traced through the boundaries of the regions. Pairs are sorted
+    /// and contain no duplicates.
+    pub fn feedforward_edges(&self) -> Vec<(NestedOpPath, NestedOpPath)> {
+        let mut sources: BTreeMap<ValueId, BTreeSet<NestedOpPath>> = BTreeMap::new();
+        let body = self.body();
+        for (path, port, _) in self.measurement_targets() {
+            if let Some(Ok(value)) = body.operation_at(&path).and_then(|op| op.output(port)) {
+                sources.entry(value.id()).or_default().insert(path);
+            }
+        }
+
+        let mut edges = BTreeSet::new();
+        for (path, op) in body.nested_operations() {
+            let input_sources = |range: core::ops::Range<usize>| {
+                let mut found = BTreeSet::new();
+                for value in range.filter_map(|i| op.input(i)?.ok()) {
+                    found.extend(sources.get(&value.id()).into_iter().flatten().cloned());
+                }
+                found
+            };
+            match op.op_type() {
+                OpType::ControlFlowOp(cf_op) if matches!(*cf_op, ControlFlowOp::Switch(_)) => {
+                    let found = input_sources(0..1);
+                    edges.extend(found.into_iter().map(|m| (m, path.clone())));
+                }
+                OpType::IntOp(_)
+                | OpType::IntArrayOp(_)
+                | OpType::FloatOp(_)
+                | OpType::FloatArrayOp(_) => {
+                    let found = input_sources(0..op.input_count());
+                    if found.is_empty() {
+                        continue;
+                    }
+                    for value in op.outputs().filter_map(Result::ok) {
+                        sources
+                            .entry(value.id())
+                            .or_default()
+                            .extend(found.iter().cloned());
+                    }
+                }
+                _ => {}
+            }
+        }
+        edges.into_iter().collect()
+    }
+
+    /// Returns the number of iterations of a [`ControlFlowOp::For`] loop in
+    /// this function, if its bounds are constant.
+    ///
+    /// The start, stop, and step inputs of the loop must be produced by
+    /// integer constant operations in this function, and are interpreted as
+    /// signed integers.
+    ///
+    /// Returns `None` if `op` is not a `For` loop, if any of its bounds is not
+    /// a constant, or if the step is zero.
+    pub fn static_iteration_count(&self, op: &Operation<'_>) -> Option<u64> {
+        let OpType::ControlFlowOp(cf_op) = op.op_type() else {
+            return None;
+        };
+        let ControlFlowOp::For { .. } = *cf_op else {
+            return None;
+        };
+
+        let bound = |idx: usize| -> Option<i128> {
+            let value = op.input(idx)?.ok()?.id();
+            let OpType::IntOp(int_op) = find_producer(self.body(), value)?.op_type() else {
+                return None;
+            };
+            let bits = int_op.const_width()? as u32;
+            let shift = 64 - bits;
+            Some((((int_op.const_value_u64()? << shift) as i64) >> shift) as i128)
+        };
+        let (start, stop, step) = (bound(0)?, bound(1)?, bound(2)?);
+
+        let count = match step {
+            0 => return None,
+            step if step > 0 => (stop - start + step - 1) / step,
+            step => (start - stop - step - 1) / -step,
+        };
+        Some(count.max(0) as u64)
+    }
+}
+
+/// Returns the operation that produces `value` in `region` or its nested
+/// regions.
+fn find_producer(region: Region<'_>, value: ValueId) -> Option<Operation<'_>> {
+    region.fold_operations(None, |found, op| {
+        found.or_else(|| {
+            op.outputs()
+                .any(|v| v.is_ok_and(|v| v.id() == value))
+                .then_some(*op)
+        })
+    })
+}
+
+/// Returns `true` if applying `second` right after `first` on the same wires
+/// is the identity, for [`FunctionDefinition::find_inverse_pairs`].
+fn is_inverse_gate(first: &GateOp<'_>, second: &GateOp<'_>) -> bool {
+    if *second == first.adjoint() {
+        return true;
+    }
+    let self_inverse = matches!(
+        first.gate_type,
+        GateOpType::WellKnown(
+            WellKnownGate::I
+                | WellKnownGate::X
+                | WellKnownGate::Y
+                | WellKnownGate::Z
+                | WellKnownGate::H
+                | WellKnownGate::Swap
+        )
+    );
+    self_inverse && first.power == 1 && first == second
+}
+
+/// Adds the values consumed by the operations and targets of `region` and its
+/// nested regions to `used`.
+fn collect_used_values(region: Region<'_>, used: &mut BTreeSet<ValueId>) {
+    used.extend(region.targets().filter_map(|v| v.ok()).map(|v| v.id()));
+    region.fold_operations((), |(), op| {
+        used.extend(op.inputs().filter_map(|v| v.ok()).map(|v| v.id()));
+        if let OpType::ControlFlowOp(cf_op) = op.op_type() {
+            for nested in cf_op.regions() {
+                used.extend(nested.targets().filter_map(|v| v.ok()).map(|v| v.id()));
+            }
+        }
+    });
+}
+
+/// Number of operations of each category in a function.
+///
+/// See [`FunctionDefinition::op_category_counts`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct CategoryCounts {
+    /// Operations on qubits and qubit registers.
+    pub quantum: usize,
+    /// Operations on integers, floats, and their arrays.
+    pub classical: usize,
+    /// Control flow operations.
+    pub control_flow: usize,
+    /// Function calls.
+    pub calls: usize,
+}
+
+impl CategoryCounts {
+    /// Returns the total number of operations counted.
+    pub fn total(&self) -> usize {
+        self.quantum + self.classical + self.control_flow + self.calls
+    }
+
+    /// Adds the operations in `region` and its nested regions to the counts.
+    fn add_region(&mut self, region: Region<'_>) {
+        region.fold_operations((), |(), op| match op.op_type() {
+            OpType::QubitOp(_) | OpType::QubitRegisterOp(_) => self.quantum += 1,
+            OpType::IntOp(_)
+            | OpType::IntArrayOp(_)
+            | OpType::FloatOp(_)
+            | OpType::FloatArrayOp(_) => self.classical += 1,
+            OpType::FuncOp(_) => self.calls += 1,
+            OpType::ControlFlowOp(_) => self.control_flow += 1,
+        });
+    }
+}
+
+/// A logical qubit in an [`InteractionGraph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum LogicalQubit {
+    /// A qubit received as the function input with the given index.
+    Input(usize),
+    /// A qubit allocated by the top-level operation with the given index.
+    Alloc(usize),
+    /// Any qubit that cannot be traced statically.
+    Unknown,
+}
+
+/// Number of multi-qubit gates acting on each pair of logical qubits in a
+/// function.
+///
+/// See [`FunctionDefinition::interaction_graph`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InteractionGraph {
+    /// The logical qubits in the function.
+    pub qubits: BTreeSet<LogicalQubit>,
+    /// Number of gates acting on each pair of qubits, keyed by the pair in
+    /// ascending order.
+    ///
+    /// Gates between two untraced qubits are recorded under
+    /// `(LogicalQubit::Unknown, LogicalQubit::Unknown)`.
+    pub edges: BTreeMap<(LogicalQubit, LogicalQubit), usize>,
+}
+
+impl InteractionGraph {
+    /// Returns the number of gates acting on both `a` and `b`.
+    pub fn interactions(&self, a: LogicalQubit, b: LogicalQubit) -> usize {
+        let key = if a <= b { (a, b) } else { (b, a) };
+        self.edges.get(&key).copied().unwrap_or(0)
+    }
+
+    /// Records an interaction between every pair of qubits acted on by a gate.
+    fn add_interactions(&mut self, qubits: &[LogicalQubit]) {
+        for (&a, &b) in qubits.iter().tuple_combinations() {
+            if a == b && a != LogicalQubit::Unknown {
+                continue;
+            }
+            let key = if a <= b { (a, b) } else { (b, a) };
+            *self.edges.entry(key).or_default() += 1;
+        }
+    }
 }
 
 impl<'a> FunctionDeclaration<'a> {
@@ -213,3 +859,724 @@ impl<'a> HasMetadataSealed for FunctionDefinition<'a> {
             .expect("Metadata should be present")
     }
 }
+
+/// Running state for counting live qubits over a function body.
+///
+/// Value ids are unique across all the regions in a function, so the state can
+/// be shared while walking nested regions.
+#[derive(Debug, Default)]
+struct QubitBudget {
+    /// Integer constants defined so far, used to resolve register sizes.
+    consts: BTreeMap<ValueId, u64>,
+    /// Number of filled slots in each qubit register value, when known.
+    filled: BTreeMap<ValueId, usize>,
+}
+
+impl QubitBudget {
+    /// Walks `region`, returning the peak and the net change in live qubits
+    /// relative to the count at the region's entry.
+    fn walk(&mut self, region: Region<'_>) -> Option<(isize, isize)> {
+        let mut live: isize = 0;
+        let mut peak: isize = 0;
+
+        for op in region.operations() {
+            let inputs: Vec<WireValue<'_>> = op.inputs().filter_map(|v| v.ok()).collect();
+            let outputs: Vec<WireValue<'_>> = op.outputs().filter_map(|v| v.ok()).collect();
+
+            match op.op_type() {
+                OpType::IntOp(int_op) => {
+                    if let (Some(value), Some(out)) = (int_op.const_value_u64(), outputs.first()) {
+                        self.consts.insert(out.id(), value);
+                    }
+                }
+                OpType::QubitOp(QubitOp::Alloc) => live += 1,
+                OpType::QubitOp(QubitOp::Free | QubitOp::FreeZero | QubitOp::Measure) => live -= 1,
+                OpType::QubitRegisterOp(qureg_op) => {
+                    live += self.qureg_op(qureg_op, &inputs, &outputs)?;
+                }
+                OpType::ControlFlowOp(cf_op) => {
+                    let (nested_peak, nested_net) = match cf_op.as_ref() {
+                        ControlFlowOp::For { region } => self.walk(*region)?,
+                        ControlFlowOp::While { before, after } => {
+                            let (before_peak, before_net) = self.walk(*before)?;
+                            let (after_peak, after_net) = self.walk(*after)?;
+                            (
+                                before_peak.max(before_net + after_peak),
+                                before_net + after_net,
+                            )
+                        }
+                        ControlFlowOp::Switch(switch_op) => {
+                            let mut result = (0, 0);
+                            for branch in switch_op.branches().chain(switch_op.default_branch()) {
+                                let (branch_peak, branch_net) = self.walk(branch)?;
+                                result = (result.0.max(branch_peak), result.1.max(branch_net));
+                            }
+                            result
+                        }
+                    };
+                    peak = peak.max(live + nested_peak);
+                    live += nested_net;
+                }
+                _ => {}
+            }
+
+            peak = peak.max(live);
+        }
+
+        Some((peak, live))
+    }
+
+    /// Tracks the register sizes through a qubit register operation, returning
+    /// the change in live qubits.
+    fn qureg_op(
+        &mut self,
+        qureg_op: QubitRegisterOp,
+        inputs: &[WireValue<'_>],
+        outputs: &[WireValue<'_>],
+    ) -> Option<isize> {
+        let out = outputs.first().map(|v| v.id());
+        let filled = match qureg_op {
+            QubitRegisterOp::Alloc => {
+                let size = inputs
+                    .first()
+                    .and_then(|v| self.consts.get(&v.id()))
+                    .map(|&n| n as usize)
+                    .or_else(|| outputs.first().and_then(|&v| self.filled(v)))?;
+                if let Some(out) = out {
+                    self.filled.insert(out, size);
+                }
+                return Some(size as isize);
+            }
+            QubitRegisterOp::Free | QubitRegisterOp::FreeZero => {
+                let size = self.filled(*inputs.first()?)?;
+                return Some(-(size as isize));
+            }
+            QubitRegisterOp::ExtractIndex => inputs
+                .first()
+                .and_then(|&v| self.filled(v))
+                .map(|n| n.saturating_sub(1)),
+            QubitRegisterOp::InsertIndex => {
+                inputs.first().and_then(|&v| self.filled(v)).map(|n| n + 1)
+            }
+            QubitRegisterOp::Length => inputs.first().and_then(|&v| self.filled(v)),
+            QubitRegisterOp::Join => match inputs {
+                [a, b] => self.filled(*a).zip(self.filled(*b)).map(|(a, b)| a + b),
+                _ => None,
+            },
+            QubitRegisterOp::Create => Some(inputs.len()),
+            _ => None,
+        };
+        if let (Some(out), Some(filled)) = (out, filled) {
+            self.filled.insert(out, filled);
+        }
+        Some(0)
+    }
+
+    /// Returns the number of filled slots in a qubit register value, if known.
+    ///
+    /// Falls back to the static length in the register's type.
+    fn filled(&self, value: WireValue<'_>) -> Option<usize> {
+        if let Some(&filled) = self.filled.get(&value.id()) {
+            return Some(filled);
+        }
+        match value.ty() {
+            Type::QubitRegister { length } => length.map(|l| l as usize),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::jeff_capnp;
+    use crate::reader::HasMetadata;
+    use crate::reader::ReadJeff;
+    use crate::test::{
+        build_program, catalyst_simple, definition, entangled_calls, entangled_qs, nested_loop,
+    };
+    use crate::Jeff;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::entangled_qs(entangled_qs(), 5)]
+    #[case::catalyst_simple(catalyst_simple(), 5)]
+    fn max_live_qubits(#[case] program: Jeff<'static>, #[case] expected: usize) {
+        let main = definition(&program, 0);
+        assert_eq!(main.max_live_qubits(), Some(expected));
+    }
+
+    #[test]
+    fn io_values() {
+        let program = build_program(|mut module| {
+            let mut strings = module.reborrow().init_strings(4);
+            strings.set(0, "main");
+            strings.set(1, "name");
+            strings.set(2, "theta");
+            strings.set(3, "q");
+
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(2);
+            for (i, arg) in ["theta", "q"].into_iter().enumerate() {
+                let mut value = values.reborrow().get(i as u32);
+                let mut meta = value.reborrow().init_metadata(1).get(0);
+                meta.set_name(1);
+                meta.init_value().set_as::<capnp::text::Owned>(arg).unwrap();
+                match i {
+                    0 => value
+                        .init_type()
+                        .set_float(jeff_capnp::FloatPrecision::Float64),
+                    _ => value.init_type().set_qubit(()),
+                }
+            }
+            let mut body = def.init_body();
+            let mut sources = body.reborrow().init_sources(2);
+            sources.set(0, 0);
+            sources.set(1, 1);
+            body.init_targets(1).set(0, 1);
+        });
+
+        let main = definition(&program, 0);
+        let arg_name = |v: WireValue<'_>| {
+            v.metadata_entries()
+                .find(|m| m.name() == "name")
+                .and_then(|m| m.value_str().map(str::to_string))
+        };
+        let inputs: Vec<_> = main
+            .input_values()
+            .map(|v| {
+                let v = v.unwrap();
+                (v.id(), arg_name(v))
+            })
+            .collect();
+        assert_eq!(
+            inputs,
+            [(0, Some("theta".to_string())), (1, Some("q".to_string()))]
+        );
+        let outputs: Vec<_> = main.output_values().map(|v| v.unwrap().id()).collect();
+        assert_eq!(outputs, [1]);
+    }
+
+    #[test]
+    fn unused_inputs() {
+        // Input 0 is ignored, input 1 is freed, and input 2 is returned.
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(3);
+            for i in 0..3 {
+                values.reborrow().get(i).init_type().set_qubit(());
+            }
+            let mut body = def.init_body();
+            let mut sources = body.reborrow().init_sources(3);
+            for i in 0..3 {
+                sources.set(i, i);
+            }
+            body.reborrow().init_targets(1).set(0, 2);
+            let mut op = body.init_operations(1).get(0);
+            op.reborrow().init_inputs(1).set(0, 1);
+            op.init_instruction().init_qubit().set_free(());
+        });
+
+        let main = definition(&program, 0);
+        assert_eq!(main.unused_inputs(), [0]);
+    }
+
+    #[rstest]
+    fn signature(entangled_calls: Jeff<'static>) {
+        let module = entangled_calls.module();
+        let signatures: Vec<_> = module.functions().map(|f| f.signature().unwrap()).collect();
+        assert_eq!(
+            signatures[0],
+            FunctionType {
+                inputs: vec![],
+                outputs: vec![Type::Int { bits: 1 }],
+            }
+        );
+        assert_eq!(signatures[1], FunctionType::default());
+        assert_eq!(signatures[2].outputs, [Type::Int { bits: 32 }]);
+        assert_eq!(signatures[3].inputs, [Type::Int { bits: 1 }]);
+
+        let display: Vec<_> = signatures.iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            display,
+            ["() -> (Int1)", "() -> ()", "() -> (Int32)", "(Int1) -> ()"]
+        );
+    }
+
+    #[rstest]
+    fn op_category_counts(entangled_calls: Jeff<'static>) {
+        let main = definition(&entangled_calls, 0);
+        let counts = main.op_category_counts();
+        assert_eq!(counts.quantum, 15);
+        assert_eq!(counts.classical, 31);
+        assert_eq!(counts.control_flow, 0);
+        assert_eq!(counts.calls, 0);
+        assert_eq!(counts.total(), main.body().operation_count());
+
+        let wrapper = definition(&entangled_calls, 1);
+        let counts = wrapper.op_category_counts();
+        assert_eq!(counts.calls, 1);
+        assert_eq!(counts.total(), 1);
+    }
+
+    #[rstest]
+    fn multi_qubit_gate_count(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+        // One Hadamard followed by four CNOTs.
+        assert_eq!(main.multi_qubit_gate_count(1), 5);
+        assert_eq!(main.multi_qubit_gate_count(2), 4);
+        assert_eq!(main.multi_qubit_gate_count(3), 0);
+    }
+
+    #[rstest]
+    fn is_classical(entangled_calls: Jeff<'static>) {
+        let sample = definition(&entangled_calls, 0);
+        assert!(!sample.is_classical());
+
+        // x = 1 + 2
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(3);
+            for i in 0..3 {
+                values.reborrow().get(i).init_type().set_int(32);
+            }
+            let mut ops = def.init_body().init_operations(3);
+            for i in 0..2 {
+                let mut op = ops.reborrow().get(i);
+                op.reborrow().init_outputs(1).set(0, i);
+                op.init_instruction().init_int().set_const32(i + 1);
+            }
+            let mut add = ops.get(2);
+            let mut inputs = add.reborrow().init_inputs(2);
+            inputs.set(0, 0);
+            inputs.set(1, 1);
+            add.reborrow().init_outputs(1).set(0, 2);
+            add.init_instruction().init_int().set_add(());
+        });
+        let main = definition(&program, 0);
+        assert!(main.is_classical());
+    }
+
+    #[test]
+    fn rotation_angles() {
+        // q = rz(q, 0.5); q = rz(q, 1.5); q = rz(q, theta)
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(7);
+            for i in 0..7 {
+                let mut ty = values.reborrow().get(i).init_type();
+                match i {
+                    0..=2 => ty.set_float(jeff_capnp::FloatPrecision::Float64),
+                    _ => ty.set_qubit(()),
+                }
+            }
+
+            let mut body = def.init_body();
+            let mut sources = body.reborrow().init_sources(2);
+            sources.set(0, 3);
+            sources.set(1, 2);
+            body.reborrow().init_targets(1).set(0, 6);
+            let mut ops = body.init_operations(5);
+            for (i, angle) in [0.5, 1.5].into_iter().enumerate() {
+                let mut constant = ops.reborrow().get(i as u32);
+                constant.reborrow().init_outputs(1).set(0, i as u32);
+                constant.init_instruction().init_float().set_const64(angle);
+            }
+            for (i, angle) in [0, 1, 2].into_iter().enumerate() {
+                let mut rz = ops.reborrow().get(i as u32 + 2);
+                let mut inputs = rz.reborrow().init_inputs(2);
+                inputs.set(0, i as u32 + 3);
+                inputs.set(1, angle);
+                rz.reborrow().init_outputs(1).set(0, i as u32 + 4);
+                rz.init_instruction()
+                    .init_qubit()
+                    .init_gate()
+                    .set_well_known(jeff_capnp::WellKnownGate::Rz);
+            }
+        });
+
+        let main = definition(&program, 0);
+        assert_eq!(main.rotation_angles(), [0.5, 1.5]);
+    }
+
+    #[rstest]
+    #[case::cancelling(jeff_capnp::WellKnownGate::H, vec![(0, 1)])]
+    #[case::not_cancelling(jeff_capnp::WellKnownGate::X, vec![])]
+    fn find_inverse_pairs(
+        #[case] second: jeff_capnp::WellKnownGate,
+        #[case] expected: Vec<(usize, usize)>,
+    ) {
+        // q = h(q); q = second(q)
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(3);
+            for i in 0..3 {
+                values.reborrow().get(i).init_type().set_qubit(());
+            }
+
+            let mut body = def.init_body();
+            body.reborrow().init_sources(1).set(0, 0);
+            body.reborrow().init_targets(1).set(0, 2);
+            let mut ops = body.init_operations(2);
+            for (i, gate) in [jeff_capnp::WellKnownGate::H, second]
+                .into_iter()
+                .enumerate()
+            {
+                let mut op = ops.reborrow().get(i as u32);
+                op.reborrow().init_inputs(1).set(0, i as u32);
+                op.reborrow().init_outputs(1).set(0, i as u32 + 1);
+                op.init_instruction()
+                    .init_qubit()
+                    .init_gate()
+                    .set_well_known(gate);
+            }
+        });
+
+        let main = definition(&program, 0);
+        assert_eq!(main.find_inverse_pairs(), expected);
+    }
+
+    #[rstest]
+    fn interaction_graph(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+        let graph = main.interaction_graph();
+        let qubits: Vec<_> = (0..5).map(LogicalQubit::Alloc).collect();
+        assert_eq!(graph.qubits, qubits.iter().copied().collect());
+
+        // The CNOTs entangle each qubit with the next one.
+        let edges: BTreeMap<_, _> = qubits
+            .iter()
+            .copied()
+            .tuple_windows()
+            .map(|pair| (pair, 1))
+            .collect();
+        assert_eq!(graph.edges, edges);
+        assert_eq!(graph.interactions(qubits[1], qubits[0]), 1);
+        assert_eq!(graph.interactions(qubits[0], qubits[2]), 0);
+    }
+
+    #[rstest]
+    fn value_producer(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+        let measure = main.body().operation(11);
+        let bit = measure.output(0).unwrap().unwrap().id();
+        let at = |op| NestedOpPath {
+            regions: vec![],
+            op,
+        };
+        assert_eq!(main.value_producer(bit), Some((at(11), 0)));
+
+        let qubit = measure.input(0).unwrap().unwrap().id();
+        // The measured qubit is the control output of the first CNOT.
+        assert_eq!(main.value_producer(qubit), Some((at(6), 0)));
+        assert_eq!(main.value_producer(ValueId::MAX), None);
+    }
+
+    #[test]
+    fn value_producer_nested() {
+        // for i in start..stop by step { q = h(q) }
+        let program = nested_loop(1, 1);
+        let main = definition(&program, 0);
+        let nested = NestedOpPath {
+            regions: vec![(0, 0)],
+            op: 0,
+        };
+        assert_eq!(main.value_producer(6), Some((nested, 0)));
+        // Region sources have no producing operation.
+        assert_eq!(main.value_producer(5), None);
+        assert_eq!(main.value_producer(3), None);
+    }
+
+    #[test]
+    fn feedforward_edges() {
+        // b = measure(q); c = not(b); switch c {}; d = const(true); switch d {}
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(4);
+            values.reborrow().get(0).init_type().set_qubit(());
+            for i in 1..4 {
+                values.reborrow().get(i).init_type().set_int(1);
+            }
+
+            let mut body = def.init_body();
+            body.reborrow().init_sources(1).set(0, 0);
+            let mut ops = body.init_operations(5);
+            let mut measure = ops.reborrow().get(0);
+            measure.reborrow().init_inputs(1).set(0, 0);
+            measure.reborrow().init_outputs(1).set(0, 1);
+            measure.init_instruction().init_qubit().set_measure(());
+            let mut not = ops.reborrow().get(1);
+            not.reborrow().init_inputs(1).set(0, 1);
+            not.reborrow().init_outputs(1).set(0, 2);
+            not.init_instruction().init_int().set_not(());
+            let mut constant = ops.reborrow().get(3);
+            constant.reborrow().init_outputs(1).set(0, 3);
+            constant.init_instruction().init_int().set_const1(true);
+            for (i, selector) in [(2, 2), (4, 3)] {
+                let mut switch = ops.reborrow().get(i);
+                switch.reborrow().init_inputs(1).set(0, selector);
+                switch
+                    .init_instruction()
+                    .init_scf()
+                    .init_switch()
+                    .init_branches(1);
+            }
+        });
+
+        let main = definition(&program, 0);
+        let top = |op| NestedOpPath {
+            regions: vec![],
+            op,
+        };
+        assert_eq!(main.feedforward_edges(), [(top(0), top(2))]);
+    }
+
+    #[test]
+    fn feedforward_edges_nested() {
+        // for _ in .. { b = measure(q); switch b {} }
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(2);
+            values.reborrow().get(0).init_type().set_qubit(());
+            values.get(1).init_type().set_int(1);
+
+            let body = def.init_body();
+            let for_op = body.init_operations(1).get(0);
+            let region = for_op.init_instruction().init_scf().init_for();
+            let mut ops = region.init_operations(2);
+            let mut measure = ops.reborrow().get(0);
+            measure.reborrow().init_inputs(1).set(0, 0);
+            measure.reborrow().init_outputs(1).set(0, 1);
+            measure.init_instruction().init_qubit().set_measure(());
+            let mut switch = ops.get(1);
+            switch.reborrow().init_inputs(1).set(0, 1);
+            switch
+                .init_instruction()
+                .init_scf()
+                .init_switch()
+                .init_branches(1);
+        });
+
+        let main = definition(&program, 0);
+        let nested = |op| NestedOpPath {
+            regions: vec![(0, 0)],
+            op,
+        };
+        assert_eq!(main.feedforward_edges(), [(nested(0), nested(1))]);
+    }
+
+    #[test]
+    fn peak_classical_array_allocs() {
+        // a = zero(n); b = zero(n); get(a, i); c = zero(n); get(b, i); get(c, i)
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(8);
+            for i in 0..8 {
+                let mut ty = values.reborrow().get(i).init_type();
+                match i {
+                    0 | 1 => ty.set_int(32),
+                    2 | 3 | 5 => ty.init_int_array().set_bitwidth(8),
+                    _ => ty.set_int(8),
+                }
+            }
+
+            let mut body = def.init_body();
+            let mut sources = body.reborrow().init_sources(2);
+            sources.set(0, 0);
+            sources.set(1, 1);
+            let mut ops = body.init_operations(6);
+            for (i, output) in [(0, 2), (1, 3), (3, 5)] {
+                let mut zero = ops.reborrow().get(i);
+                zero.reborrow().init_inputs(1).set(0, 0);
+                zero.reborrow().init_outputs(1).set(0, output);
+                zero.init_instruction().init_int_array().set_zero(8);
+            }
+            for (i, array, output) in [(2, 2, 4), (4, 3, 6), (5, 5, 7)] {
+                let mut get = ops.reborrow().get(i);
+                let mut inputs = get.reborrow().init_inputs(2);
+                inputs.set(0, array);
+                inputs.set(1, 1);
+                get.reborrow().init_outputs(1).set(0, output);
+                get.init_instruction().init_int_array().set_get_index(());
+            }
+        });
+
+        let main = definition(&program, 0);
+        // `a` is last used before `c` is created.
+        assert_eq!(main.peak_classical_array_allocs(), 2);
+    }
+
+    #[rstest]
+    fn group_by_kind(entangled_calls: Jeff<'static>) {
+        let main = definition(&entangled_calls, 0);
+        let groups = main.group_by_kind();
+        assert_eq!(groups[&OpKind::Qubit].len(), 15);
+        assert!(!groups.contains_key(&OpKind::Func));
+        let total: usize = groups.values().map(Vec::len).sum();
+        assert_eq!(total, main.body().nested_operations().count());
+        for (kind, paths) in &groups {
+            for path in paths {
+                let op = main.body().operation_at(path).unwrap();
+                assert_eq!(op.op_type().kind(), *kind);
+            }
+        }
+
+        let wrapper = definition(&entangled_calls, 1);
+        let groups = wrapper.group_by_kind();
+        let call = NestedOpPath {
+            regions: vec![],
+            op: 0,
+        };
+        assert_eq!(groups[&OpKind::Func], [call]);
+
+        let program = nested_loop(3, 1);
+        let looped = definition(&program, 0);
+        let groups = looped.group_by_kind();
+        let at = |op| NestedOpPath {
+            regions: vec![(0, 0)],
+            op,
+        };
+        assert_eq!(groups[&OpKind::Qubit], [at(0), at(1), at(2)]);
+        assert_eq!(groups[&OpKind::ControlFlow].len(), 1);
+    }
+
+    #[rstest]
+    fn measurement_targets(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+        let targets = main.measurement_targets();
+        assert_eq!(targets.len(), 5);
+        for (path, port, ty) in targets {
+            assert_eq!(port, 0);
+            assert_eq!(ty, Type::Int { bits: 1 });
+            let OpType::QubitOp(op) = main.body().operation_at(&path).unwrap().op_type() else {
+                panic!("Expected a qubit operation");
+            };
+            assert_eq!(op, QubitOp::Measure);
+        }
+    }
+
+    #[test]
+    fn measurement_targets_nested() {
+        // for _ in .. { b = measure(q) }
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(2);
+            values.reborrow().get(0).init_type().set_qubit(());
+            values.get(1).init_type().set_int(1);
+
+            let body = def.init_body();
+            let for_op = body.init_operations(1).get(0);
+            let region = for_op.init_instruction().init_scf().init_for();
+            let mut measure = region.init_operations(1).get(0);
+            measure.reborrow().init_inputs(1).set(0, 0);
+            measure.reborrow().init_outputs(1).set(0, 1);
+            measure.init_instruction().init_qubit().set_measure(());
+        });
+
+        let main = definition(&program, 0);
+        let nested = NestedOpPath {
+            regions: vec![(0, 0)],
+            op: 0,
+        };
+        assert_eq!(
+            main.measurement_targets(),
+            [(nested, 0, Type::Int { bits: 1 })]
+        );
+    }
+
+    #[test]
+    fn static_iteration_count() {
+        // Loops over `start..stop` by `step`, with constant bounds. If
+        // `const_start` is false, the loop starts at the function input.
+        let build = |start: i32, stop: i32, step: i32, const_start: bool| {
+            build_program(|module| {
+                let function = module.init_functions(1).get(0);
+                let mut def = function.init_definition();
+                let mut values = def.reborrow().init_values(5);
+                for i in 0..5 {
+                    values.reborrow().get(i).init_type().set_int(32);
+                }
+                let mut body = def.init_body();
+                body.reborrow().init_sources(1).set(0, 0);
+                let mut ops = body.init_operations(4);
+                for (i, bound) in [start, stop, step].into_iter().enumerate() {
+                    let mut op = ops.reborrow().get(i as u32);
+                    op.reborrow().init_outputs(1).set(0, i as u32 + 1);
+                    op.init_instruction().init_int().set_const32(bound as u32);
+                }
+                let mut for_op = ops.get(3);
+                let mut inputs = for_op.reborrow().init_inputs(3);
+                inputs.set(0, if const_start { 1 } else { 0 });
+                inputs.set(1, 2);
+                inputs.set(2, 3);
+                let mut region = for_op.init_instruction().init_scf().init_for();
+                region.reborrow().init_sources(1).set(0, 4);
+                region.init_operations(0);
+            })
+        };
+        let count = |program: Jeff<'static>| {
+            let main = definition(&program, 0);
+            let body = main.body();
+            // Constants are not loops.
+            assert_eq!(main.static_iteration_count(&body.operation(0)), None);
+            main.static_iteration_count(&body.operation(3))
+        };
+
+        assert_eq!(count(build(0, 10, 1, true)), Some(10));
+        assert_eq!(count(build(0, 10, 3, true)), Some(4));
+        assert_eq!(count(build(10, 0, -2, true)), Some(5));
+        assert_eq!(count(build(5, 0, 1, true)), Some(0));
+        assert_eq!(count(build(0, 10, 0, true)), None);
+        assert_eq!(count(build(0, 10, 1, false)), None);
+    }
+
+    #[rstest]
+    fn slice_between(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+        let at = |op| NestedOpPath {
+            regions: vec![],
+            op,
+        };
+        let slice = main.slice_between(&at(6), &at(9)).unwrap();
+        assert_eq!(slice, [at(6), at(7), at(8), at(9)]);
+        for path in &slice {
+            let op = main.body().operation_at(path).unwrap();
+            assert_eq!(op.input_count(), 2);
+        }
+        assert_eq!(main.slice_between(&at(6), &at(6)), Some(vec![at(6)]));
+
+        // Inverted, out-of-bounds, and mismatched nesting levels.
+        assert_eq!(main.slice_between(&at(9), &at(6)), None);
+        let count = main.body().operation_count();
+        assert_eq!(main.slice_between(&at(6), &at(count)), None);
+        let nested = NestedOpPath {
+            regions: vec![(0, 0)],
+            op: 0,
+        };
+        assert_eq!(main.slice_between(&nested, &nested), None);
+        assert_eq!(main.slice_between(&at(6), &nested), None);
+    }
+
+    #[test]
+    fn slice_between_nested() {
+        let program = nested_loop(3, 1);
+        let main = definition(&program, 0);
+        let at = |op| NestedOpPath {
+            regions: vec![(0, 0)],
+            op,
+        };
+        let slice = main.slice_between(&at(1), &at(2)).unwrap();
+        assert_eq!(slice, [at(1), at(2)]);
+        assert!(main.body().operation_at(&at(2)).is_some());
+        assert_eq!(main.slice_between(&at(1), &at(3)), None);
+    }
+}
@@ -1,5 +1,7 @@
 //! Metadata associated with jeff elements.
 
+use alloc::string::{String, ToString};
+
 use crate::capnp::jeff_capnp;
 
 use super::string_table::StringTable;
@@ -41,13 +43,11 @@ impl<'a> Metadata<'a> {
     }
 
     /// Returns the name of this metadata entry.
-    pub fn name(&self) -> &str {
+    pub fn name(&self) -> &'a str {
         self.name
     }
 
     /// Returns the value of this metadata entry, as a capnproto any pointer.
-    //
-    // TODO: Add `try_value_*` getters that try to cast into str / int / float / etc.
     pub fn value_any_pointer(&self) -> capnp::any_pointer::Reader<'a> {
         self.value
     }
@@ -55,14 +55,128 @@ impl<'a> Metadata<'a> {
     /// Returns the value as a string.
     ///
     /// Returns `None` if the value cannot be converted to a string.
-    pub fn value_str(&self) -> Option<&str> {
-        let reader = self.value.get_as::<capnp::text::Reader>().ok()?;
+    pub fn value_str(&self) -> Option<&'a str> {
+        let reader = self.value.get_as::<capnp::text::Reader<'a>>().ok()?;
         reader.to_str().ok()
     }
+
+    /// Parses a text value as an unsigned integer.
+    ///
+    /// Only text values are parsed. Returns `None` if the value is not a string
+    /// holding a decimal integer, including when it is an integer encoded as a
+    /// capnproto value. See [`Metadata::value_as`] to read those.
+    pub fn parse_u64(&self) -> Option<u64> {
+        self.value_str()?.parse().ok()
+    }
+
+    /// Parses a text value as a signed integer.
+    ///
+    /// Only text values are parsed. Returns `None` if the value is not a string
+    /// holding a decimal integer, including when it is an integer encoded as a
+    /// capnproto value. See [`Metadata::value_as`] to read those.
+    pub fn parse_i64(&self) -> Option<i64> {
+        self.value_str()?.parse().ok()
+    }
+
+    /// Parses a text value as a floating point number.
+    ///
+    /// Only text values are parsed. Returns `None` if the value is not a string
+    /// holding a number, including when it is a number encoded as a capnproto
+    /// value. See [`Metadata::value_as`] to read those.
+    pub fn parse_f64(&self) -> Option<f64> {
+        self.value_str()?.parse().ok()
+    }
+
+    /// Returns the value as a capnproto pointer of type `T`.
+    ///
+    /// This is mainly useful to read structured metadata that was embedded
+    /// using one of the schema types, e.g. `jeff_capnp::value::Owned`.
+    ///
+    /// Returns `None` if the value cannot be read as a `T`.
+    ///
+    /// Note that the generated `jeff_capnp` module is `#[doc(hidden)]`, and its
+    /// types carry no semver guarantees.
+    pub fn value_as<T: capnp::traits::Owned>(&self) -> Option<T::Reader<'a>> {
+        self.value.get_as::<T::Reader<'a>>().ok()
+    }
+
+    /// Returns an owned copy of the value.
+    ///
+    /// Text values are parsed as an integer, a float, or a boolean (`true` or
+    /// `false`), in that order, and are kept as a string otherwise. Values that
+    /// are not text are returned as [`MetadataValue::Opaque`].
+    pub fn to_owned_value(&self) -> MetadataValue {
+        let Some(value) = self.value_str() else {
+            return MetadataValue::Opaque;
+        };
+        if let Ok(int) = value.parse() {
+            MetadataValue::Int(int)
+        } else if let Ok(float) = value.parse() {
+            MetadataValue::Float(float)
+        } else if let Ok(boolean) = value.parse() {
+            MetadataValue::Bool(boolean)
+        } else {
+            MetadataValue::String(value.to_string())
+        }
+    }
 }
 
-impl std::fmt::Debug for Metadata<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// Owned value of a metadata entry.
+///
+/// See [`Metadata::to_owned_value`]. With the `serde` feature, values are
+/// serialized as plain JSON-like scalars, and [`MetadataValue::Opaque`] as a
+/// unit value.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(untagged))]
+#[non_exhaustive]
+pub enum MetadataValue {
+    /// A string value.
+    String(String),
+    /// An integer value.
+    Int(i64),
+    /// A floating point value.
+    Float(f64),
+    /// A boolean value.
+    Bool(bool),
+    /// A value that is not stored as text.
+    Opaque,
+}
+
+impl MetadataValue {
+    /// Returns the text encoding of this value, or `None` for
+    /// [`MetadataValue::Opaque`].
+    pub(crate) fn to_text(&self) -> Option<String> {
+        match self {
+            MetadataValue::String(s) => Some(s.clone()),
+            MetadataValue::Int(i) => Some(i.to_string()),
+            // Use the debug format so that integral floats keep their `.0`.
+            MetadataValue::Float(f) => Some(alloc::format!("{f:?}")),
+            MetadataValue::Bool(b) => Some(b.to_string()),
+            MetadataValue::Opaque => None,
+        }
+    }
+}
+
+/// The kind of element a metadata entry is attached to.
+///
+/// See [`Jeff::rewrite`](crate::Jeff::rewrite).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MetaScope {
+    /// Metadata attached to the module.
+    Module,
+    /// Metadata attached to a function.
+    Function,
+    /// Metadata attached to a region.
+    Region,
+    /// Metadata attached to an operation.
+    Operation,
+    /// Metadata attached to a value.
+    Value,
+}
+
+impl core::fmt::Debug for Metadata<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Metadata")
             .field("name", &self.name)
             .field("value", &"...")
@@ -100,6 +214,156 @@ pub trait HasMetadata: sealed::HasMetadataSealed {
         let m = self.metadata_reader().try_get(n as u32)?;
         Some(Metadata::read_capnp(m, self.strings()))
     }
+
+    /// Returns the first metadata entry named `name`.
+    ///
+    /// Returns `None` if there is no such entry.
+    fn metadata_by_name(&self, name: &str) -> Option<Metadata<'_>> {
+        self.metadata_entries().find(|m| m.name() == name)
+    }
+
+    /// Returns an iterator over the metadata entries whose name starts with
+    /// `prefix`.
+    ///
+    /// This is useful for reading namespaced annotations, e.g. all `"opt."`
+    /// hints.
+    fn metadata_with_prefix<'s>(
+        &'s self,
+        prefix: &'s str,
+    ) -> impl Iterator<Item = Metadata<'s>> + 's {
+        self.metadata_entries()
+            .filter(move |m| m.name().starts_with(prefix))
+    }
+}
+
+impl<T: sealed::HasMetadataSealed> HasMetadata for T {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::ReadJeff;
+    use crate::test::build_program;
+
+    #[test]
+    fn metadata_with_prefix() {
+        let program = build_program(|mut module| {
+            let mut strings = module.reborrow().init_strings(3);
+            strings.set(0, "opt.level");
+            strings.set(1, "source");
+            strings.set(2, "opt.unroll");
+
+            let mut metadata = module.init_metadata(3);
+            for (i, (key, value)) in [(0, "3"), (1, "kernel.py"), (2, "8")]
+                .into_iter()
+                .enumerate()
+            {
+                let mut meta = metadata.reborrow().get(i as u32);
+                meta.set_name(key);
+                meta.init_value()
+                    .set_as::<capnp::text::Owned>(value)
+                    .unwrap();
+            }
+        });
+
+        let module = program.module();
+        let hints: Vec<_> = module
+            .metadata_with_prefix("opt.")
+            .map(|m| (m.name().to_string(), m.parse_u64()))
+            .collect();
+        assert_eq!(
+            hints,
+            [
+                ("opt.level".to_string(), Some(3)),
+                ("opt.unroll".to_string(), Some(8))
+            ]
+        );
+        assert_eq!(module.metadata_with_prefix("debug.").count(), 0);
+    }
+
+    /// Builds a program with one text metadata entry per value, and a final
+    /// entry holding a struct.
+    fn metadata_values(values: &[&str]) -> crate::Jeff<'static> {
+        build_program(|mut module| {
+            module.reborrow().init_strings(1).set(0, "key");
+            let mut metadata = module.init_metadata(values.len() as u32 + 1);
+            for (i, value) in values.iter().enumerate() {
+                let meta = metadata.reborrow().get(i as u32);
+                meta.init_value()
+                    .set_as::<capnp::text::Owned>(*value)
+                    .unwrap();
+            }
+            metadata
+                .get(values.len() as u32)
+                .init_value()
+                .init_as::<jeff_capnp::meta::Builder<'_>>();
+        })
+    }
+
+    #[test]
+    fn to_owned_value() {
+        let program = metadata_values(&["-3", "0.5", "true", "kernel.py"]);
+        let values: Vec<_> = program
+            .module()
+            .metadata_entries()
+            .map(|m| m.to_owned_value())
+            .collect();
+        assert_eq!(
+            values,
+            [
+                MetadataValue::Int(-3),
+                MetadataValue::Float(0.5),
+                MetadataValue::Bool(true),
+                MetadataValue::String("kernel.py".to_string()),
+                MetadataValue::Opaque,
+            ]
+        );
+    }
+
+    #[test]
+    fn value_as_struct() {
+        let program = build_program(|mut module| {
+            module.reborrow().init_strings(1).set(0, "key");
+            let mut metadata = module.init_metadata(2);
+            metadata
+                .reborrow()
+                .get(0)
+                .init_value()
+                .init_as::<jeff_capnp::value::Builder<'_>>()
+                .init_type()
+                .set_int(32);
+            metadata
+                .get(1)
+                .init_value()
+                .set_as::<capnp::text::Owned>("kernel.py")
+                .unwrap();
+        });
+
+        let module = program.module();
+        let value = module
+            .metadata(0)
+            .value_as::<jeff_capnp::value::Owned>()
+            .unwrap();
+        match value.get_type().unwrap().which().unwrap() {
+            jeff_capnp::type_::Which::Int(bits) => assert_eq!(bits, 32),
+            _ => panic!("expected an int type"),
+        }
+        assert!(module
+            .metadata(1)
+            .value_as::<jeff_capnp::value::Owned>()
+            .is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_value() {
+        let program = metadata_values(&["-3", "0.5", "true", "kernel.py"]);
+        let json: Vec<_> = program
+            .module()
+            .metadata_entries()
+            .map(|m| serde_json::to_string(&m.to_owned_value()).unwrap())
+            .collect();
+        assert_eq!(json, ["-3", "0.5", "true", "\"kernel.py\"", "null"]);
+    }
 }
 
 pub(crate) mod sealed {
@@ -1,10 +1,83 @@
 //! Top-level module definition in a jeff program.
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
 use crate::capnp::jeff_capnp;
+use crate::types::{FunctionType, Type};
 
 use super::function::FunctionId;
 use super::metadata::sealed::HasMetadataSealed;
+use super::metadata::{HasMetadata, MetadataValue};
+use super::optype::{OpType, QubitOp};
 use super::string_table::StringTable;
-use super::Function;
+use super::value::ValueId;
+use super::{Function, FunctionDeclaration, FunctionDefinition, Operation, ReadError, Region};
+
+/// Information about the tool that generated a jeff program.
+///
+/// See [`Module::tool_info`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ToolInfo<'a> {
+    /// Name of the tool.
+    pub name: &'a str,
+    /// Version of the tool, if it is a valid semantic version.
+    pub version: Option<semver::Version>,
+}
+
+/// Overview of a function in a module.
+///
+/// See [`Module::function_summary`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct FunctionSummary {
+    /// Name of the function.
+    pub name: String,
+    /// Signature of the function, or `None` if its types could not be read.
+    pub signature: Option<FunctionType>,
+    /// Number of operations in the function body, including operations in
+    /// nested control flow regions. Zero for declarations.
+    pub operation_count: usize,
+    /// Number of gate operations in the function body, including gates in
+    /// nested control flow regions. Zero for declarations.
+    pub gate_count: usize,
+    /// Number of operations on the longest dependency chain between the
+    /// top-level operations of the function body.
+    ///
+    /// Control flow operations count as a single step. Zero for declarations.
+    pub depth: usize,
+    /// Function metadata entries with one of the keys in
+    /// [`FunctionSummary::METADATA_KEYS`], in their encoded order.
+    pub metadata: Vec<(String, MetadataValue)>,
+}
+
+impl FunctionSummary {
+    /// Metadata keys included in a function summary.
+    pub const METADATA_KEYS: &'static [&'static str] = &[
+        Operation::LOC_FILE_KEY,
+        Operation::LOC_LINE_KEY,
+        Operation::LOC_COL_KEY,
+    ];
+}
+
+/// Location of an operation in a module, possibly inside nested control flow
+/// regions.
+///
+/// See [`Module::operations`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModuleOpPath {
+    /// Function whose body contains the operation.
+    pub function: FunctionId,
+    /// Enclosing control flow operations, from the function body inwards.
+    ///
+    /// Each entry is the index of the operation in its region, and the index
+    /// of the nested region in [`ControlFlowOp::regions`](super::optype::ControlFlowOp::regions).
+    pub regions: Vec<(usize, usize)>,
+    /// Index of the operation in its region.
+    pub op: usize,
+}
 
 /// Top-level module definition in a jeff program.
 #[derive(Clone, Copy, Debug)]
@@ -19,6 +92,11 @@ impl<'a> Module<'a> {
         Self { module }
     }
 
+    /// Returns the internal capnp module reader.
+    pub(crate) fn capnp_reader(&self) -> jeff_capnp::module::Reader<'a> {
+        self.module
+    }
+
     /// Version of the jeff protocol used in this module.
     pub fn version(&self) -> semver::Version {
         let major = self.module.get_version() as u64;
@@ -42,6 +120,133 @@ impl<'a> Module<'a> {
             .map(move |f| Function::read_capnp(f, string_table))
     }
 
+    /// Returns an iterator over the functions defined in this module, along
+    /// with their [FunctionId]s.
+    pub fn functions_indexed(&self) -> impl Iterator<Item = (FunctionId, Function<'a>)> {
+        self.functions()
+            .enumerate()
+            .map(|(idx, f)| (idx as FunctionId, f))
+    }
+
+    /// Returns an iterator over the function definitions in this module, along
+    /// with their [FunctionId]s.
+    ///
+    /// Function declarations are skipped. See [`Module::declarations`].
+    pub fn definitions(&self) -> impl Iterator<Item = (FunctionId, FunctionDefinition<'a>)> {
+        self.functions_indexed().filter_map(|(idx, f)| match f {
+            Function::Definition(def) => Some((idx, def)),
+            Function::Declaration(_) => None,
+        })
+    }
+
+    /// Returns an iterator over the types of the values of every function
+    /// definition in this module.
+    ///
+    /// Values are yielded in function order, and in value table order within
+    /// each function. Declarations have no value table and are skipped.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::InvalidValue`] if the type of a value cannot be decoded.
+    pub fn all_value_types(
+        &self,
+    ) -> impl Iterator<Item = (FunctionId, ValueId, Result<Type, ReadError>)> + 'a {
+        self.definitions().flat_map(|(func, def)| {
+            def.values()
+                .iter_types()
+                .map(move |(value, ty)| (func, value, ty))
+        })
+    }
+
+    /// Returns every distinct type used in this module, in the function
+    /// signatures or in the value tables of the function definitions.
+    ///
+    /// Types that cannot be decoded are skipped.
+    pub fn used_types(&self) -> BTreeSet<Type> {
+        let mut types: BTreeSet<Type> = self
+            .all_value_types()
+            .filter_map(|(_, _, ty)| ty.ok())
+            .collect();
+        for function in self.functions() {
+            if let Ok(signature) = function.signature() {
+                types.extend(signature.inputs);
+                types.extend(signature.outputs);
+            }
+        }
+        types
+    }
+
+    /// Returns an iterator over every operation in the function definitions
+    /// of this module, along with its path.
+    ///
+    /// Functions are visited in order, and each function body is walked in
+    /// pre-order: control flow operations are yielded before the operations in
+    /// their nested regions.
+    pub fn operations(&self) -> impl Iterator<Item = (ModuleOpPath, Operation<'a>)> {
+        self.definitions().flat_map(|(function, def)| {
+            def.body().nested_operations().map(move |(path, op)| {
+                let path = ModuleOpPath {
+                    function,
+                    regions: path.regions,
+                    op: path.op,
+                };
+                (path, op)
+            })
+        })
+    }
+
+    /// Returns the paths of every operation in a function definition, in a
+    /// canonical order that respects the dataflow.
+    ///
+    /// Each region is visited in [`Region::topological_order`], so an
+    /// operation always comes after the operations producing its inputs, and
+    /// ties are broken by the encoded index. Control flow operations are
+    /// followed by the operations of their nested regions, region by region.
+    /// The order only depends on the structure of the function, so tools can
+    /// use positions in it as stable operation indices.
+    ///
+    /// Regions whose dependencies cannot be read are visited in encoded order.
+    /// Returns `None` if `function` is not a function definition.
+    pub fn canonical_order(&self, function: FunctionId) -> Option<Vec<ModuleOpPath>> {
+        let Function::Definition(def) = self.try_function(function)? else {
+            return None;
+        };
+        let mut paths = Vec::new();
+        collect_canonical(function, def.body(), &mut Vec::new(), &mut paths);
+        Some(paths)
+    }
+
+    /// Returns the total number of operations in the function definitions of
+    /// this module, including the operations in nested regions.
+    pub fn total_operation_count(&self) -> usize {
+        self.definitions()
+            .map(|(_, def)| def.body().fold_operations(0, |count, _| count + 1))
+            .sum()
+    }
+
+    /// Returns the operation at the given path.
+    ///
+    /// Returns `None` if the path does not refer to an operation in this
+    /// module.
+    pub fn operation_at(&self, path: &ModuleOpPath) -> Option<Operation<'a>> {
+        let Function::Definition(def) = self.try_function(path.function)? else {
+            return None;
+        };
+        let region = def.body().nested_region(&path.regions)?;
+        (path.op < region.operation_count()).then(|| region.operation(path.op))
+    }
+
+    /// Returns an iterator over the external function declarations in this
+    /// module, along with their [FunctionId]s.
+    ///
+    /// Function definitions are skipped. See [`Module::definitions`].
+    pub fn declarations(&self) -> impl Iterator<Item = (FunctionId, FunctionDeclaration<'a>)> {
+        self.functions_indexed().filter_map(|(idx, f)| match f {
+            Function::Declaration(decl) => Some((idx, decl)),
+            Function::Definition(_) => None,
+        })
+    }
+
     /// Returns the number of functions defined in this module.
     pub fn function_count(&self) -> usize {
         self.functions_reader().len() as usize
@@ -62,6 +267,37 @@ impl<'a> Module<'a> {
         Some(Function::read_capnp(f, self.strings()))
     }
 
+    /// Returns an overview of the function with the given id.
+    ///
+    /// Returns `None` if there is no function with that id.
+    pub fn function_summary(&self, id: FunctionId) -> Option<FunctionSummary> {
+        let function = self.try_function(id)?;
+        let metadata = function
+            .metadata_entries()
+            .filter(|meta| FunctionSummary::METADATA_KEYS.contains(&meta.name()))
+            .map(|meta| (meta.name().to_string(), meta.to_owned_value()))
+            .collect();
+        let (operation_count, gate_count, depth) = match &function {
+            Function::Declaration(_) => (0, 0, 0),
+            Function::Definition(def) => {
+                let body = def.body();
+                let (operations, gates) = body.fold_operations((0, 0), |(ops, gates), op| {
+                    let is_gate = matches!(op.op_type(), OpType::QubitOp(QubitOp::Gate(_)));
+                    (ops + 1, gates + usize::from(is_gate))
+                });
+                (operations, gates, region_depth(&body))
+            }
+        };
+        Some(FunctionSummary {
+            name: function.name().to_string(),
+            signature: function.signature().ok(),
+            operation_count,
+            gate_count,
+            depth,
+            metadata,
+        })
+    }
+
     /// Returns the internal storage of strings.
     pub fn strings(&self) -> StringTable<'a> {
         StringTable::read_capnp(
@@ -71,6 +307,19 @@ impl<'a> Module<'a> {
         )
     }
 
+    /// Eagerly checks that every entry in the module's string table is valid utf8.
+    ///
+    /// Strings are otherwise decoded lazily, and accessors such as
+    /// [`Function::name`] panic on invalid entries. Calling this once after
+    /// loading a module makes those accessors safe.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::StringNotUtf8`] for the first string that is not valid utf8.
+    pub fn verify_strings(&self) -> Result<(), ReadError> {
+        self.strings().verify_utf8()
+    }
+
     /// Returns the [FunctionId] of the entrypoint function for this module.
     pub fn entrypoint_id(&self) -> FunctionId {
         self.module.get_entrypoint() as FunctionId
@@ -85,10 +334,47 @@ impl<'a> Module<'a> {
         self.functions().nth(self.entrypoint_id() as usize).unwrap()
     }
 
+    /// Returns the ids of the functions reachable from the entrypoint.
+    ///
+    /// The entrypoint is always included. Calls are followed transitively
+    /// through function definitions, including calls nested in control flow
+    /// regions. Declarations are included when called, but have no outgoing
+    /// calls. Call targets outside the function table are ignored.
+    pub fn reachable_functions(&self) -> BTreeSet<FunctionId> {
+        let mut reachable = BTreeSet::new();
+        let mut worklist = vec![self.entrypoint_id()];
+        while let Some(id) = worklist.pop() {
+            let Some(function) = self.try_function(id) else {
+                continue;
+            };
+            if !reachable.insert(id) {
+                continue;
+            }
+            if let Function::Definition(def) = function {
+                worklist.extend(def.body().called_functions_recursive());
+            }
+        }
+        reachable
+    }
+
+    /// Returns a hash of the module's structural contents.
+    ///
+    /// The hash covers the format version, the name of the entrypoint, and for
+    /// each function its name, signature, body boundary, and operations, with
+    /// string indices resolved to their values. It is consistent with
+    /// [`Module::eq_ignoring_metadata`] and [`Module::structurally_eq`]: equal
+    /// modules have equal hashes, regardless of the order of their function
+    /// and string tables, their tool information, or their metadata.
+    ///
+    /// The hash uses 64-bit FNV-1a, and is stable across runs and platforms.
+    pub fn content_hash(&self) -> u64 {
+        super::diff::structural_hash(self)
+    }
+
     /// Returns the tool name used to generate this program.
     ///
     /// See [`Module::tool_version`].
-    pub fn tool(&self) -> &str {
+    pub fn tool(&self) -> &'a str {
         self.module
             .get_tool()
             .ok()
@@ -99,13 +385,77 @@ impl<'a> Module<'a> {
     /// Returns the tool version used to generate this program.
     ///
     /// See [`Module::tool`].
-    pub fn tool_version(&self) -> &str {
+    pub fn tool_version(&self) -> &'a str {
         self.module
             .get_tool_version()
             .ok()
             .and_then(|r| r.to_str().ok())
             .unwrap_or("")
     }
+
+    /// Returns the tool name and version used to generate this program.
+    ///
+    /// Returns `None` if both [`Module::tool`] and [`Module::tool_version`]
+    /// are empty. The version is `None` if it is not a valid semantic
+    /// version.
+    pub fn tool_info(&self) -> Option<ToolInfo<'a>> {
+        let name = self.tool();
+        let version = self.tool_version();
+        if name.is_empty() && version.is_empty() {
+            return None;
+        }
+        Some(ToolInfo {
+            name,
+            version: semver::Version::parse(version).ok(),
+        })
+    }
+}
+
+/// Returns the number of operations on the longest dependency chain in a
+/// region, for [`Module::function_summary`].
+///
+/// Operations are assumed to appear after the operations producing their
+/// inputs. Regions with invalid value references have depth zero.
+fn region_depth(region: &Region<'_>) -> usize {
+    let Ok(dependencies) = region.op_dependencies() else {
+        return 0;
+    };
+    let mut depths: Vec<usize> = Vec::with_capacity(dependencies.len());
+    for deps in &dependencies {
+        let depth = deps
+            .iter()
+            .filter_map(|&dep| depths.get(dep))
+            .max()
+            .map_or(1, |d| d + 1);
+        depths.push(depth);
+    }
+    depths.into_iter().max().unwrap_or(0)
+}
+
+/// Recursive helper for [`Module::canonical_order`].
+fn collect_canonical(
+    function: FunctionId,
+    region: Region<'_>,
+    regions: &mut Vec<(usize, usize)>,
+    paths: &mut Vec<ModuleOpPath>,
+) {
+    let order = region
+        .topological_order()
+        .unwrap_or_else(|_| (0..region.operation_count()).collect());
+    for idx in order {
+        paths.push(ModuleOpPath {
+            function,
+            regions: regions.clone(),
+            op: idx,
+        });
+        if let OpType::ControlFlowOp(cf_op) = region.operation(idx).op_type() {
+            for (nested, nested_region) in cf_op.regions().into_iter().enumerate() {
+                regions.push((idx, nested));
+                collect_canonical(function, nested_region, regions, paths);
+                regions.pop();
+            }
+        }
+    }
 }
 
 impl<'a> HasMetadataSealed for Module<'a> {
@@ -119,3 +469,322 @@ impl<'a> HasMetadataSealed for Module<'a> {
             .expect("Metadata should be present")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::ReadJeff;
+    use crate::test::{
+        build_program, definition, entangled_calls, entangled_qs, nested_loop, read_owned,
+    };
+    use crate::Jeff;
+    use alloc::collections::BTreeMap;
+    use rstest::rstest;
+
+    #[rstest]
+    fn function_summary(entangled_calls: Jeff<'static>) {
+        let module = entangled_calls.module();
+        let summary = module.function_summary(0).unwrap();
+        assert_eq!(summary.name, "__nvqpp__mlirgen__function_sample._Z6samplev");
+        assert_eq!(
+            summary.signature,
+            Some(FunctionType {
+                inputs: vec![],
+                outputs: vec![Type::bool()],
+            })
+        );
+        assert_eq!(summary.operation_count, 46);
+        assert_eq!(summary.gate_count, 5);
+        assert_eq!(summary.depth, 19);
+        assert!(summary.metadata.is_empty());
+
+        assert_eq!(
+            module.function_summary(module.function_count() as FunctionId),
+            None
+        );
+    }
+
+    #[rstest]
+    fn operations(entangled_calls: Jeff<'static>) {
+        let module = entangled_calls.module();
+        let ops: Vec<_> = module.operations().collect();
+        assert_eq!(ops.len(), module.total_operation_count());
+        for (path, op) in &ops {
+            assert!(path.regions.is_empty());
+            assert!(module.operation_at(path).unwrap().op_type_eq(op));
+        }
+    }
+
+    #[rstest]
+    fn canonical_order(entangled_calls: Jeff<'static>) {
+        let module = entangled_calls.module();
+        let order = module.canonical_order(0).unwrap();
+        assert_eq!(module.canonical_order(0), Some(order.clone()));
+
+        let main = definition(&entangled_calls, 0);
+        assert_eq!(order.len(), main.body().operation_count());
+        let positions: BTreeMap<usize, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(position, path)| (path.op, position))
+            .collect();
+        for (op, deps) in main.body().op_dependencies().unwrap().iter().enumerate() {
+            for dep in deps {
+                assert!(positions[dep] < positions[&op]);
+            }
+        }
+    }
+
+    #[test]
+    fn canonical_order_reorders() {
+        // The H gate is encoded before the allocation of its qubit.
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(2);
+            for i in 0..2 {
+                values.reborrow().get(i).init_type().set_qubit(());
+            }
+            let mut ops = def.init_body().init_operations(2);
+            let mut h = ops.reborrow().get(0);
+            h.reborrow().init_inputs(1).set(0, 0);
+            h.reborrow().init_outputs(1).set(0, 1);
+            h.init_instruction()
+                .init_qubit()
+                .init_gate()
+                .set_well_known(jeff_capnp::WellKnownGate::H);
+            let mut alloc = ops.get(1);
+            alloc.reborrow().init_outputs(1).set(0, 0);
+            alloc.init_instruction().init_qubit().set_alloc(());
+        });
+
+        let order: Vec<_> = program
+            .module()
+            .canonical_order(0)
+            .unwrap()
+            .into_iter()
+            .map(|path| path.op)
+            .collect();
+        assert_eq!(order, [1, 0]);
+    }
+
+    #[test]
+    fn nested_operations() {
+        // for i in start..stop by step { q = h(q) }
+        let program = nested_loop(1, 1);
+        let module = program.module();
+        let paths: Vec<_> = module.operations().map(|(path, _)| path).collect();
+        let gate = ModuleOpPath {
+            function: 0,
+            regions: vec![(0, 0)],
+            op: 0,
+        };
+        assert_eq!(
+            paths,
+            [
+                ModuleOpPath {
+                    function: 0,
+                    regions: vec![],
+                    op: 0
+                },
+                gate.clone()
+            ]
+        );
+        assert_eq!(module.total_operation_count(), 2);
+        assert!(matches!(
+            module.operation_at(&gate).unwrap().op_type(),
+            OpType::QubitOp(_)
+        ));
+        let missing = ModuleOpPath {
+            regions: vec![(0, 1)],
+            ..gate
+        };
+        assert!(module.operation_at(&missing).is_none());
+    }
+
+    #[rstest]
+    fn verify_strings(entangled_qs: Jeff<'static>) {
+        entangled_qs.module().verify_strings().unwrap();
+
+        let invalid = build_program(|module| {
+            let mut strings = module.init_strings(3);
+            strings.set(0, "main");
+            strings.set(1, capnp::text::Reader::from(&[0x66, 0xff, 0x6f][..]));
+            strings.set(2, capnp::text::Reader::from(&[0xc3][..]));
+        });
+        let err = invalid.module().verify_strings().unwrap_err();
+        assert!(matches!(err, ReadError::StringNotUtf8 { idx: 1, .. }));
+    }
+
+    #[test]
+    fn definitions_and_declarations() {
+        let program = build_program(|mut module| {
+            let mut functions = module.reborrow().init_functions(3);
+            for i in 0..3 {
+                let mut function = functions.reborrow().get(i);
+                function.set_name(i as u16);
+                if i == 1 {
+                    function.init_definition().init_body();
+                } else {
+                    function.init_declaration();
+                }
+            }
+            let mut strings = module.init_strings(3);
+            strings.set(0, "extern_a");
+            strings.set(1, "main");
+            strings.set(2, "extern_b");
+        });
+        let module = program.module();
+
+        let defs: Vec<_> = module.definitions().map(|(id, _)| id).collect();
+        assert_eq!(defs, [1]);
+        let (_, main) = module.definitions().next().unwrap();
+        assert_eq!(main.name(), "main");
+
+        let decls: Vec<_> = module.declarations().map(|(id, _)| id).collect();
+        assert_eq!(decls, [0, 2]);
+        let (_, extern_b) = module.declarations().last().unwrap();
+        assert_eq!(extern_b.name(), "extern_b");
+    }
+
+    #[rstest]
+    fn reachable_functions(entangled_calls: Jeff<'static>) {
+        // The entrypoint of `entangled_calls` is function 0,
+        // `__nvqpp__mlirgen__function_sample`, which makes no calls.
+        let module = entangled_calls.module();
+        assert_eq!(module.entrypoint_id(), 0);
+        assert_eq!(module.reachable_functions(), BTreeSet::from([0]));
+
+        // Function 0 calls 1, which calls the declaration 2. Function 3 is
+        // never called.
+        let program = build_program(|module| {
+            let mut functions = module.init_functions(4);
+            for (i, callee) in [(0, Some(1)), (1, Some(2)), (3, Some(0))] {
+                let body = functions.reborrow().get(i).init_definition().init_body();
+                if let Some(callee) = callee {
+                    let op = body.init_operations(1).get(0);
+                    op.init_instruction().init_func().set_func_call(callee);
+                }
+            }
+            functions.get(2).init_declaration();
+        });
+        assert_eq!(
+            program.module().reachable_functions(),
+            BTreeSet::from([0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn tool_info() {
+        let build = |tool: Option<(&str, &str)>| {
+            build_program(|mut module| {
+                if let Some((name, version)) = tool {
+                    module.set_tool(name);
+                    module.set_tool_version(version);
+                }
+            })
+        };
+
+        let program = build(Some(("qcc", "1.2.3-rc.1")));
+        let info = program.module().tool_info().unwrap();
+        assert_eq!(info.name, "qcc");
+        assert_eq!(
+            info.version,
+            Some(semver::Version::parse("1.2.3-rc.1").unwrap())
+        );
+
+        let program = build(Some(("qcc", "nightly")));
+        let info = program.module().tool_info().unwrap();
+        assert_eq!(info.name, "qcc");
+        assert_eq!(info.version, None);
+
+        assert_eq!(build(None).module().tool_info(), None);
+    }
+
+    #[rstest]
+    fn used_types(entangled_calls: Jeff<'static>) {
+        let types = entangled_calls.module().used_types();
+        assert_eq!(
+            types.into_iter().collect::<Vec<_>>(),
+            [
+                Type::Qubit,
+                Type::bool(),
+                Type::int(32),
+                Type::IntArray {
+                    bits: 1,
+                    length: Some(5),
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn all_value_types(entangled_calls: Jeff<'static>) {
+        let module = entangled_calls.module();
+        let types: Vec<_> = module
+            .all_value_types()
+            .map(|(func, value, ty)| (func, value, ty.unwrap()))
+            .collect();
+        let value_count: usize = module
+            .definitions()
+            .map(|(_, def)| def.values().len())
+            .sum();
+        assert_eq!(types.len(), value_count);
+
+        assert!(types.iter().any(|(_, _, ty)| *ty == Type::Qubit));
+        assert!(types
+            .iter()
+            .any(|(_, _, ty)| matches!(ty, Type::IntArray { .. })));
+        // Values are grouped by function, in function order.
+        assert!(types.is_sorted_by_key(|&(func, _, _)| func));
+    }
+
+    #[rstest]
+    fn content_hash(entangled_qs: Jeff<'static>) {
+        let hash = entangled_qs.module().content_hash();
+        let round_trip = read_owned(&entangled_qs.as_bytes()).unwrap();
+        assert_eq!(round_trip.module().content_hash(), hash);
+
+        // The same module, encoded with a different layout.
+        let build = |strings_first: bool, name: &str| {
+            build_program(|mut module| {
+                if strings_first {
+                    module.reborrow().init_strings(1).set(0, name);
+                }
+                let function = module.reborrow().init_functions(1).get(0);
+                function.init_definition().init_body();
+                if !strings_first {
+                    module.init_strings(1).set(0, name);
+                }
+            })
+        };
+        let a = build(true, "main");
+        let b = build(false, "main");
+        assert_ne!(a.as_bytes(), b.as_bytes());
+        assert_eq!(a.module().content_hash(), b.module().content_hash());
+        assert_ne!(
+            a.module().content_hash(),
+            build(true, "other").module().content_hash()
+        );
+        assert_ne!(a.module().content_hash(), hash);
+
+        // Permuted string tables and different tool information.
+        let build = |strings: [&str; 2], tool: &str| {
+            build_program(|mut module| {
+                module.set_tool(tool);
+                let name = strings.iter().position(|s| *s == "main").unwrap();
+                let mut function = module.reborrow().init_functions(1).get(0);
+                function.set_name(name as u16);
+                function.init_definition().init_body();
+                let mut table = module.init_strings(2);
+                for (i, s) in strings.into_iter().enumerate() {
+                    table.set(i as u32, s);
+                }
+            })
+        };
+        let a = build(["main", "x"], "a");
+        let b = build(["x", "main"], "b");
+        assert_eq!(a, b);
+        assert_eq!(a.module().content_hash(), b.module().content_hash());
+    }
+}
@@ -1,11 +1,17 @@
 //! Node operation definitions.
 
+use alloc::vec::Vec;
+
 use crate::reader::value::{ValueTable, WireValue};
 use crate::types::Type;
 use crate::{jeff_capnp, Direction};
 
+use super::diff::op_types_eq;
 use super::metadata::sealed::HasMetadataSealed;
-use super::optype::OpType;
+use super::metadata::{HasMetadata, Metadata};
+use super::optype::{
+    ControlFlowOp, FloatArrayOp, GateOpType, IntArrayOp, OpType, QubitOp, QubitRegisterOp, SwitchOp,
+};
 use super::string_table::StringTable;
 use super::value::ValueId;
 use super::ReadError;
@@ -21,7 +27,59 @@ pub struct Operation<'a> {
     values: ValueTable<'a>,
 }
 
+/// An operation together with its resolved input and output values.
+///
+/// See [`Region::operations_with_wires`](super::Region::operations_with_wires).
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ResolvedOp<'a> {
+    /// The operation.
+    pub operation: Operation<'a>,
+    /// Input values of the operation, in port order.
+    pub inputs: Vec<WireValue<'a>>,
+    /// Output values of the operation, in port order.
+    pub outputs: Vec<WireValue<'a>>,
+}
+
+impl<'a> ResolvedOp<'a> {
+    /// Resolves the input and output values of an operation.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::ValueOutOfBounds`] if an encoded value references an invalid index in the value table.
+    pub fn resolve(operation: Operation<'a>) -> Result<Self, ReadError> {
+        Ok(Self {
+            inputs: operation.boundary_vec(Direction::Incoming)?,
+            outputs: operation.boundary_vec(Direction::Outgoing)?,
+            operation,
+        })
+    }
+}
+
+/// A location in the user source code that produced an operation.
+///
+/// See [`Operation::source_location`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, derive_more::Display)]
+#[display("{file}:{line}:{col}")]
+pub struct SourceLoc<'a> {
+    /// Path of the source file.
+    pub file: &'a str,
+    /// Line number in the source file.
+    pub line: u32,
+    /// Column number in the source file.
+    pub col: u32,
+}
+
 impl<'a> Operation<'a> {
+    /// Metadata key for the source file of an operation.
+    pub const LOC_FILE_KEY: &'static str = "loc.file";
+    /// Metadata key for the source line of an operation.
+    pub const LOC_LINE_KEY: &'static str = "loc.line";
+    /// Metadata key for the source column of an operation.
+    pub const LOC_COL_KEY: &'static str = "loc.col";
+    /// Metadata key for the estimated cost of an operation.
+    pub const COST_KEY: &'static str = "cost";
+
     /// Create a new dataflow operation reader from a capnp reader.
     pub(crate) fn read_capnp(
         operation: jeff_capnp::op::Reader<'a>,
@@ -40,6 +98,22 @@ impl<'a> Operation<'a> {
         OpType::read_capnp(self.op.get_instruction(), self.strings, self.values)
     }
 
+    /// Returns the control flow operation, if this is one.
+    pub fn as_control_flow(&self) -> Option<ControlFlowOp<'a>> {
+        match self.op_type() {
+            OpType::ControlFlowOp(cf_op) => Some(*cf_op),
+            _ => None,
+        }
+    }
+
+    /// Returns the switch operation, if this is one.
+    pub fn as_switch(&self) -> Option<SwitchOp<'a>> {
+        match self.as_control_flow()? {
+            ControlFlowOp::Switch(switch) => Some(switch),
+            _ => None,
+        }
+    }
+
     /// Returns an iterator over the input or output values of this operation.
     ///
     /// # Errors
@@ -76,6 +150,15 @@ impl<'a> Operation<'a> {
         self.boundary(Direction::Outgoing)
     }
 
+    /// Returns the inputs or outputs of this operation, collected into a vector.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::ValueOutOfBounds`] if an encoded value references an invalid index in the value table.
+    pub fn boundary_vec(&self, direction: Direction) -> Result<Vec<WireValue<'a>>, ReadError> {
+        self.boundary(direction).collect()
+    }
+
     /// Returns the number of inputs or output values in this operation.
     pub fn boundary_count(&self, direction: Direction) -> usize {
         match direction {
@@ -148,6 +231,167 @@ impl<'a> Operation<'a> {
     pub fn output_types(&self) -> impl Iterator<Item = Result<Type, ReadError>> + 'a {
         self.outputs().map(move |res| res.map(|t| t.ty()))
     }
+
+    /// Returns `true` if both operations have the same operation type.
+    ///
+    /// The input and output values of the operations are ignored, so two
+    /// [`IntOp::Const32(5)`](crate::reader::optype::IntOp::Const32) operations
+    /// compare equal regardless of the wires they produce. Control flow
+    /// operations are equal when their nested regions are structurally equal,
+    /// including the value ids inside them.
+    pub fn op_type_eq(&self, other: &Operation<'_>) -> bool {
+        op_types_eq(&self.op_type(), &other.op_type())
+    }
+
+    /// Returns the input carrying the rotation angle of a Pauli-product
+    /// rotation gate.
+    ///
+    /// The angle is the first input after the qubit inputs, including any
+    /// control qubits. Returns `None` if the operation is not a Pauli-product
+    /// rotation, or if it has no angle input.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::ValueOutOfBounds`] if the encoded value references an invalid index in the value table.
+    pub fn ppr_angle(&self) -> Option<Result<WireValue<'a>, ReadError>> {
+        let OpType::QubitOp(QubitOp::Gate(gate)) = self.op_type() else {
+            return None;
+        };
+        if !matches!(gate.gate_type, GateOpType::PauliProdRotation { .. }) {
+            return None;
+        }
+        self.input(gate.num_qubits())
+    }
+
+    /// Returns the number of qubits collected by a qubit register
+    /// [`QubitRegisterOp::Create`] operation, i.e. the length of the created
+    /// register.
+    ///
+    /// Only qubit-typed inputs are counted, and inputs that cannot be read are
+    /// skipped. Returns `None` if the operation is not a register creation.
+    pub fn create_arity(&self) -> Option<usize> {
+        let OpType::QubitRegisterOp(QubitRegisterOp::Create) = self.op_type() else {
+            return None;
+        };
+        let qubits = self
+            .input_types()
+            .filter(|ty| matches!(ty, Ok(Type::Qubit)));
+        Some(qubits.count())
+    }
+
+    /// Returns the type of the elements collected by an [`IntArrayOp::Create`]
+    /// or [`FloatArrayOp::Create`] operation.
+    ///
+    /// This is the common type of the operation inputs. When there are no
+    /// inputs, the element type of the created array is used instead. Returns
+    /// `None` if the operation is not an array creation, or if it has no inputs
+    /// and no array output.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::MismatchedElementType`] for the first input whose type
+    ///   differs from the first input.
+    /// - [`ReadError::ValueOutOfBounds`] if an encoded value references an invalid index in the value table.
+    /// - [`ReadError::InvalidValue`] if the type of a value cannot be decoded.
+    ///
+    /// [`IntArrayOp::Create`]: super::optype::IntArrayOp::Create
+    /// [`FloatArrayOp::Create`]: super::optype::FloatArrayOp::Create
+    pub fn created_element_type(&self) -> Option<Result<Type, ReadError>> {
+        if !matches!(
+            self.op_type(),
+            OpType::IntArrayOp(IntArrayOp::Create) | OpType::FloatArrayOp(FloatArrayOp::Create)
+        ) {
+            return None;
+        }
+        let mut inputs = self.input_types();
+        let Some(first) = inputs.next() else {
+            return match self.output(0)? {
+                Ok(array) => array.ty().element_type().map(Ok),
+                Err(e) => Some(Err(e)),
+            };
+        };
+        let check = || {
+            let expected = first?;
+            for (idx, ty) in inputs.enumerate() {
+                let found = ty?;
+                if found != expected {
+                    return Err(ReadError::MismatchedElementType {
+                        idx: idx + 1,
+                        expected,
+                        found,
+                    });
+                }
+            }
+            Ok(expected)
+        };
+        Some(check())
+    }
+
+    /// Returns the location in the user source code that produced this operation.
+    ///
+    /// The location is read from the [`Operation::LOC_FILE_KEY`],
+    /// [`Operation::LOC_LINE_KEY`], and [`Operation::LOC_COL_KEY`] metadata
+    /// entries. Returns `None` if any of them is missing or malformed.
+    pub fn source_location(&self) -> Option<SourceLoc<'a>> {
+        let mut file = None;
+        let mut line = None;
+        let mut col = None;
+        let metadata = self.op.get_metadata().expect("Metadata should be present");
+        for meta in metadata.iter() {
+            let Ok(meta) = Metadata::try_read_capnp(meta, self.strings) else {
+                continue;
+            };
+            match meta.name() {
+                Self::LOC_FILE_KEY => file = meta.value_str(),
+                Self::LOC_LINE_KEY => line = meta.parse_u64(),
+                Self::LOC_COL_KEY => col = meta.parse_u64(),
+                _ => {}
+            }
+        }
+        Some(SourceLoc {
+            file: file?,
+            line: line?.try_into().ok()?,
+            col: col?.try_into().ok()?,
+        })
+    }
+
+    /// Returns the estimated cost of this operation, as annotated by the
+    /// producer in the [`Operation::COST_KEY`] metadata entry.
+    ///
+    /// Returns `None` if the entry is missing or does not hold a number.
+    pub fn cost_hint(&self) -> Option<f64> {
+        let metadata = self.op.get_metadata().expect("Metadata should be present");
+        metadata
+            .iter()
+            .filter_map(|meta| Metadata::try_read_capnp(meta, self.strings).ok())
+            .find(|meta| meta.name() == Self::COST_KEY)?
+            .parse_f64()
+    }
+
+    /// Returns the value of the metadata entry `key` as a string.
+    ///
+    /// Returns `None` if there is no such entry, or its value is not a string.
+    pub fn meta_str(&self, key: &str) -> Option<&str> {
+        self.metadata_by_name(key)?.value_str()
+    }
+
+    /// Returns the text value of the metadata entry `key`, parsed as a signed
+    /// integer.
+    ///
+    /// Returns `None` if there is no such entry, or its value is not text
+    /// holding an integer. See [`Metadata::parse_i64`].
+    pub fn meta_i64(&self, key: &str) -> Option<i64> {
+        self.metadata_by_name(key)?.parse_i64()
+    }
+
+    /// Returns the text value of the metadata entry `key`, parsed as a
+    /// floating point number.
+    ///
+    /// Returns `None` if there is no such entry, or its value is not text
+    /// holding a number. See [`Metadata::parse_f64`].
+    pub fn meta_f64(&self, key: &str) -> Option<f64> {
+        self.metadata_by_name(key)?.parse_f64()
+    }
 }
 
 impl<'a> HasMetadataSealed for Operation<'a> {
@@ -159,3 +403,332 @@ impl<'a> HasMetadataSealed for Operation<'a> {
         self.op.get_metadata().expect("Metadata should be present")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::reader::optype::ControlFlowOp;
+    use crate::reader::{ReadError, SourceLoc};
+    use crate::test::{build_program, definition, entangled_qs};
+    use crate::types::{FloatPrecision, Type};
+    use crate::{jeff_capnp, Direction, Jeff};
+    use rstest::rstest;
+
+    #[test]
+    fn created_element_type() {
+        // a = create(x, y, z); b = create(x, w); x = y
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(6);
+            for i in 0..6 {
+                let mut ty = values.reborrow().get(i).init_type();
+                match i {
+                    0..=2 => ty.set_int(8),
+                    3 => ty.set_int(16),
+                    _ => ty.init_int_array().set_bitwidth(8),
+                }
+            }
+
+            let mut body = def.init_body();
+            let mut sources = body.reborrow().init_sources(4);
+            for i in 0..4 {
+                sources.set(i, i);
+            }
+            let mut ops = body.init_operations(3);
+            for (i, inputs, output) in [(0, &[0, 1, 2][..], 4), (1, &[0, 3][..], 5)] {
+                let mut create = ops.reborrow().get(i);
+                let mut list = create.reborrow().init_inputs(inputs.len() as u32);
+                for (j, &input) in inputs.iter().enumerate() {
+                    list.set(j as u32, input);
+                }
+                create.reborrow().init_outputs(1).set(0, output);
+                create.init_instruction().init_int_array().set_create(());
+            }
+            let mut eq = ops.get(2);
+            let mut inputs = eq.reborrow().init_inputs(2);
+            inputs.set(0, 0);
+            inputs.set(1, 1);
+            eq.init_instruction().init_int().set_eq(());
+        });
+
+        let main = definition(&program, 0);
+        let body = main.body();
+        assert_eq!(
+            body.operation(0).created_element_type().unwrap().unwrap(),
+            Type::int(8)
+        );
+        assert!(matches!(
+            body.operation(1).created_element_type(),
+            Some(Err(ReadError::MismatchedElementType { idx: 1, .. }))
+        ));
+        assert!(body.operation(2).created_element_type().is_none());
+    }
+
+    #[test]
+    fn control_flow_downcasts() {
+        // switch x {} {}; q = alloc()
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(2);
+            values.reborrow().get(0).init_type().set_int(32);
+            values.reborrow().get(1).init_type().set_qubit(());
+            let mut ops = def.init_body().init_operations(2);
+            let mut switch = ops.reborrow().get(0);
+            switch.reborrow().init_inputs(1).set(0, 0);
+            switch
+                .init_instruction()
+                .init_scf()
+                .init_switch()
+                .init_branches(2);
+            let mut alloc = ops.get(1);
+            alloc.reborrow().init_outputs(1).set(0, 1);
+            alloc.init_instruction().init_qubit().set_alloc(());
+        });
+
+        let main = definition(&program, 0);
+        let switch = main.body().operation(0);
+        assert!(matches!(
+            switch.as_control_flow(),
+            Some(ControlFlowOp::Switch(_))
+        ));
+        assert_eq!(switch.as_switch().unwrap().branch_count(), 2);
+
+        let alloc = main.body().operation(1);
+        assert!(alloc.as_control_flow().is_none());
+        assert!(alloc.as_switch().is_none());
+    }
+
+    #[test]
+    fn cost_hint() {
+        let program = build_program(|mut module| {
+            let mut strings = module.reborrow().init_strings(2);
+            strings.set(0, "main");
+            strings.set(1, "cost");
+
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            def.reborrow()
+                .init_values(1)
+                .get(0)
+                .init_type()
+                .set_qubit(());
+            let mut op = def.init_body().init_operations(2).get(0);
+            op.reborrow().init_outputs(1).set(0, 0);
+            op.reborrow().init_instruction().init_qubit().set_alloc(());
+            let mut meta = op.init_metadata(1).get(0);
+            meta.set_name(1);
+            meta.init_value()
+                .set_as::<capnp::text::Owned>("2.5")
+                .unwrap();
+        });
+
+        let main = definition(&program, 0);
+        assert_eq!(main.body().operation(0).cost_hint(), Some(2.5));
+        assert_eq!(main.body().operation(1).cost_hint(), None);
+    }
+
+    #[test]
+    fn typed_metadata() {
+        let program = build_program(|mut module| {
+            let mut strings = module.reborrow().init_strings(3);
+            strings.set(0, "main");
+            strings.set(1, "label");
+            strings.set(2, "shots");
+
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            def.reborrow()
+                .init_values(1)
+                .get(0)
+                .init_type()
+                .set_qubit(());
+            let mut op = def.init_body().init_operations(1).get(0);
+            op.reborrow().init_outputs(1).set(0, 0);
+            op.reborrow().init_instruction().init_qubit().set_alloc(());
+            let mut metadata = op.init_metadata(2);
+            for (i, (key, value)) in [(1, "ancilla"), (2, "-12")].into_iter().enumerate() {
+                let mut meta = metadata.reborrow().get(i as u32);
+                meta.set_name(key);
+                meta.init_value()
+                    .set_as::<capnp::text::Owned>(value)
+                    .unwrap();
+            }
+        });
+
+        let main = definition(&program, 0);
+        let op = main.body().operation(0);
+        assert_eq!(op.meta_str("label"), Some("ancilla"));
+        assert_eq!(op.meta_i64("label"), None);
+        assert_eq!(op.meta_i64("shots"), Some(-12));
+        assert_eq!(op.meta_f64("shots"), Some(-12.0));
+        assert_eq!(op.meta_str("missing"), None);
+    }
+
+    #[test]
+    fn source_location() {
+        let program = build_program(|mut module| {
+            let mut strings = module.reborrow().init_strings(4);
+            strings.set(0, "main");
+            strings.set(1, "loc.file");
+            strings.set(2, "loc.line");
+            strings.set(3, "loc.col");
+
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            def.reborrow()
+                .init_values(1)
+                .get(0)
+                .init_type()
+                .set_qubit(());
+            let mut op = def.init_body().init_operations(2).get(0);
+            op.reborrow().init_outputs(1).set(0, 0);
+            op.reborrow().init_instruction().init_qubit().set_alloc(());
+            let mut metadata = op.init_metadata(3);
+            for (i, (key, value)) in [(1, "kernel.py"), (2, "12"), (3, "4")]
+                .into_iter()
+                .enumerate()
+            {
+                let mut meta = metadata.reborrow().get(i as u32);
+                meta.set_name(key);
+                meta.init_value()
+                    .set_as::<capnp::text::Owned>(value)
+                    .unwrap();
+            }
+        });
+
+        let main = definition(&program, 0);
+        let loc = main.body().operation(0).source_location();
+        assert_eq!(
+            loc,
+            Some(SourceLoc {
+                file: "kernel.py",
+                line: 12,
+                col: 4
+            })
+        );
+        assert_eq!(loc.unwrap().to_string(), "kernel.py:12:4");
+        assert_eq!(main.body().operation(1).source_location(), None);
+    }
+
+    #[test]
+    fn ppr_angle() {
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(5);
+            for i in 0..5 {
+                let mut ty = values.reborrow().get(i).init_type();
+                match i {
+                    2 => ty.set_float(jeff_capnp::FloatPrecision::Float64),
+                    _ => ty.set_qubit(()),
+                }
+            }
+
+            // q0, q1 = ppr_xz(angle, q0, q1); q0 = h(q0)
+            let mut ops = def.init_body().init_operations(2);
+            let mut ppr = ops.reborrow().get(0);
+            let mut inputs = ppr.reborrow().init_inputs(3);
+            for i in 0..3 {
+                inputs.set(i, i);
+            }
+            let mut outputs = ppr.reborrow().init_outputs(2);
+            outputs.set(0, 3);
+            outputs.set(1, 4);
+            let mut paulis = ppr
+                .init_instruction()
+                .init_qubit()
+                .init_gate()
+                .init_ppr()
+                .init_pauli_string(2);
+            paulis.set(0, jeff_capnp::Pauli::X);
+            paulis.set(1, jeff_capnp::Pauli::Z);
+
+            let mut h = ops.get(1);
+            h.reborrow().init_inputs(1).set(0, 3);
+            h.reborrow().init_outputs(1).set(0, 0);
+            h.init_instruction()
+                .init_qubit()
+                .init_gate()
+                .set_well_known(jeff_capnp::WellKnownGate::H);
+        });
+
+        let main = definition(&program, 0);
+        let body = main.body();
+        let angle = body.operation(0).ppr_angle().unwrap().unwrap();
+        assert_eq!(angle.id(), 2);
+        assert_eq!(angle.ty(), Type::float(FloatPrecision::Float64));
+        assert!(body.operation(1).ppr_angle().is_none());
+    }
+
+    #[test]
+    fn create_arity() {
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(4);
+            for i in 0..3 {
+                values.reborrow().get(i).init_type().set_qubit(());
+            }
+            values.get(3).init_type().init_qureg().set_dynamic(());
+
+            // reg = create(q0, q1, q2); free(reg)
+            let mut ops = def.init_body().init_operations(2);
+            let mut create = ops.reborrow().get(0);
+            let mut inputs = create.reborrow().init_inputs(3);
+            for i in 0..3 {
+                inputs.set(i, i);
+            }
+            create.reborrow().init_outputs(1).set(0, 3);
+            create.init_instruction().init_qureg().set_create(());
+            let mut free = ops.get(1);
+            free.reborrow().init_inputs(1).set(0, 3);
+            free.init_instruction().init_qureg().set_free(());
+        });
+
+        let main = definition(&program, 0);
+        assert_eq!(main.body().operation(0).create_arity(), Some(3));
+        assert_eq!(main.body().operation(1).create_arity(), None);
+    }
+
+    #[rstest]
+    fn boundary_vec(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+        for op in main.body().operations() {
+            for direction in Direction::BOTH {
+                let values = op.boundary_vec(direction).unwrap();
+                assert_eq!(values.len(), op.boundary_count(direction));
+            }
+        }
+    }
+
+    #[test]
+    fn op_type_eq_consts() {
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let body = function.init_definition().init_body();
+            let mut ops = body.init_operations(3);
+            for (i, val) in [5, 5, 6].into_iter().enumerate() {
+                let mut op = ops.reborrow().get(i as u32);
+                op.reborrow().init_outputs(1).set(0, i as u32);
+                op.init_instruction().init_int().set_const32(val);
+            }
+        });
+        let main = definition(&program, 0);
+        let body = main.body();
+        assert!(body.operation(0).op_type_eq(&body.operation(1)));
+        assert!(!body.operation(0).op_type_eq(&body.operation(2)));
+    }
+
+    #[rstest]
+    fn op_type_eq_gates(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+        let body = main.body();
+        // Two CNOTs acting on different qubits.
+        assert!(body.operation(6).op_type_eq(&body.operation(7)));
+        // A Hadamard and a CNOT.
+        assert!(!body.operation(5).op_type_eq(&body.operation(6)));
+        // A qubit allocation and a measurement.
+        assert!(!body.operation(0).op_type_eq(&body.operation(11)));
+    }
+}
@@ -5,12 +5,20 @@ mod control_flow;
 mod float;
 mod int;
 pub mod qubit;
+mod semantics;
+mod typing;
 
 pub use const_val::ConstArray;
 pub use control_flow::{ControlFlowOp, FuncOp, SwitchOp};
 pub use float::{FloatArrayOp, FloatOp};
 pub use int::{IntArrayOp, IntOp};
-pub use qubit::{GateOp, GateOpType, QubitOp, QubitRegisterOp, WellKnownGate};
+pub use qubit::{
+    GateCostKey, GateCostType, GateOp, GateOpType, GateSignature, GateTier, GateWireType, QubitOp,
+    QubitRegisterOp, RotationKind, WellKnownGate,
+};
+pub use semantics::OpSemantics;
+
+use alloc::boxed::Box;
 
 use crate::jeff_capnp;
 use crate::reader::value::ValueTable;
@@ -42,7 +50,45 @@ pub enum OpType<'a> {
     FuncOp(FuncOp),
 }
 
+/// The category of an operation, without any of its associated data.
+///
+/// See [`OpType::kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+#[non_exhaustive]
+pub enum OpKind {
+    /// Operation on a single qubit.
+    Qubit,
+    /// Operation on a register of qubits.
+    QubitRegister,
+    /// Operation involving an integer.
+    Int,
+    /// Operation involving an array of integers.
+    IntArray,
+    /// Operation involving a floating-point number.
+    Float,
+    /// Operation involving an array of floating-point numbers.
+    FloatArray,
+    /// Operation for control flow.
+    ControlFlow,
+    /// Operation involving a function.
+    Func,
+}
+
 impl<'a> OpType<'a> {
+    /// Returns the category of this operation.
+    pub fn kind(&self) -> OpKind {
+        match self {
+            OpType::QubitOp(_) => OpKind::Qubit,
+            OpType::QubitRegisterOp(_) => OpKind::QubitRegister,
+            OpType::IntOp(_) => OpKind::Int,
+            OpType::IntArrayOp(_) => OpKind::IntArray,
+            OpType::FloatOp(_) => OpKind::Float,
+            OpType::FloatArrayOp(_) => OpKind::FloatArray,
+            OpType::ControlFlowOp(_) => OpKind::ControlFlow,
+            OpType::FuncOp(_) => OpKind::Func,
+        }
+    }
+
     /// Create a new operation type from a capnp reader.
     pub(crate) fn read_capnp(
         op: jeff_capnp::op::instruction::Reader<'a>,
@@ -86,3 +132,35 @@ impl<'a> OpType<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{build_program, definition};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::qubit(OpType::QubitOp(QubitOp::Alloc), OpKind::Qubit)]
+    #[case::qureg(OpType::QubitRegisterOp(QubitRegisterOp::Alloc), OpKind::QubitRegister)]
+    #[case::int(OpType::IntOp(IntOp::Add), OpKind::Int)]
+    #[case::int_array(OpType::IntArrayOp(IntArrayOp::Length), OpKind::IntArray)]
+    #[case::float(OpType::FloatOp(FloatOp::Add), OpKind::Float)]
+    #[case::float_array(OpType::FloatArrayOp(FloatArrayOp::Length), OpKind::FloatArray)]
+    #[case::func(OpType::FuncOp(FuncOp { func_idx: 0 }), OpKind::Func)]
+    fn op_kind(#[case] op: OpType<'_>, #[case] kind: OpKind) {
+        assert_eq!(op.kind(), kind);
+    }
+
+    #[test]
+    fn control_flow_kind() {
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let body = function.init_definition().init_body();
+            let op = body.init_operations(1).get(0);
+            op.init_instruction().init_scf().init_for();
+        });
+        let main = definition(&program, 0);
+        let op_type = main.body().operation(0).op_type();
+        assert_eq!(op_type.kind(), OpKind::ControlFlow);
+    }
+}
@@ -1,5 +1,7 @@
 //! Operations returning a constant value.
 
+use alloc::vec::Vec;
+
 use capnp::private::layout::PrimitiveElement;
 
 /// An array of constant values of a primitive type.
@@ -12,17 +14,38 @@ where
     values: capnp::primitive_list::Reader<'a, T>,
 }
 
-impl<T: std::fmt::Debug> std::fmt::Debug for ConstArray<'_, T>
+impl<T: core::fmt::Debug> core::fmt::Debug for ConstArray<'_, T>
 where
     T: PrimitiveElement + Copy + capnp::introspect::Introspect,
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("ConstArray")
             .field("values", &self.values)
             .finish()
     }
 }
 
+impl<T> PartialEq for ConstArray<'_, T>
+where
+    T: PrimitiveElement + Copy + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.values().eq(other.values())
+    }
+}
+
+impl<T> Eq for ConstArray<'_, T> where T: PrimitiveElement + Copy + Eq {}
+
+impl<T> core::hash::Hash for ConstArray<'_, T>
+where
+    T: PrimitiveElement + Copy + core::hash::Hash,
+{
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        self.values().for_each(|value| value.hash(state));
+    }
+}
+
 impl<'a, T: PrimitiveElement + Copy> ConstArray<'a, T> {
     /// Create a new constant array from a capnp reader.
     pub(crate) fn read_capnp(values: capnp::primitive_list::Reader<'a, T>) -> Self {
@@ -53,3 +76,13 @@ impl<'a, T: PrimitiveElement + Copy> ConstArray<'a, T> {
         self.values.get(idx as u32)
     }
 }
+
+impl<T: PrimitiveElement + Copy + Into<u128>> ConstArray<'_, T> {
+    /// Returns the constant values zero-extended to `u128`.
+    ///
+    /// Values are treated as unsigned. Interpreting them as signed integers
+    /// of the original bitwidth is left to the consumer.
+    pub fn widen_to_u128(&self) -> Vec<u128> {
+        self.values().map(Into::into).collect()
+    }
+}
@@ -1,5 +1,8 @@
 //! Control-flow operations.
 
+use alloc::vec;
+use alloc::vec::Vec;
+
 use crate::reader::string_table::StringTable;
 use crate::reader::value::ValueTable;
 use crate::{jeff_capnp, reader};
@@ -46,7 +49,7 @@ pub enum ControlFlowOp<'a> {
 }
 
 /// A function call operation.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct FuncOp {
     /// The function index to call in the module.
     pub func_idx: u16,
@@ -104,6 +107,37 @@ impl<'a> ControlFlowOp<'a> {
             },
         }
     }
+
+    /// Returns the number of values in the loop state.
+    ///
+    /// For `For` loops this is the number of sources of the body, excluding
+    /// the iteration index. For `While` loops it is the number of sources of
+    /// the `before` region.
+    ///
+    /// Returns `None` for switch statements, which carry no loop state.
+    pub fn state_arity(&self) -> Option<usize> {
+        match self {
+            ControlFlowOp::For { region } => Some(region.source_count().saturating_sub(1)),
+            ControlFlowOp::While { before, .. } => Some(before.source_count()),
+            ControlFlowOp::Switch(_) => None,
+        }
+    }
+
+    /// Returns the regions nested directly in this operation.
+    ///
+    /// These are the body of `For` loops, the `before` and `after` regions of
+    /// `While` loops, and the branches of switch statements followed by their
+    /// default branch, if any.
+    pub fn regions(&self) -> Vec<reader::Region<'a>> {
+        match self {
+            ControlFlowOp::For { region } => vec![*region],
+            ControlFlowOp::While { before, after } => vec![*before, *after],
+            ControlFlowOp::Switch(switch_op) => switch_op
+                .branches()
+                .chain(switch_op.default_branch())
+                .collect(),
+        }
+    }
 }
 
 impl<'a> SwitchOp<'a> {
@@ -116,8 +150,9 @@ impl<'a> SwitchOp<'a> {
         let branches = switch.get_branches().expect("Branches should be present");
 
         let default = switch
-            .get_default()
-            .ok()
+            .has_default()
+            .then(|| switch.get_default().ok())
+            .flatten()
             .map(|r| reader::Region::read_capnp(r, strings, values));
 
         Self {
@@ -161,7 +196,122 @@ impl<'a> SwitchOp<'a> {
     /// Returns the default branch of this switch statement.
     ///
     /// Returns `None` if there is no default branch.
-    pub fn default_branch(&self) -> Option<reader::Region<'_>> {
+    pub fn default_branch(&self) -> Option<reader::Region<'a>> {
         self.default
     }
+
+    /// Returns the branch selected by `index` at runtime.
+    ///
+    /// This is the branch at `index` if there is one, and the default branch
+    /// otherwise. Returns `None` if `index` is out of range and there is no
+    /// default branch, in which case executing the switch is an error.
+    pub fn branch_for_index(&self, index: usize) -> Option<reader::Region<'a>> {
+        match index < self.branch_count() {
+            true => Some(self.branch(index)),
+            false => self.default,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::optype::OpType;
+    use crate::test::{build_program, definition};
+    use crate::Jeff;
+    use rstest::rstest;
+
+    /// Returns the state arity of the first operation in the program's first function.
+    fn first_state_arity(program: &Jeff<'_>) -> Option<usize> {
+        let main = definition(program, 0);
+        let OpType::ControlFlowOp(cf_op) = main.body().operation(0).op_type() else {
+            panic!("Expected a control flow operation");
+        };
+        cf_op.state_arity()
+    }
+
+    #[test]
+    fn for_state_arity() {
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let body = function.init_definition().init_body();
+            let op = body.init_operations(1).get(0);
+            let mut region = op.init_instruction().init_scf().init_for();
+            region.reborrow().init_sources(3);
+            region.init_targets(2);
+        });
+        assert_eq!(first_state_arity(&program), Some(2));
+    }
+
+    #[test]
+    fn while_state_arity() {
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let body = function.init_definition().init_body();
+            let op = body.init_operations(1).get(0);
+            let mut while_loop = op.init_instruction().init_scf().init_while();
+            let mut before = while_loop.reborrow().init_before();
+            before.reborrow().init_sources(1);
+            before.init_targets(2);
+            let mut after = while_loop.init_after();
+            after.reborrow().init_sources(1);
+            after.init_targets(1);
+        });
+        assert_eq!(first_state_arity(&program), Some(1));
+    }
+
+    #[test]
+    fn switch_state_arity() {
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let body = function.init_definition().init_body();
+            let op = body.init_operations(1).get(0);
+            op.init_instruction()
+                .init_scf()
+                .init_switch()
+                .init_branches(2);
+        });
+        assert_eq!(first_state_arity(&program), None);
+    }
+
+    /// Builds a switch whose branch `i` has `i + 1` operations, and whose
+    /// default branch, if any, has no operations.
+    fn switch_program(with_default: bool) -> Jeff<'static> {
+        build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let body = function.init_definition().init_body();
+            let op = body.init_operations(1).get(0);
+            let mut switch = op.init_instruction().init_scf().init_switch();
+            let mut branches = switch.reborrow().init_branches(2);
+            for i in 0..2 {
+                branches.reborrow().get(i).init_operations(i + 1);
+            }
+            if with_default {
+                switch.init_default();
+            }
+        })
+    }
+
+    #[rstest]
+    #[case::in_range(true, 1, Some(2))]
+    #[case::default(true, 5, Some(0))]
+    #[case::no_default(false, 5, None)]
+    fn branch_for_index(
+        #[case] with_default: bool,
+        #[case] index: usize,
+        #[case] op_count: Option<usize>,
+    ) {
+        let program = switch_program(with_default);
+        let main = definition(&program, 0);
+        let OpType::ControlFlowOp(cf_op) = main.body().operation(0).op_type() else {
+            panic!("Expected a control flow operation");
+        };
+        let ControlFlowOp::Switch(switch) = *cf_op else {
+            panic!("Expected a switch");
+        };
+        assert_eq!(
+            switch.branch_for_index(index).map(|r| r.operation_count()),
+            op_count
+        );
+    }
 }
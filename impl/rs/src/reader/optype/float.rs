@@ -5,7 +5,7 @@ use crate::jeff_capnp;
 use crate::types::FloatPrecision;
 
 /// An operation over floating point numbers.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[non_exhaustive]
 pub enum FloatOp {
     /// Create a constant 32 bit float.
@@ -75,7 +75,7 @@ pub enum FloatOp {
 }
 
 /// An operation over floating point arrays.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[non_exhaustive]
 pub enum FloatArrayOp<'a> {
     /// Create a constant 32 bit float array.
@@ -135,6 +135,80 @@ impl FloatOp {
             jeff_capnp::float_op::Which::Min(()) => Self::Min,
         }
     }
+
+    /// Returns the precision and value of a constant operation, with the
+    /// value widened to `f64`.
+    ///
+    /// Returns `None` if the operation is not [`FloatOp::Const32`] or
+    /// [`FloatOp::Const64`].
+    pub fn const_with_precision(&self) -> Option<(FloatPrecision, f64)> {
+        match *self {
+            Self::Const32(val) => Some((FloatPrecision::Float32, val as f64)),
+            Self::Const64(val) => Some((FloatPrecision::Float64, val)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl FloatOp {
+    /// Evaluates a unary operation on a constant input.
+    ///
+    /// Returns `None` if the operation does not take exactly one float input
+    /// and return a float. In particular, the [`FloatOp::IsNan`] and
+    /// [`FloatOp::IsInf`] predicates return `None`, as their result is an
+    /// integer.
+    ///
+    /// This requires the `std` feature.
+    pub fn eval_unary(&self, x: f64) -> Option<f64> {
+        let res = match self {
+            Self::Sqrt => x.sqrt(),
+            Self::Abs => x.abs(),
+            Self::Ceil => x.ceil(),
+            Self::Floor => x.floor(),
+            Self::Exp => x.exp(),
+            Self::Log => x.ln(),
+            Self::Sin => x.sin(),
+            Self::Cos => x.cos(),
+            Self::Tan => x.tan(),
+            Self::Asin => x.asin(),
+            Self::Acos => x.acos(),
+            Self::Atan => x.atan(),
+            Self::Sinh => x.sinh(),
+            Self::Cosh => x.cosh(),
+            Self::Tanh => x.tanh(),
+            Self::Asinh => x.asinh(),
+            Self::Acosh => x.acosh(),
+            Self::Atanh => x.atanh(),
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    /// Evaluates a binary operation on constant inputs.
+    ///
+    /// Returns `None` if the operation does not take exactly two float inputs
+    /// and return a float. In particular, the comparisons [`FloatOp::Eq`],
+    /// [`FloatOp::Lt`], and [`FloatOp::Lte`] return `None`, as their result
+    /// is an integer.
+    ///
+    /// [`FloatOp::Atan2`] computes `atan2(a, b)`, with `a` as the `y`
+    /// coordinate.
+    ///
+    /// This requires the `std` feature.
+    pub fn eval_binary(&self, a: f64, b: f64) -> Option<f64> {
+        let res = match self {
+            Self::Add => a + b,
+            Self::Sub => a - b,
+            Self::Mul => a * b,
+            Self::Pow => a.powf(b),
+            Self::Atan2 => a.atan2(b),
+            Self::Max => a.max(b),
+            Self::Min => a.min(b),
+            _ => return None,
+        };
+        Some(res)
+    }
 }
 
 impl<'a> FloatArrayOp<'a> {
@@ -162,3 +236,35 @@ impl<'a> FloatArrayOp<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn eval() {
+        assert_eq!(FloatOp::Sqrt.eval_unary(4.0), Some(2.0));
+        assert_eq!(FloatOp::Sqrt.eval_binary(4.0, 1.0), None);
+        assert_eq!(FloatOp::IsNan.eval_unary(f64::NAN), None);
+
+        let angle = FloatOp::Atan2.eval_binary(1.0, -1.0).unwrap();
+        assert!((angle - 3.0 * core::f64::consts::FRAC_PI_4).abs() < 1e-12);
+        assert_eq!(FloatOp::Atan2.eval_unary(1.0), None);
+        assert_eq!(FloatOp::Lt.eval_binary(1.0, 2.0), None);
+        assert_eq!(FloatOp::Const64(1.0).eval_unary(0.0), None);
+    }
+
+    #[test]
+    fn const_with_precision() {
+        assert_eq!(
+            FloatOp::Const32(1.5).const_with_precision(),
+            Some((FloatPrecision::Float32, 1.5))
+        );
+        assert_eq!(
+            FloatOp::Const64(-0.25).const_with_precision(),
+            Some((FloatPrecision::Float64, -0.25))
+        );
+        assert_eq!(FloatOp::Add.const_with_precision(), None);
+    }
+}
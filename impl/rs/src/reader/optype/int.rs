@@ -1,11 +1,13 @@
 //! Integer operations
 
+use alloc::vec::Vec;
+
 use crate::jeff_capnp;
 
 use super::ConstArray;
 
 /// An operation over integers.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub enum IntOp {
     /// Create a constant 1 bit integer.
@@ -69,7 +71,7 @@ pub enum IntOp {
 }
 
 /// An operation over integer arrays.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub enum IntArrayOp<'a> {
     /// Create a constant 1 bit integer array.
@@ -134,6 +136,107 @@ impl IntOp {
     }
 }
 
+impl IntOp {
+    /// Returns the bitwidth of the integer created by a constant operation.
+    ///
+    /// Returns `None` if this is not a constant operation.
+    pub fn const_width(&self) -> Option<u8> {
+        match self {
+            Self::Const1(_) => Some(1),
+            Self::Const8(_) => Some(8),
+            Self::Const16(_) => Some(16),
+            Self::Const32(_) => Some(32),
+            Self::Const64(_) => Some(64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value created by a constant operation, widened to 64 bits.
+    ///
+    /// Returns `None` if this is not a constant operation.
+    pub fn const_value_u64(&self) -> Option<u64> {
+        match *self {
+            Self::Const1(v) => Some(v as u64),
+            Self::Const8(v) => Some(v as u64),
+            Self::Const16(v) => Some(v as u64),
+            Self::Const32(v) => Some(v as u64),
+            Self::Const64(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl IntOp {
+    /// Evaluates a unary operation on a constant input.
+    ///
+    /// The input is interpreted as a 64-bit integer; the result is not
+    /// truncated to the bitwidth of the operation's type.
+    ///
+    /// Returns `None` if the operation does not take exactly one integer
+    /// input.
+    pub fn eval_unary(&self, x: i64) -> Option<i64> {
+        match self {
+            Self::Not => Some(!x),
+            Self::Abs => Some(x.wrapping_abs()),
+            _ => None,
+        }
+    }
+
+    /// Evaluates a binary operation on constant inputs.
+    ///
+    /// The inputs are interpreted as 64-bit integers, and reinterpreted as
+    /// unsigned for the unsigned variants of the operations. Arithmetic wraps
+    /// on overflow, and the result is not truncated to the bitwidth of the
+    /// operation's type. Signed division truncates towards zero.
+    ///
+    /// Returns `None` if the operation does not take exactly two integer
+    /// inputs and return an integer, if it divides by zero, if a shift amount
+    /// is out of range, or if a power has a negative exponent. Comparisons
+    /// return `None` too, as their result is a 1 bit integer rather than a
+    /// value of the inputs' type.
+    pub fn eval_binary(&self, a: i64, b: i64) -> Option<i64> {
+        let (ua, ub) = (a as u64, b as u64);
+        let res = match self {
+            Self::Add => a.wrapping_add(b),
+            Self::Sub => a.wrapping_sub(b),
+            Self::Mul => a.wrapping_mul(b),
+            Self::DivS => a.checked_div(b)?,
+            Self::DivU => ua.checked_div(ub)? as i64,
+            Self::RemS => a.checked_rem(b)?,
+            Self::RemU => ua.checked_rem(ub)? as i64,
+            Self::Pow => a.wrapping_pow(u32::try_from(b).ok()?),
+            Self::And => a & b,
+            Self::Or => a | b,
+            Self::Xor => a ^ b,
+            Self::MinS => a.min(b),
+            Self::MinU => ua.min(ub) as i64,
+            Self::MaxS => a.max(b),
+            Self::MaxU => ua.max(ub) as i64,
+            Self::Shl => ua.checked_shl(u32::try_from(b).ok()?)? as i64,
+            Self::Shr => ua.checked_shr(u32::try_from(b).ok()?)? as i64,
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    /// Returns the identity element of a binary operation, such that `x op e`
+    /// is `x` for any input `x`.
+    ///
+    /// For [`IntOp::Sub`] this is only a right identity.
+    ///
+    /// Returns `None` for operations without an identity element. This
+    /// includes [`IntOp::And`], whose identity is the all-ones value of the
+    /// operation's bitwidth and so cannot be determined from the operation
+    /// alone.
+    pub fn identity_element(&self) -> Option<u64> {
+        match self {
+            Self::Add | Self::Sub | Self::Or | Self::Xor => Some(0),
+            Self::Mul => Some(1),
+            _ => None,
+        }
+    }
+}
+
 impl<'a> IntArrayOp<'a> {
     /// Create a new integer array operation from a capnp reader.
     pub(crate) fn read_capnp(int_array_op: jeff_capnp::int_array_op::Reader<'a>) -> Self {
@@ -163,4 +266,102 @@ impl<'a> IntArrayOp<'a> {
             jeff_capnp::int_array_op::Which::Create(()) => Self::Create,
         }
     }
+
+    /// Returns the values of a constant array operation, zero-extended to
+    /// `u128` regardless of the array's bitwidth.
+    ///
+    /// Values are treated as unsigned. Interpreting them as signed integers
+    /// of the original bitwidth is left to the consumer.
+    ///
+    /// Returns `None` if this is not a constant array operation.
+    pub fn widen_to_u128(&self) -> Option<Vec<u128>> {
+        match self {
+            Self::ConstArray1(array) => Some(array.widen_to_u128()),
+            Self::ConstArray8(array) => Some(array.widen_to_u128()),
+            Self::ConstArray16(array) => Some(array.widen_to_u128()),
+            Self::ConstArray32(array) => Some(array.widen_to_u128()),
+            Self::ConstArray64(array) => Some(array.widen_to_u128()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::const1(IntOp::Const1(true), Some(1), Some(1))]
+    #[case::const8(IntOp::Const8(u8::MAX), Some(8), Some(u8::MAX as u64))]
+    #[case::const16(IntOp::Const16(1234), Some(16), Some(1234))]
+    #[case::const32(IntOp::Const32(u32::MAX), Some(32), Some(u32::MAX as u64))]
+    #[case::const64(IntOp::Const64(u64::MAX), Some(64), Some(u64::MAX))]
+    #[case::add(IntOp::Add, None, None)]
+    fn const_width(#[case] op: IntOp, #[case] width: Option<u8>, #[case] value: Option<u64>) {
+        assert_eq!(op.const_width(), width);
+        assert_eq!(op.const_value_u64(), value);
+    }
+
+    #[rstest]
+    #[case::div_s(IntOp::DivS, -7, 2, Some(-3))]
+    #[case::div_s_neg(IntOp::DivS, 7, -2, Some(-3))]
+    #[case::div_u(IntOp::DivU, -2, 2, Some(i64::MAX))]
+    #[case::div_zero(IntOp::DivS, 1, 0, None)]
+    #[case::div_overflow(IntOp::DivS, i64::MIN, -1, None)]
+    #[case::rem_s(IntOp::RemS, -7, 2, Some(-1))]
+    #[case::pow(IntOp::Pow, 3, 4, Some(81))]
+    #[case::pow_neg(IntOp::Pow, 3, -1, None)]
+    #[case::shl(IntOp::Shl, 1, 4, Some(16))]
+    #[case::shr(IntOp::Shr, -1, 60, Some(0xf))]
+    #[case::shr_out_of_range(IntOp::Shr, 1, 64, None)]
+    #[case::min_u(IntOp::MinU, -1, 1, Some(1))]
+    #[case::lt(IntOp::LtS, 1, 2, None)]
+    #[case::not(IntOp::Not, 1, 2, None)]
+    fn eval_binary(#[case] op: IntOp, #[case] a: i64, #[case] b: i64, #[case] res: Option<i64>) {
+        assert_eq!(op.eval_binary(a, b), res);
+    }
+
+    #[rstest]
+    #[case::add(IntOp::Add, Some(0))]
+    #[case::mul(IntOp::Mul, Some(1))]
+    #[case::and(IntOp::And, None)]
+    #[case::div_s(IntOp::DivS, None)]
+    fn identity_element(#[case] op: IntOp, #[case] identity: Option<u64>) {
+        assert_eq!(op.identity_element(), identity);
+        if let Some(e) = identity {
+            assert_eq!(op.eval_binary(42, e as i64), Some(42));
+        }
+    }
+
+    #[test]
+    fn widen_to_u128() {
+        let mut message = capnp::message::Builder::new_default();
+        let op = message.init_root::<jeff_capnp::int_array_op::Builder>();
+        let mut values = op.init_const8(3);
+        for (i, v) in [0, 7, u8::MAX].into_iter().enumerate() {
+            values.set(i as u32, v);
+        }
+        let reader = message.get_root_as_reader().unwrap();
+        let op = IntArrayOp::read_capnp(reader);
+        assert_eq!(op.widen_to_u128(), Some(vec![0, 7, 255]));
+
+        let mut message = capnp::message::Builder::new_default();
+        let op = message.init_root::<jeff_capnp::int_array_op::Builder>();
+        let mut values = op.init_const64(2);
+        values.set(0, 1 << 40);
+        values.set(1, u64::MAX);
+        let reader = message.get_root_as_reader().unwrap();
+        let op = IntArrayOp::read_capnp(reader);
+        assert_eq!(op.widen_to_u128(), Some(vec![1 << 40, u64::MAX as u128]));
+
+        assert_eq!(IntArrayOp::Length.widen_to_u128(), None);
+    }
+
+    #[test]
+    fn eval_unary() {
+        assert_eq!(IntOp::Not.eval_unary(0), Some(-1));
+        assert_eq!(IntOp::Abs.eval_unary(-5), Some(5));
+        assert_eq!(IntOp::Add.eval_unary(1), None);
+    }
 }
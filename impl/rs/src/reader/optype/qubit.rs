@@ -13,83 +13,105 @@
 //! These gates can also be controlled, made adjoint, and exponentiated.
 
 mod pauli;
+mod signature;
 mod well_known;
 
-pub use pauli::{Pauli, PauliString};
-pub use well_known::WellKnownGate;
+pub use pauli::{Pauli, PauliIter, PauliPhase, PauliString, PauliTerm};
+pub use signature::{GateSignature, GateWireType};
+pub use well_known::{GateTier, WellKnownGate};
+
+use alloc::string::{String, ToString};
 
 use crate::jeff_capnp;
 use crate::reader::string_table::StringTable;
 use crate::reader::ReadError;
 
 /// An operation over qubits.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, derive_more::Display)]
 #[non_exhaustive]
 pub enum QubitOp<'a> {
     /// Allocates a new qubit in the |0> state.
+    #[display("alloc")]
     Alloc,
     /// Frees a qubit.
     ///
     /// This operation makes no assumptions about the state of the qubit.
+    #[display("free")]
     Free,
     /// Frees a qubit in the |0> state.
     ///
     /// This operation can be used to avoid performing resets when it is known
     /// that the qubit has already been reset. It is undefined behavior to free
     /// a qubit that is not in the |0> state.
+    #[display("free_zero")]
     FreeZero,
     /// Perform a destructive measurement of a qubit in the computational basis.
+    #[display("measure")]
     Measure,
     /// Perform a non-destructive measurement of a qubit in the computational basis.
+    #[display("measure_nd")]
     MeasureNd,
     /// Resets a qubit to the |0> state.
+    #[display("reset")]
     Reset,
     /// Apply a quantum gate.
+    #[display("gate({_0})")]
     Gate(GateOp<'a>),
 }
 
 /// An operation over qubit registers.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, derive_more::Display)]
 #[non_exhaustive]
 pub enum QubitRegisterOp {
     /// Allocates a new qubit register given a number of qubits in the |0> state.
+    #[display("qureg.alloc")]
     Alloc = 0,
     /// Frees a qubit register.
     ///
     /// This operation makes no assumptions about the state of the qubits.
+    #[display("qureg.free")]
     Free = 10,
     /// Frees a qubit register, assuming that all qubits are in the |0> state.
     ///
     /// It is undefined behavior to free a qubit register containing qubits that are not in the |0> state.
+    #[display("qureg.free_zero")]
     FreeZero = 1,
     /// Extracts a single qubit from a qubit register.
     ///
     /// The slot must have been filled before and is marked as empty after the extraction.
+    #[display("qureg.extract_index")]
     ExtractIndex,
     /// Insert a single qubit into a qubit register.
     ///
     /// The slot must have been empty before and is marked as filled after the insertion.
+    #[display("qureg.insert_index")]
     InsertIndex,
     /// Extract a slice of qubits from a qubit register given a range of indices.
     ///
     /// All slots in the range are marked as empty in the original register.
+    #[display("qureg.extract_slice")]
     ExtractSlice,
     /// Insert a slice of qubits into a qubit register.
     ///
     /// All slots in the inserted range in the original register must have been empty.
+    #[display("qureg.insert_slice")]
     InsertSlice,
     /// Returns the length of the qubit register.
+    #[display("qureg.length")]
     Length,
     /// Splits a qubit register into two qubit registers at a given index.
+    #[display("qureg.split")]
     Split,
     /// Joins together two qubit registers into a single qubit register.
+    #[display("qureg.join")]
     Join,
     /// Creates a qubit register from a variable number of input qubits.
+    #[display("qureg.create")]
     Create,
 }
 
 /// Quantum gate operation.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub struct GateOp<'a> {
     /// The type of gate.
@@ -102,7 +124,74 @@ pub struct GateOp<'a> {
     pub power: u8,
 }
 
-impl GateOp<'_> {
+impl<'a> GateOp<'a> {
+    /// Returns this gate with `n` additional control qubits.
+    ///
+    /// The new controls are prepended to the gate's qubit inputs.
+    #[must_use]
+    pub fn controlled(self, n: u8) -> GateOp<'a> {
+        Self {
+            control_qubits: self.control_qubits + n,
+            ..self
+        }
+    }
+
+    /// Returns an owned key identifying this gate in a cost table.
+    ///
+    /// Custom gates are not normalized, see [`GateOp::normalize`].
+    pub fn cost_key(&self) -> GateCostKey {
+        let gate = match self.gate_type {
+            GateOpType::Custom {
+                name,
+                num_qubits,
+                num_params,
+            } => GateCostType::Custom {
+                name: name.to_string(),
+                num_qubits,
+                num_params,
+            },
+            GateOpType::WellKnown(gate) => GateCostType::WellKnown(gate),
+            GateOpType::PauliProdRotation { pauli_string } => GateCostType::PauliProdRotation {
+                weight: pauli_string.weight(),
+            },
+        };
+        GateCostKey {
+            gate,
+            control_qubits: self.control_qubits,
+            adjoint: self.adjoint,
+            power: self.power,
+        }
+    }
+
+    /// Returns the adjoint of this gate.
+    ///
+    /// Applying this twice returns the original gate.
+    #[must_use]
+    pub fn adjoint(self) -> GateOp<'a> {
+        Self {
+            adjoint: !self.adjoint,
+            ..self
+        }
+    }
+
+    /// Returns this gate raised to the power `p`.
+    ///
+    /// The power multiplies the gate's existing power.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the resulting power does not fit in a `u8`.
+    #[must_use]
+    pub fn powered(self, p: u8) -> GateOp<'a> {
+        Self {
+            power: self
+                .power
+                .checked_mul(p)
+                .expect("Gate power should fit in a u8"),
+            ..self
+        }
+    }
+
     /// If this gate has a custom identifier, try to convert it to a well-known
     /// gate and return the new gate operation.
     pub fn normalize(self) -> Self {
@@ -163,7 +252,11 @@ impl GateOp<'_> {
 }
 
 /// The type of gate operation.
-#[derive(Clone, Copy, Debug, derive_more::Display)]
+///
+/// Custom gates compare equal when their name, number of qubits, and number
+/// of parameters match. Pauli-product rotations compare their Pauli strings
+/// element-wise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, derive_more::Display)]
 pub enum GateOpType<'a> {
     /// A custom gate.
     #[display("Custom({name}, {num_qubits}, {num_params})")]
@@ -189,6 +282,50 @@ pub enum GateOpType<'a> {
     },
 }
 
+/// Owned key identifying a gate, for looking up per-gate costs.
+///
+/// Gates with the same type and modifiers produce equal keys. Gate parameters
+/// are runtime inputs, so they are not part of the key.
+///
+/// See [`GateOp::cost_key`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct GateCostKey {
+    /// The type of gate.
+    pub gate: GateCostType,
+    /// The number of control qubits for gate.
+    pub control_qubits: u8,
+    /// Whether the adjoint of the gate is applied.
+    pub adjoint: bool,
+    /// A number of times the gate is applied in sequence.
+    pub power: u8,
+}
+
+/// The type of gate in a [`GateCostKey`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GateCostType {
+    /// A custom gate.
+    Custom {
+        /// The name of the gate.
+        name: String,
+        /// The number of qubits the gate acts on.
+        num_qubits: u8,
+        /// The number of floating point parameters of the gate.
+        num_params: u8,
+    },
+    /// A gate in the common shared gate set.
+    WellKnown(WellKnownGate),
+    /// A Pauli-product rotation, identified by the number of qubits it acts on
+    /// non-trivially.
+    ///
+    /// See [`PauliString::weight`].
+    PauliProdRotation {
+        /// The weight of the Pauli string.
+        weight: usize,
+    },
+}
+
 impl<'a> Default for GateOpType<'a> {
     fn default() -> Self {
         GateOpType::WellKnown(WellKnownGate::I)
@@ -215,6 +352,24 @@ impl<'a> QubitOp<'a> {
             _ => unimplemented!(),
         }
     }
+
+    /// Returns `true` if this operation measures a qubit, destructively or not.
+    pub fn is_measurement(&self) -> bool {
+        matches!(self, Self::Measure | Self::MeasureNd)
+    }
+
+    /// Returns `true` if this operation allocates a new qubit.
+    pub fn is_allocation(&self) -> bool {
+        matches!(self, Self::Alloc)
+    }
+
+    /// Returns `true` if this operation frees a qubit.
+    ///
+    /// Destructive measurements also consume their qubit, but are reported by
+    /// [`QubitOp::is_measurement`] instead.
+    pub fn is_deallocation(&self) -> bool {
+        matches!(self, Self::Free | Self::FreeZero)
+    }
 }
 
 impl QubitRegisterOp {
@@ -326,6 +481,178 @@ impl<'a> GateOp<'a> {
             GateOpType::PauliProdRotation { pauli_string } => pauli_string.num_params(),
         }
     }
+
+    /// Returns the ordered input and output types of the gate, including its
+    /// control qubits.
+    pub fn signature(&self) -> GateSignature {
+        GateSignature::new(self.num_qubits(), self.num_params())
+    }
+
+    /// Returns the single-qubit Pauli rotation equivalent to this gate, if
+    /// any.
+    ///
+    /// `Rx`, `Ry`, and `Rz` are parametric rotations with `Rx(θ) = PPR_X(-θ/2)`,
+    /// and single-qubit Pauli-product rotations map to themselves. The `X`,
+    /// `Y`, `Z`, `S`, and `T` gates are fixed-angle rotations, equal to the
+    /// returned rotation up to a global phase.
+    ///
+    /// The adjoint and power modifiers are folded into the angle. Returns
+    /// `None` for controlled gates and gates that are not single-qubit Pauli
+    /// rotations.
+    pub fn as_pauli_rotation(&self) -> Option<(Pauli, RotationKind)> {
+        use core::f64::consts::{FRAC_PI_2, FRAC_PI_4, FRAC_PI_8};
+
+        if self.control_qubits > 0 {
+            return None;
+        }
+        let (pauli, kind) = match self.gate_type {
+            GateOpType::WellKnown(gate) => match gate {
+                WellKnownGate::Rx => (Pauli::X, RotationKind::Parametric { factor: -0.5 }),
+                WellKnownGate::Ry => (Pauli::Y, RotationKind::Parametric { factor: -0.5 }),
+                WellKnownGate::Rz => (Pauli::Z, RotationKind::Parametric { factor: -0.5 }),
+                WellKnownGate::X => (Pauli::X, RotationKind::Fixed { angle: -FRAC_PI_2 }),
+                WellKnownGate::Y => (Pauli::Y, RotationKind::Fixed { angle: -FRAC_PI_2 }),
+                WellKnownGate::Z => (Pauli::Z, RotationKind::Fixed { angle: -FRAC_PI_2 }),
+                WellKnownGate::S => (Pauli::Z, RotationKind::Fixed { angle: -FRAC_PI_4 }),
+                WellKnownGate::T => (Pauli::Z, RotationKind::Fixed { angle: -FRAC_PI_8 }),
+                _ => return None,
+            },
+            GateOpType::PauliProdRotation { pauli_string } if pauli_string.len() == 1 => {
+                match pauli_string.get(0) {
+                    Pauli::I => return None,
+                    pauli => (pauli, RotationKind::Parametric { factor: 1.0 }),
+                }
+            }
+            _ => return None,
+        };
+
+        let scale = match self.adjoint {
+            true => -(self.power as f64),
+            false => self.power as f64,
+        };
+        let kind = match kind {
+            RotationKind::Parametric { factor } => RotationKind::Parametric {
+                factor: factor * scale,
+            },
+            RotationKind::Fixed { angle } => RotationKind::Fixed {
+                angle: angle * scale,
+            },
+        };
+        Some((pauli, kind))
+    }
+
+    /// Returns whether this gate, acting on `qubits`, commutes with `other`,
+    /// acting on `other_qubits`.
+    ///
+    /// The qubit slices identify the qubits each gate acts on, including its
+    /// control qubits, e.g. by their index in a register. Gates acting on
+    /// disjoint qubits always commute. On shared qubits, gates that are both
+    /// diagonal in the computational basis commute, as do single-qubit
+    /// rotations about the same Pauli axis. Single-qubit rotations about
+    /// different axes by fixed angles, such as `X` and `Z`, do not commute.
+    ///
+    /// Returns `None` when commutation cannot be decided cheaply, e.g. for
+    /// custom gates or rotations by runtime angles.
+    pub fn commutes_with(
+        &self,
+        qubits: &[usize],
+        other: &GateOp<'_>,
+        other_qubits: &[usize],
+    ) -> Option<bool> {
+        if qubits.iter().all(|q| !other_qubits.contains(q)) {
+            return Some(true);
+        }
+        if self.is_diagonal() && other.is_diagonal() {
+            return Some(true);
+        }
+        let ((pauli, kind), (other_pauli, other_kind)) =
+            (self.as_pauli_rotation()?, other.as_pauli_rotation()?);
+        if pauli == other_pauli {
+            return Some(true);
+        }
+        // Rotations about anticommuting Paulis commute only when one of them
+        // is a multiple of the identity.
+        match (kind, other_kind) {
+            (RotationKind::Fixed { angle }, _) | (_, RotationKind::Fixed { angle })
+                if is_multiple_of_pi(angle) =>
+            {
+                Some(true)
+            }
+            (RotationKind::Fixed { .. }, RotationKind::Fixed { .. }) => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the gate is diagonal in the computational basis,
+    /// regardless of its parameters.
+    ///
+    /// Controlled diagonal gates are diagonal, as are the adjoints and powers
+    /// of diagonal gates.
+    fn is_diagonal(&self) -> bool {
+        match self.gate_type {
+            GateOpType::WellKnown(gate) => matches!(
+                gate,
+                WellKnownGate::GPhase
+                    | WellKnownGate::I
+                    | WellKnownGate::Z
+                    | WellKnownGate::S
+                    | WellKnownGate::T
+                    | WellKnownGate::R1
+                    | WellKnownGate::Rz
+            ),
+            GateOpType::PauliProdRotation { pauli_string } => pauli_string
+                .iter()
+                .all(|pauli| matches!(pauli, Pauli::I | Pauli::Z)),
+            GateOpType::Custom { .. } => false,
+        }
+    }
+}
+
+/// Returns `true` if `angle` is a multiple of π, up to rounding errors.
+fn is_multiple_of_pi(angle: f64) -> bool {
+    let turns = angle / core::f64::consts::PI;
+    let nearest = match turns >= 0.0 {
+        true => (turns + 0.5) as i64,
+        false => (turns - 0.5) as i64,
+    };
+    (turns - nearest as f64).abs() < 1e-9
+}
+
+/// Relation between a gate and the angle of an equivalent Pauli rotation.
+///
+/// Angles follow the Pauli-product rotation convention `PPR(θ) = exp(iθP)`.
+/// See [`GateOp::as_pauli_rotation`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum RotationKind {
+    /// The rotation angle is the gate's float parameter multiplied by `factor`.
+    Parametric {
+        /// Scale applied to the gate parameter.
+        factor: f64,
+    },
+    /// The gate is a rotation by a fixed angle, up to a global phase.
+    Fixed {
+        /// Rotation angle in radians.
+        angle: f64,
+    },
+}
+
+impl core::fmt::Display for GateOp<'_> {
+    /// Formats the gate as its type preceded by its modifiers, e.g.
+    /// `ctrl(2) adj H^3`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.control_qubits > 0 {
+            write!(f, "ctrl({}) ", self.control_qubits)?;
+        }
+        if self.adjoint {
+            write!(f, "adj ")?;
+        }
+        write!(f, "{}", self.gate_type)?;
+        if self.power != 1 {
+            write!(f, "^{}", self.power)?;
+        }
+        Ok(())
+    }
 }
 
 impl<'a> Default for GateOp<'a> {
@@ -355,4 +682,191 @@ mod tests {
         assert_eq!(gate.num_qubits(), num_qubits);
         assert_eq!(gate.num_params(), num_params);
     }
+
+    #[test]
+    fn test_gate_type_eq() {
+        let custom = |name| GateOpType::Custom {
+            name,
+            num_qubits: 2,
+            num_params: 1,
+        };
+        assert_eq!(custom("foo"), custom("foo"));
+        assert_ne!(custom("foo"), custom("bar"));
+        assert_ne!(
+            custom("foo"),
+            GateOpType::Custom {
+                name: "foo",
+                num_qubits: 2,
+                num_params: 0,
+            }
+        );
+        assert_eq!(
+            GateOpType::WellKnown(WellKnownGate::H),
+            GateOpType::WellKnown(WellKnownGate::H)
+        );
+        assert_ne!(GateOpType::WellKnown(WellKnownGate::H), custom("h"));
+    }
+
+    #[test]
+    fn test_as_pauli_rotation() {
+        let gate = |g| GateOp {
+            gate_type: GateOpType::WellKnown(g),
+            ..Default::default()
+        };
+        assert_eq!(
+            gate(WellKnownGate::Rx).as_pauli_rotation(),
+            Some((Pauli::X, RotationKind::Parametric { factor: -0.5 }))
+        );
+        assert_eq!(
+            gate(WellKnownGate::Rz)
+                .adjoint()
+                .powered(2)
+                .as_pauli_rotation(),
+            Some((Pauli::Z, RotationKind::Parametric { factor: 1.0 }))
+        );
+        assert_eq!(
+            gate(WellKnownGate::T).as_pauli_rotation(),
+            Some((
+                Pauli::Z,
+                RotationKind::Fixed {
+                    angle: -core::f64::consts::FRAC_PI_8
+                }
+            ))
+        );
+        assert_eq!(gate(WellKnownGate::H).as_pauli_rotation(), None);
+        assert_eq!(gate(WellKnownGate::U).as_pauli_rotation(), None);
+        assert_eq!(
+            gate(WellKnownGate::Rx).controlled(1).as_pauli_rotation(),
+            None
+        );
+    }
+
+    #[rstest]
+    #[case::alloc(QubitOp::Alloc, false, true, false)]
+    #[case::free(QubitOp::Free, false, false, true)]
+    #[case::free_zero(QubitOp::FreeZero, false, false, true)]
+    #[case::measure(QubitOp::Measure, true, false, false)]
+    #[case::measure_nd(QubitOp::MeasureNd, true, false, false)]
+    #[case::reset(QubitOp::Reset, false, false, false)]
+    #[case::gate(QubitOp::Gate(GateOp::default()), false, false, false)]
+    fn test_qubit_op_predicates(
+        #[case] op: QubitOp,
+        #[case] measurement: bool,
+        #[case] allocation: bool,
+        #[case] deallocation: bool,
+    ) {
+        assert_eq!(op.is_measurement(), measurement);
+        assert_eq!(op.is_allocation(), allocation);
+        assert_eq!(op.is_deallocation(), deallocation);
+    }
+
+    #[rstest]
+    #[case::alloc(QubitOp::Alloc, "alloc")]
+    #[case::free(QubitOp::Free, "free")]
+    #[case::free_zero(QubitOp::FreeZero, "free_zero")]
+    #[case::measure(QubitOp::Measure, "measure")]
+    #[case::measure_nd(QubitOp::MeasureNd, "measure_nd")]
+    #[case::reset(QubitOp::Reset, "reset")]
+    #[case::gate(QubitOp::Gate(GateOp { gate_type: GateOpType::WellKnown(WellKnownGate::H), ..Default::default() }), "gate(H)")]
+    #[case::gate_modifiers(QubitOp::Gate(GateOp { gate_type: GateOpType::WellKnown(WellKnownGate::X), control_qubits: 1, ..Default::default() }), "gate(ctrl(1) X)")]
+    fn test_qubit_op_display(#[case] op: QubitOp, #[case] expected: &str) {
+        assert_eq!(op.to_string(), expected);
+    }
+
+    #[rstest]
+    #[case::plain(GateOp { gate_type: GateOpType::WellKnown(WellKnownGate::H), ..Default::default() }, "H")]
+    #[case::controlled(GateOp { gate_type: GateOpType::WellKnown(WellKnownGate::X), control_qubits: 2, ..Default::default() }, "ctrl(2) X")]
+    #[case::adjoint(GateOp { gate_type: GateOpType::WellKnown(WellKnownGate::S), adjoint: true, ..Default::default() }, "adj S")]
+    #[case::power(GateOp { gate_type: GateOpType::WellKnown(WellKnownGate::T), power: 3, ..Default::default() }, "T^3")]
+    #[case::all(GateOp { gate_type: GateOpType::WellKnown(WellKnownGate::H), control_qubits: 2, adjoint: true, power: 3 }, "ctrl(2) adj H^3")]
+    #[case::custom(GateOp { gate_type: GateOpType::Custom { name: "foo", num_qubits: 2, num_params: 1 }, power: 2, ..Default::default() }, "Custom(foo, 2, 1)^2")]
+    fn test_gate_op_display(#[case] gate: GateOp, #[case] expected: &str) {
+        assert_eq!(gate.to_string(), expected);
+    }
+
+    #[rstest]
+    #[case::alloc(QubitRegisterOp::Alloc, "qureg.alloc")]
+    #[case::free(QubitRegisterOp::Free, "qureg.free")]
+    #[case::free_zero(QubitRegisterOp::FreeZero, "qureg.free_zero")]
+    #[case::extract_index(QubitRegisterOp::ExtractIndex, "qureg.extract_index")]
+    #[case::insert_index(QubitRegisterOp::InsertIndex, "qureg.insert_index")]
+    #[case::extract_slice(QubitRegisterOp::ExtractSlice, "qureg.extract_slice")]
+    #[case::insert_slice(QubitRegisterOp::InsertSlice, "qureg.insert_slice")]
+    #[case::length(QubitRegisterOp::Length, "qureg.length")]
+    #[case::split(QubitRegisterOp::Split, "qureg.split")]
+    #[case::join(QubitRegisterOp::Join, "qureg.join")]
+    #[case::create(QubitRegisterOp::Create, "qureg.create")]
+    fn test_qubit_register_op_display(#[case] op: QubitRegisterOp, #[case] expected: &str) {
+        assert_eq!(op.to_string(), expected);
+    }
+
+    #[test]
+    fn test_commutes_with() {
+        let gate = |g| GateOp {
+            gate_type: GateOpType::WellKnown(g),
+            ..Default::default()
+        };
+        let (x, z, rz) = (
+            gate(WellKnownGate::X),
+            gate(WellKnownGate::Z),
+            gate(WellKnownGate::Rz),
+        );
+
+        // Disjoint qubits.
+        assert_eq!(x.commutes_with(&[0], &z, &[1]), Some(true));
+        // Rotations about the same axis.
+        assert_eq!(rz.commutes_with(&[0], &rz, &[0]), Some(true));
+        // Diagonal gates, sharing one qubit.
+        let cz = z.controlled(1);
+        assert_eq!(cz.commutes_with(&[0, 1], &rz, &[1]), Some(true));
+        // Anticommuting Paulis.
+        assert_eq!(x.commutes_with(&[0], &z, &[0]), Some(false));
+        // X^2 is the identity, up to a global phase.
+        assert_eq!(x.powered(2).commutes_with(&[0], &z, &[0]), Some(true));
+        assert_eq!(z.commutes_with(&[0], &x.powered(2), &[0]), Some(true));
+        // Depends on the runtime angle.
+        assert_eq!(gate(WellKnownGate::Rx).commutes_with(&[0], &z, &[0]), None);
+        assert_eq!(gate(WellKnownGate::H).commutes_with(&[0], &z, &[0]), None);
+    }
+
+    #[test]
+    fn test_modifiers() {
+        let gate = GateOp::default().controlled(2);
+        assert_eq!(gate.control_qubits, 2);
+        assert_eq!(gate.num_qubits(), 3);
+
+        let gate = gate.controlled(1).adjoint().powered(3).powered(2);
+        assert_eq!(gate.num_qubits(), 4);
+        assert!(gate.adjoint);
+        assert_eq!(gate.power, 6);
+        assert!(!gate.adjoint().adjoint);
+    }
+
+    #[test]
+    fn test_cost_key() {
+        let custom = |name| GateOp {
+            gate_type: GateOpType::Custom {
+                name,
+                num_qubits: 2,
+                num_params: 1,
+            },
+            ..Default::default()
+        };
+        let name = String::from("foo");
+        assert_eq!(custom("foo").cost_key(), custom(&name).cost_key());
+        assert_ne!(custom("foo").cost_key(), custom("bar").cost_key());
+
+        let h = GateOp {
+            gate_type: GateOpType::WellKnown(WellKnownGate::H),
+            ..Default::default()
+        };
+        assert_eq!(h.controlled(1).cost_key(), h.controlled(1).cost_key());
+        assert_ne!(h.cost_key(), h.controlled(1).cost_key());
+        assert_ne!(h.cost_key(), h.adjoint().cost_key());
+        assert_ne!(h.cost_key(), h.powered(2).cost_key());
+
+        let key = h.adjoint().cost_key();
+        assert_eq!(key.gate, GateCostType::WellKnown(WellKnownGate::H));
+        assert!(key.adjoint);
+    }
 }
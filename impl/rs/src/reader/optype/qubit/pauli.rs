@@ -1,5 +1,8 @@
 //! Definitions for the Pauli-product rotation gate.
 
+use alloc::vec::Vec;
+use core::ops::Mul;
+
 use itertools::Itertools;
 
 use crate::jeff_capnp;
@@ -18,8 +21,17 @@ pub struct PauliString<'a> {
     paulis: capnp::enum_list::Reader<'a, jeff_capnp::Pauli>,
 }
 
+/// Iterator over the Pauli operators in a [`PauliString`].
+#[derive(Clone)]
+pub struct PauliIter<'a> {
+    /// List reader over the Pauli operators.
+    paulis: capnp::enum_list::Reader<'a, jeff_capnp::Pauli>,
+    /// Range of indices left to yield.
+    range: core::ops::Range<u32>,
+}
+
 /// A Pauli operator.
-#[derive(Clone, Copy, Debug, derive_more::Display)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, derive_more::Display)]
 #[display("Pauli({pauli})", pauli = self.name())]
 pub enum Pauli {
     /// Pauli-X operator.
@@ -32,6 +44,39 @@ pub enum Pauli {
     I,
 }
 
+/// A phase factor in `{+1, +i, -1, -i}`, as produced by multiplying Pauli
+/// operators.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, derive_more::Display)]
+pub enum PauliPhase {
+    /// `+1`
+    #[default]
+    #[display("+")]
+    One,
+    /// `+i`
+    #[display("+i")]
+    I,
+    /// `-1`
+    #[display("-")]
+    MinusOne,
+    /// `-i`
+    #[display("-i")]
+    MinusI,
+}
+
+/// A Pauli tensor product with a phase factor.
+///
+/// Unlike [`PauliString`], this owns its operators, so it can be built up by
+/// multiplying terms together. This is the basic element of a stabilizer
+/// table.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, derive_more::Display)]
+#[display("{phase}{paulis}", paulis = self.paulis.iter().map(|p| p.name()).join(""))]
+pub struct PauliTerm {
+    /// The Pauli operator acting on each qubit.
+    pub paulis: Vec<Pauli>,
+    /// The phase factor of the product.
+    pub phase: PauliPhase,
+}
+
 impl<'a> PauliString<'a> {
     /// Create a new Pauli string from a capnp reader.
     pub(super) fn read_capnp(
@@ -62,10 +107,16 @@ impl<'a> PauliString<'a> {
     }
 
     /// Returns an iterator over the Pauli operators in this string.
-    pub fn iter(&self) -> impl Iterator<Item = Pauli> + 'a {
-        self.paulis
-            .iter()
-            .map(|p| Pauli::read_capnp(p.expect("Invalid Pauli operator")))
+    pub fn iter(&self) -> PauliIter<'a> {
+        PauliIter {
+            paulis: self.paulis,
+            range: 0..self.paulis.len(),
+        }
+    }
+
+    /// Returns the number of non-identity Pauli operators in this string.
+    pub fn weight(&self) -> usize {
+        self.iter().filter(|&p| p != Pauli::I).count()
     }
 
     /// Returns the number of qubits that the gate acts on.
@@ -79,6 +130,78 @@ impl<'a> PauliString<'a> {
     }
 }
 
+impl PartialEq for PauliString<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for PauliString<'_> {}
+
+impl core::hash::Hash for PauliString<'_> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        self.iter().for_each(|pauli| pauli.hash(state));
+    }
+}
+
+impl<'a> IntoIterator for &PauliString<'a> {
+    type Item = Pauli;
+    type IntoIter = PauliIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for PauliString<'a> {
+    type Item = Pauli;
+    type IntoIter = PauliIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl PauliIter<'_> {
+    /// Decodes the Pauli operator at the given index.
+    fn read(&self, idx: u32) -> Pauli {
+        Pauli::read_capnp(self.paulis.get(idx).expect("Invalid Pauli operator"))
+    }
+}
+
+impl Iterator for PauliIter<'_> {
+    type Item = Pauli;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let idx = self.range.next()?;
+        Some(self.read(idx))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for PauliIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let idx = self.range.next_back()?;
+        Some(self.read(idx))
+    }
+}
+
+impl ExactSizeIterator for PauliIter<'_> {}
+
+impl core::iter::FusedIterator for PauliIter<'_> {}
+
+impl core::fmt::Debug for PauliIter<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PauliIter")
+            .field("range", &self.range)
+            .finish_non_exhaustive()
+    }
+}
+
 impl Pauli {
     /// Create a new well-known gate type from a capnp reader.
     pub(self) fn read_capnp(pauli: jeff_capnp::Pauli) -> Self {
@@ -102,3 +225,180 @@ impl Pauli {
         }
     }
 }
+
+impl PauliPhase {
+    /// Returns the phase as a power of `i`.
+    fn exponent(self) -> u8 {
+        match self {
+            Self::One => 0,
+            Self::I => 1,
+            Self::MinusOne => 2,
+            Self::MinusI => 3,
+        }
+    }
+
+    /// Returns the phase `i^exponent`.
+    fn from_exponent(exponent: u8) -> Self {
+        match exponent % 4 {
+            0 => Self::One,
+            1 => Self::I,
+            2 => Self::MinusOne,
+            _ => Self::MinusI,
+        }
+    }
+}
+
+impl Mul for PauliPhase {
+    type Output = PauliPhase;
+
+    // Multiplying powers of `i` adds their exponents.
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::from_exponent(self.exponent() + rhs.exponent())
+    }
+}
+
+impl Mul for Pauli {
+    type Output = (PauliPhase, Pauli);
+
+    /// Multiplies two Pauli operators, returning the resulting operator and
+    /// its phase factor.
+    fn mul(self, rhs: Self) -> Self::Output {
+        use Pauli::*;
+
+        match (self, rhs) {
+            (I, p) | (p, I) => (PauliPhase::One, p),
+            (X, X) | (Y, Y) | (Z, Z) => (PauliPhase::One, I),
+            (X, Y) => (PauliPhase::I, Z),
+            (Y, X) => (PauliPhase::MinusI, Z),
+            (Y, Z) => (PauliPhase::I, X),
+            (Z, Y) => (PauliPhase::MinusI, X),
+            (Z, X) => (PauliPhase::I, Y),
+            (X, Z) => (PauliPhase::MinusI, Y),
+        }
+    }
+}
+
+impl PauliTerm {
+    /// Returns the product of this term with `other`.
+    ///
+    /// The operators are multiplied qubit by qubit, accumulating their phases.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the terms act on a different number of qubits.
+    #[must_use]
+    pub fn multiply(&self, other: &PauliTerm) -> PauliTerm {
+        assert_eq!(
+            self.paulis.len(),
+            other.paulis.len(),
+            "Pauli terms should act on the same number of qubits"
+        );
+        let mut phase = self.phase * other.phase;
+        let paulis = self
+            .paulis
+            .iter()
+            .zip(&other.paulis)
+            .map(|(&a, &b)| {
+                let (p, pauli) = a * b;
+                phase = phase * p;
+                pauli
+            })
+            .collect();
+        PauliTerm { paulis, phase }
+    }
+}
+
+impl From<PauliString<'_>> for PauliTerm {
+    fn from(string: PauliString<'_>) -> Self {
+        Self {
+            paulis: string.iter().collect(),
+            phase: PauliPhase::One,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::optype::GateOpType;
+
+    #[test]
+    fn iterate_borrowed_string() {
+        let mut message = capnp::message::Builder::new_default();
+        let mut list = message.initn_root::<capnp::enum_list::Builder<'_, jeff_capnp::Pauli>>(3);
+        list.set(0, jeff_capnp::Pauli::X);
+        list.set(1, jeff_capnp::Pauli::I);
+        list.set(2, jeff_capnp::Pauli::Z);
+
+        let reader = message
+            .get_root_as_reader::<capnp::enum_list::Reader<'_, jeff_capnp::Pauli>>()
+            .unwrap();
+        let pauli_string = PauliString::read_capnp(reader);
+
+        let mut names = String::new();
+        for p in &pauli_string {
+            names.push_str(p.name());
+        }
+        assert_eq!(names, "XIZ");
+        assert_eq!(pauli_string.iter().len(), 3);
+        assert_eq!(pauli_string.weight(), 2);
+        assert_eq!(pauli_string.iter().rev().map(|p| p.name()).join(""), "ZIX");
+    }
+
+    #[test]
+    fn compare_strings() {
+        let build = |paulis: &[jeff_capnp::Pauli]| {
+            let mut message = capnp::message::Builder::new_default();
+            let mut list =
+                message.initn_root::<capnp::enum_list::Builder<'_, jeff_capnp::Pauli>>(
+                    paulis.len() as u32,
+                );
+            for (i, &p) in paulis.iter().enumerate() {
+                list.set(i as u32, p);
+            }
+            message
+        };
+        let xz = build(&[jeff_capnp::Pauli::X, jeff_capnp::Pauli::Z]);
+        let xz_again = build(&[jeff_capnp::Pauli::X, jeff_capnp::Pauli::Z]);
+        let xy = build(&[jeff_capnp::Pauli::X, jeff_capnp::Pauli::Y]);
+        fn read<A: capnp::message::Allocator>(
+            message: &capnp::message::Builder<A>,
+        ) -> PauliString<'_> {
+            PauliString::read_capnp(message.get_root_as_reader().unwrap())
+        }
+
+        assert_eq!(read(&xz), read(&xz_again));
+        assert_ne!(read(&xz), read(&xy));
+        assert_eq!(
+            GateOpType::PauliProdRotation {
+                pauli_string: read(&xz)
+            },
+            GateOpType::PauliProdRotation {
+                pauli_string: read(&xz_again)
+            }
+        );
+        assert_eq!(PauliTerm::from(read(&xz)).to_string(), "+XZ");
+    }
+
+    #[test]
+    fn multiply_terms() {
+        let xi = PauliTerm {
+            paulis: vec![Pauli::X, Pauli::I],
+            phase: PauliPhase::One,
+        };
+        let zi = PauliTerm {
+            paulis: vec![Pauli::Z, Pauli::I],
+            phase: PauliPhase::One,
+        };
+
+        let product = xi.multiply(&zi);
+        assert_eq!(product.paulis, [Pauli::Y, Pauli::I]);
+        assert_eq!(product.phase, PauliPhase::MinusI);
+        assert_eq!(product.to_string(), "-iYI");
+
+        // Multiplying back by `ZI` gives `-i * (YZ) = -i * iX = X`.
+        assert_eq!(product.multiply(&zi), xi);
+        assert_eq!(zi.multiply(&xi).phase, PauliPhase::I);
+    }
+}
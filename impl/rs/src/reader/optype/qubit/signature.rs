@@ -0,0 +1,53 @@
+//! Input and output types of gate operations.
+
+use alloc::vec::Vec;
+
+use crate::types::Type;
+
+/// The type of a gate input or output.
+///
+/// Gate parameters may be floats of any precision, so this is coarser than
+/// [`Type`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, derive_more::Display)]
+#[non_exhaustive]
+pub enum GateWireType {
+    /// A [`Type::Qubit`] acted on by the gate.
+    Qubit,
+    /// A [`Type::Float`] parameter, of any precision.
+    Float,
+}
+
+impl GateWireType {
+    /// Returns `true` if a value of type `ty` can be connected to this wire.
+    pub fn accepts(&self, ty: &Type) -> bool {
+        match self {
+            GateWireType::Qubit => matches!(ty, Type::Qubit),
+            GateWireType::Float => matches!(ty, Type::Float { .. }),
+        }
+    }
+}
+
+/// Ordered input and output types of a gate operation.
+///
+/// Gates take their qubits followed by their float parameters as inputs, and
+/// return the same qubits as outputs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GateSignature {
+    /// Input types, qubits first and then parameters.
+    pub inputs: Vec<GateWireType>,
+    /// Output types.
+    pub outputs: Vec<GateWireType>,
+}
+
+impl GateSignature {
+    /// Returns the signature of a gate acting on `num_qubits` qubits with
+    /// `num_params` float parameters.
+    pub fn new(num_qubits: usize, num_params: usize) -> Self {
+        let qubits = core::iter::repeat_n(GateWireType::Qubit, num_qubits);
+        let params = core::iter::repeat_n(GateWireType::Float, num_params);
+        Self {
+            inputs: qubits.clone().chain(params).collect(),
+            outputs: qubits.collect(),
+        }
+    }
+}
@@ -1,9 +1,20 @@
 //! Definitions for the well-known gates with well-defined semantics.
 
+#[cfg(feature = "std")]
+use alloc::vec;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_4};
+
+#[cfg(feature = "std")]
+use num_complex::Complex64;
+
+use super::GateSignature;
 use crate::jeff_capnp;
 
 /// Well-known quantum gates.
-#[derive(Clone, Copy, Debug, Default, derive_more::Display)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, derive_more::Display)]
 #[non_exhaustive]
 pub enum WellKnownGate {
     /// Global phase operation on the "vacuum" state (no qubits).
@@ -199,6 +210,25 @@ pub enum WellKnownGate {
     Swap,
 }
 
+/// Gate-set tiers of the well-known gates, ordered by increasing expressivity.
+///
+/// Backends supporting a given tier can usually execute the gates of all lower
+/// tiers. See [`WellKnownGate::tier`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+#[non_exhaustive]
+pub enum GateTier {
+    /// Pauli gates and the identity.
+    Pauli,
+    /// Non-Pauli Clifford gates.
+    Clifford,
+    /// Fixed non-Clifford phase gates, such as `T`.
+    Phase,
+    /// Parametrised rotations.
+    Rotation,
+    /// Arbitrary single-qubit unitaries.
+    Universal,
+}
+
 impl WellKnownGate {
     /// Create a new well-known gate type from a capnp reader.
     pub(super) fn read_capnp(well_known: jeff_capnp::WellKnownGate) -> Self {
@@ -246,6 +276,132 @@ impl WellKnownGate {
         }
     }
 
+    /// Returns the names of the float parameters that the gate takes as
+    /// inputs, in order.
+    ///
+    /// The names follow the matrix definitions of each gate, e.g. `["theta"]`
+    /// for [`WellKnownGate::Rz`] and `["theta", "phi", "lambda"]` for
+    /// [`WellKnownGate::U`]. There are always [`WellKnownGate::num_params`]
+    /// names.
+    #[inline]
+    #[must_use]
+    pub fn param_names(&self) -> &'static [&'static str] {
+        use WellKnownGate::*;
+
+        match self {
+            I | X | Y | Z | S | T | H | Swap => &[],
+            GPhase | R1 | Rx | Ry | Rz => &["theta"],
+            U => &["theta", "phi", "lambda"],
+        }
+    }
+
+    /// Returns the ordered input and output types of the gate.
+    ///
+    /// Inputs are the [`WellKnownGate::num_qubits`] qubits followed by the
+    /// [`WellKnownGate::num_params`] float parameters, and the same qubits are
+    /// returned as outputs.
+    #[must_use]
+    pub fn signature(&self) -> GateSignature {
+        GateSignature::new(self.num_qubits(), self.num_params())
+    }
+
+    /// Returns the gate-set tier of the gate.
+    ///
+    /// Tiers are ordered independently of the gate discriminants, so that
+    /// `X.tier() < T.tier() < Rx.tier() < U.tier()`.
+    #[inline]
+    #[must_use]
+    pub fn tier(&self) -> GateTier {
+        use WellKnownGate::*;
+
+        match self {
+            I | X | Y | Z => GateTier::Pauli,
+            S | H | Swap => GateTier::Clifford,
+            T => GateTier::Phase,
+            GPhase | R1 | Rx | Ry | Rz => GateTier::Rotation,
+            U => GateTier::Universal,
+        }
+    }
+
+    /// Number of well-known gates.
+    ///
+    /// Identifiers returned by [`WellKnownGate::to_u16`] are in `0..COUNT`.
+    pub const COUNT: u16 = 14;
+
+    /// Returns a stable integer identifier for the gate.
+    ///
+    /// Unlike the discriminants of the encoded schema, these values are part of
+    /// the public API and never change, so they can be used across FFI
+    /// boundaries. New gates are assigned the next free identifier.
+    ///
+    /// | Gate     | Id |
+    /// |----------|----|
+    /// | `GPhase` | 0  |
+    /// | `I`      | 1  |
+    /// | `X`      | 2  |
+    /// | `Y`      | 3  |
+    /// | `Z`      | 4  |
+    /// | `S`      | 5  |
+    /// | `T`      | 6  |
+    /// | `R1`     | 7  |
+    /// | `Rx`     | 8  |
+    /// | `Ry`     | 9  |
+    /// | `Rz`     | 10 |
+    /// | `H`      | 11 |
+    /// | `U`      | 12 |
+    /// | `Swap`   | 13 |
+    #[inline]
+    #[must_use]
+    pub const fn to_u16(&self) -> u16 {
+        use WellKnownGate::*;
+
+        match self {
+            GPhase => 0,
+            I => 1,
+            X => 2,
+            Y => 3,
+            Z => 4,
+            S => 5,
+            T => 6,
+            R1 => 7,
+            Rx => 8,
+            Ry => 9,
+            Rz => 10,
+            H => 11,
+            U => 12,
+            Swap => 13,
+        }
+    }
+
+    /// Returns the gate with the given stable identifier.
+    ///
+    /// See [`WellKnownGate::to_u16`]. Returns `None` if `id` is not assigned
+    /// to a gate.
+    #[inline]
+    #[must_use]
+    pub const fn from_u16(id: u16) -> Option<Self> {
+        use WellKnownGate::*;
+
+        let gate = match id {
+            0 => GPhase,
+            1 => I,
+            2 => X,
+            3 => Y,
+            4 => Z,
+            5 => S,
+            6 => T,
+            7 => R1,
+            8 => Rx,
+            9 => Ry,
+            10 => Rz,
+            11 => H,
+            12 => U,
+            13 => Swap,
+            _ => return None,
+        };
+        Some(gate)
+    }
+
     /// Returns the well known gate corresponding to the given name.
     pub fn from_name(name: &str) -> Option<Self> {
         let gate = match name.to_ascii_lowercase().as_str() {
@@ -268,3 +424,274 @@ impl WellKnownGate {
         Some(gate)
     }
 }
+
+#[cfg(feature = "std")]
+impl WellKnownGate {
+    /// Applies the gate to the `targets` qubits of a dense statevector,
+    /// in-place.
+    ///
+    /// Qubit `q` of the state corresponds to bit `q` of the basis state
+    /// indices. The first target is the most significant qubit of the gate's
+    /// matrix, so the `Swap` gate exchanges `targets[0]` and `targets[1]`.
+    ///
+    /// This is a reference implementation, intended to validate gate
+    /// definitions and decompositions rather than to simulate large circuits.
+    ///
+    /// This requires the `std` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `params` or `targets` do not match the number of parameters
+    /// or qubits of the gate, if the length of `state` is not a power of two,
+    /// or if a target is repeated or out of range for the state.
+    pub fn apply(&self, params: &[f64], state: &mut [Complex64], targets: &[usize]) {
+        assert_eq!(
+            params.len(),
+            self.num_params(),
+            "Wrong number of parameters"
+        );
+        assert_eq!(targets.len(), self.num_qubits(), "Wrong number of targets");
+        assert!(
+            state.len().is_power_of_two(),
+            "State length should be a power of two"
+        );
+        let num_qubits = state.len().trailing_zeros() as usize;
+        for (i, &target) in targets.iter().enumerate() {
+            assert!(target < num_qubits, "Target {target} is out of range");
+            assert!(
+                !targets[..i].contains(&target),
+                "Target {target} is repeated"
+            );
+        }
+
+        let matrix = self.matrix(params);
+        let dim = 1 << targets.len();
+        let target_mask: usize = targets.iter().map(|&t| 1 << t).sum();
+        // Basis state index for each row of the matrix, given the index with
+        // all the target bits cleared.
+        let index = |base: usize, row: usize| {
+            targets
+                .iter()
+                .enumerate()
+                .filter(|&(pos, _)| row & (1 << (targets.len() - 1 - pos)) != 0)
+                .fold(base, |idx, (_, &target)| idx | (1 << target))
+        };
+        let mut amplitudes = vec![Complex64::ZERO; dim];
+        for base in (0..state.len()).filter(|base| base & target_mask == 0) {
+            for (row, amplitude) in amplitudes.iter_mut().enumerate() {
+                *amplitude = (0..dim)
+                    .map(|col| matrix[row * dim + col] * state[index(base, col)])
+                    .sum();
+            }
+            for (row, amplitude) in amplitudes.iter().enumerate() {
+                state[index(base, row)] = *amplitude;
+            }
+        }
+    }
+
+    /// Returns the unitary matrix of the gate in row-major order, for the
+    /// given parameters.
+    fn matrix(&self, params: &[f64]) -> Vec<Complex64> {
+        let (zero, one, i) = (Complex64::ZERO, Complex64::ONE, Complex64::I);
+        let phase = |theta: f64| Complex64::from_polar(1.0, theta);
+        let (cos, sin) = match params.first() {
+            Some(theta) => ((theta / 2.0).cos(), (theta / 2.0).sin()),
+            None => (1.0, 0.0),
+        };
+        match self {
+            Self::GPhase => vec![phase(params[0])],
+            Self::I => vec![one, zero, zero, one],
+            Self::X => vec![zero, one, one, zero],
+            Self::Y => vec![zero, -i, i, zero],
+            Self::Z => vec![one, zero, zero, -one],
+            Self::S => vec![one, zero, zero, i],
+            Self::T => vec![one, zero, zero, phase(FRAC_PI_4)],
+            Self::R1 => vec![one, zero, zero, phase(params[0])],
+            Self::Rx => vec![cos.into(), -i * sin, -i * sin, cos.into()],
+            Self::Ry => vec![cos.into(), (-sin).into(), sin.into(), cos.into()],
+            Self::Rz => vec![phase(-params[0] / 2.0), zero, zero, phase(params[0] / 2.0)],
+            Self::H => {
+                let h = Complex64::from(FRAC_1_SQRT_2);
+                vec![h, h, h, -h]
+            }
+            Self::U => {
+                let (phi, lambda) = (params[1], params[2]);
+                vec![
+                    cos.into(),
+                    -phase(lambda) * sin,
+                    phase(phi) * sin,
+                    phase(phi + lambda) * cos,
+                ]
+            }
+            Self::Swap => vec![
+                one, zero, zero, zero, //
+                zero, zero, one, zero, //
+                zero, one, zero, zero, //
+                zero, zero, zero, one,
+            ],
+        }
+    }
+}
+
+// Checks at compile time that the stable identifiers round-trip and cover
+// exactly `0..COUNT`.
+const _: () = {
+    let mut id = 0;
+    while id < WellKnownGate::COUNT {
+        match WellKnownGate::from_u16(id) {
+            Some(gate) => assert!(gate.to_u16() == id),
+            None => panic!("Well-known gate identifiers should be contiguous"),
+        }
+        id += 1;
+    }
+    assert!(WellKnownGate::from_u16(WellKnownGate::COUNT).is_none());
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::optype::GateWireType::{Float, Qubit};
+
+    #[cfg(feature = "std")]
+    fn assert_state(state: &[Complex64], expected: &[Complex64]) {
+        assert_eq!(state.len(), expected.len());
+        for (a, b) in state.iter().zip(expected) {
+            assert!((a - b).norm() < 1e-12, "{state:?} != {expected:?}");
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn apply_h() {
+        let mut state = [Complex64::ONE, Complex64::ZERO];
+        WellKnownGate::H.apply(&[], &mut state, &[0]);
+        let amplitude = Complex64::from(core::f64::consts::FRAC_1_SQRT_2);
+        assert_state(&state, &[amplitude, amplitude]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn apply_swap() {
+        // |q1 q0> = |01>, i.e. qubit 0 is set.
+        let mut state = [Complex64::ZERO; 4];
+        state[0b01] = Complex64::ONE;
+        WellKnownGate::Swap.apply(&[], &mut state, &[0, 1]);
+        let mut expected = [Complex64::ZERO; 4];
+        expected[0b10] = Complex64::ONE;
+        assert_state(&state, &expected);
+
+        // Single-qubit gates only touch their target.
+        WellKnownGate::X.apply(&[], &mut state, &[0]);
+        expected = [Complex64::ZERO; 4];
+        expected[0b11] = Complex64::ONE;
+        assert_state(&state, &expected);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn matrix_is_unitary() {
+        let params = [0.3, -1.1, 2.5];
+        let gates = (0..)
+            .map_while(|idx| jeff_capnp::WellKnownGate::try_from(idx).ok())
+            .map(WellKnownGate::read_capnp);
+        for gate in gates {
+            let matrix = gate.matrix(&params[..gate.num_params()]);
+            let dim = 1 << gate.num_qubits();
+            assert_eq!(matrix.len(), dim * dim, "{gate}");
+            for (row, col) in (0..dim).flat_map(|row| (0..dim).map(move |col| (row, col))) {
+                // Entry (row, col) of `matrix * matrix^dagger`.
+                let product: Complex64 = (0..dim)
+                    .map(|k| matrix[row * dim + k] * matrix[col * dim + k].conj())
+                    .sum();
+                let expected = if row == col { 1.0 } else { 0.0 };
+                assert!(
+                    (product - expected).norm() < 1e-12,
+                    "{gate} is not unitary: {matrix:?}"
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn apply_u() {
+        let (theta, phi, lambda): (f64, f64, f64) = (0.7, 1.2, -0.4);
+        let phase = |angle: f64| Complex64::from_polar(1.0, angle);
+        let (cos, sin) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+
+        let mut state = [Complex64::ZERO, Complex64::ONE];
+        WellKnownGate::U.apply(&[theta, phi, lambda], &mut state, &[0]);
+        assert_state(&state, &[-phase(lambda) * sin, phase(phi + lambda) * cos]);
+
+        let mut state = [Complex64::ONE, Complex64::ZERO];
+        WellKnownGate::U.apply(&[theta, phi, lambda], &mut state, &[0]);
+        assert_state(&state, &[cos.into(), phase(phi) * sin]);
+    }
+
+    #[test]
+    fn signature() {
+        let u = WellKnownGate::U.signature();
+        assert_eq!(u.inputs, [Qubit, Float, Float, Float]);
+        assert_eq!(u.outputs, [Qubit]);
+
+        let gphase = WellKnownGate::GPhase.signature();
+        assert_eq!(gphase.inputs, [Float]);
+        assert!(gphase.outputs.is_empty());
+    }
+
+    #[test]
+    fn param_names() {
+        let gates = (0..)
+            .map_while(|idx| jeff_capnp::WellKnownGate::try_from(idx).ok())
+            .map(WellKnownGate::read_capnp);
+        for gate in gates {
+            assert_eq!(gate.param_names().len(), gate.num_params(), "{gate}");
+        }
+        assert_eq!(WellKnownGate::Rz.param_names(), ["theta"]);
+        assert_eq!(WellKnownGate::U.param_names(), ["theta", "phi", "lambda"]);
+        assert!(WellKnownGate::H.param_names().is_empty());
+    }
+
+    #[test]
+    fn u16_round_trip() {
+        let gates: Vec<_> = (0..)
+            .map_while(|idx| jeff_capnp::WellKnownGate::try_from(idx).ok())
+            .map(WellKnownGate::read_capnp)
+            .collect();
+        assert_eq!(gates.len(), WellKnownGate::COUNT as usize);
+        for gate in gates {
+            assert_eq!(WellKnownGate::from_u16(gate.to_u16()), Some(gate));
+        }
+        assert_eq!(WellKnownGate::from_u16(WellKnownGate::COUNT), None);
+    }
+
+    #[test]
+    fn tier() {
+        use WellKnownGate::*;
+
+        assert!(X.tier() < T.tier());
+        assert!(T.tier() < Rx.tier());
+        assert!(Rx.tier() < U.tier());
+        for pauli in [I, X, Y, Z] {
+            assert_eq!(pauli.tier(), GateTier::Pauli);
+        }
+        assert_eq!(H.tier(), GateTier::Clifford);
+    }
+
+    #[test]
+    fn gate_cost_table() {
+        let costs: std::collections::HashMap<WellKnownGate, u32> = [
+            (WellKnownGate::H, 1),
+            (WellKnownGate::T, 10),
+            (WellKnownGate::Swap, 3),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(costs.get(&WellKnownGate::T), Some(&10));
+        assert_eq!(
+            costs.get(&WellKnownGate::from_name("swap").unwrap()),
+            Some(&3)
+        );
+        assert_eq!(costs.get(&WellKnownGate::X), None);
+    }
+}
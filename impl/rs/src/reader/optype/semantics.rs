@@ -0,0 +1,309 @@
+//! Classification of operations by their semantics.
+
+use super::{
+    ControlFlowOp, FloatArrayOp, FloatOp, FuncOp, IntArrayOp, IntOp, OpType, QubitOp,
+    QubitRegisterOp,
+};
+
+/// Common semantic properties of operations.
+///
+/// This is implemented for [`OpType`] and for each of the operation enums it
+/// wraps, so that generic passes can classify operations without matching on
+/// every variant.
+pub trait OpSemantics {
+    /// Returns `true` if the operation has no side effects, so that it can be
+    /// removed when its outputs are unused or reordered with other pure
+    /// operations.
+    ///
+    /// Operations on qubits are never pure. Control flow operations are pure
+    /// when all the operations in their nested regions are. Function calls are
+    /// conservatively treated as impure, since the callee is not known.
+    fn is_pure(&self) -> bool;
+
+    /// Returns `true` if the operation may act on quantum state.
+    ///
+    /// Control flow operations are quantum when any operation in their nested
+    /// regions is. Function calls are conservatively treated as quantum, since
+    /// the callee is not known.
+    fn is_quantum(&self) -> bool;
+
+    /// Returns the number of inputs and outputs of the operation, if they are
+    /// fixed by the operation itself.
+    ///
+    /// Returns `None` for operations taking a variable number of inputs, such
+    /// as array constructors and function calls.
+    fn static_arity(&self) -> Option<(usize, usize)>;
+}
+
+impl OpSemantics for OpType<'_> {
+    fn is_pure(&self) -> bool {
+        match self {
+            OpType::QubitOp(op) => op.is_pure(),
+            OpType::QubitRegisterOp(op) => op.is_pure(),
+            OpType::IntOp(op) => op.is_pure(),
+            OpType::IntArrayOp(op) => op.is_pure(),
+            OpType::FloatOp(op) => op.is_pure(),
+            OpType::FloatArrayOp(op) => op.is_pure(),
+            OpType::ControlFlowOp(op) => op.is_pure(),
+            OpType::FuncOp(op) => op.is_pure(),
+        }
+    }
+
+    fn is_quantum(&self) -> bool {
+        match self {
+            OpType::QubitOp(op) => op.is_quantum(),
+            OpType::QubitRegisterOp(op) => op.is_quantum(),
+            OpType::IntOp(op) => op.is_quantum(),
+            OpType::IntArrayOp(op) => op.is_quantum(),
+            OpType::FloatOp(op) => op.is_quantum(),
+            OpType::FloatArrayOp(op) => op.is_quantum(),
+            OpType::ControlFlowOp(op) => op.is_quantum(),
+            OpType::FuncOp(op) => op.is_quantum(),
+        }
+    }
+
+    fn static_arity(&self) -> Option<(usize, usize)> {
+        match self {
+            OpType::QubitOp(op) => op.static_arity(),
+            OpType::QubitRegisterOp(op) => op.static_arity(),
+            OpType::IntOp(op) => op.static_arity(),
+            OpType::IntArrayOp(op) => op.static_arity(),
+            OpType::FloatOp(op) => op.static_arity(),
+            OpType::FloatArrayOp(op) => op.static_arity(),
+            OpType::ControlFlowOp(op) => op.static_arity(),
+            OpType::FuncOp(op) => op.static_arity(),
+        }
+    }
+}
+
+impl OpSemantics for QubitOp<'_> {
+    fn is_pure(&self) -> bool {
+        false
+    }
+
+    fn is_quantum(&self) -> bool {
+        true
+    }
+
+    fn static_arity(&self) -> Option<(usize, usize)> {
+        Some(match self {
+            QubitOp::Alloc => (0, 1),
+            QubitOp::Free | QubitOp::FreeZero => (1, 0),
+            QubitOp::Measure | QubitOp::Reset => (1, 1),
+            QubitOp::MeasureNd => (1, 2),
+            QubitOp::Gate(gate) => {
+                let signature = gate.signature();
+                (signature.inputs.len(), signature.outputs.len())
+            }
+        })
+    }
+}
+
+impl OpSemantics for QubitRegisterOp {
+    fn is_pure(&self) -> bool {
+        false
+    }
+
+    fn is_quantum(&self) -> bool {
+        true
+    }
+
+    fn static_arity(&self) -> Option<(usize, usize)> {
+        match self {
+            QubitRegisterOp::Alloc => Some((1, 1)),
+            QubitRegisterOp::Free | QubitRegisterOp::FreeZero => Some((1, 0)),
+            QubitRegisterOp::ExtractIndex => Some((2, 2)),
+            QubitRegisterOp::InsertIndex => Some((3, 1)),
+            QubitRegisterOp::ExtractSlice => Some((3, 2)),
+            QubitRegisterOp::InsertSlice => Some((3, 1)),
+            QubitRegisterOp::Length => Some((1, 2)),
+            QubitRegisterOp::Split => Some((2, 2)),
+            QubitRegisterOp::Join => Some((2, 1)),
+            QubitRegisterOp::Create => None,
+        }
+    }
+}
+
+impl OpSemantics for IntOp {
+    fn is_pure(&self) -> bool {
+        true
+    }
+
+    fn is_quantum(&self) -> bool {
+        false
+    }
+
+    fn static_arity(&self) -> Option<(usize, usize)> {
+        let inputs = match self {
+            IntOp::Const1(_)
+            | IntOp::Const8(_)
+            | IntOp::Const16(_)
+            | IntOp::Const32(_)
+            | IntOp::Const64(_) => 0,
+            IntOp::Not | IntOp::Abs => 1,
+            _ => 2,
+        };
+        Some((inputs, 1))
+    }
+}
+
+impl OpSemantics for IntArrayOp<'_> {
+    fn is_pure(&self) -> bool {
+        true
+    }
+
+    fn is_quantum(&self) -> bool {
+        false
+    }
+
+    fn static_arity(&self) -> Option<(usize, usize)> {
+        match self {
+            IntArrayOp::ConstArray1(_)
+            | IntArrayOp::ConstArray8(_)
+            | IntArrayOp::ConstArray16(_)
+            | IntArrayOp::ConstArray32(_)
+            | IntArrayOp::ConstArray64(_) => Some((0, 1)),
+            IntArrayOp::Zero { .. } | IntArrayOp::Length => Some((1, 1)),
+            IntArrayOp::GetIndex => Some((2, 1)),
+            IntArrayOp::SetIndex => Some((3, 1)),
+            IntArrayOp::Create => None,
+        }
+    }
+}
+
+impl OpSemantics for FloatOp {
+    fn is_pure(&self) -> bool {
+        true
+    }
+
+    fn is_quantum(&self) -> bool {
+        false
+    }
+
+    fn static_arity(&self) -> Option<(usize, usize)> {
+        let inputs = match self {
+            FloatOp::Const32(_) | FloatOp::Const64(_) => 0,
+            FloatOp::Add
+            | FloatOp::Sub
+            | FloatOp::Mul
+            | FloatOp::Pow
+            | FloatOp::Atan2
+            | FloatOp::Max
+            | FloatOp::Min
+            | FloatOp::Eq
+            | FloatOp::Lt
+            | FloatOp::Lte => 2,
+            _ => 1,
+        };
+        Some((inputs, 1))
+    }
+}
+
+impl OpSemantics for FloatArrayOp<'_> {
+    fn is_pure(&self) -> bool {
+        true
+    }
+
+    fn is_quantum(&self) -> bool {
+        false
+    }
+
+    fn static_arity(&self) -> Option<(usize, usize)> {
+        match self {
+            FloatArrayOp::Const32(_) | FloatArrayOp::Const64(_) => Some((0, 1)),
+            FloatArrayOp::Zero { .. } | FloatArrayOp::Length => Some((1, 1)),
+            FloatArrayOp::GetIndex => Some((2, 1)),
+            FloatArrayOp::SetIndex => Some((3, 1)),
+            FloatArrayOp::Create => None,
+        }
+    }
+}
+
+impl OpSemantics for ControlFlowOp<'_> {
+    fn is_pure(&self) -> bool {
+        self.regions()
+            .iter()
+            .all(|region| region.operations().all(|op| op.op_type().is_pure()))
+    }
+
+    fn is_quantum(&self) -> bool {
+        self.regions()
+            .iter()
+            .any(|region| region.operations().any(|op| op.op_type().is_quantum()))
+    }
+
+    // Loops take their bounds and loop state as inputs and return the loop
+    // state. Switch statements take a selector followed by the branch
+    // arguments, and return the branch results.
+    fn static_arity(&self) -> Option<(usize, usize)> {
+        match self {
+            ControlFlowOp::For { region } => {
+                let state = region.source_count().saturating_sub(1);
+                Some((state + 3, state))
+            }
+            ControlFlowOp::While { before, .. } => {
+                let state = before.source_count();
+                Some((state, state))
+            }
+            ControlFlowOp::Switch(switch_op) => {
+                let branch = switch_op
+                    .branches()
+                    .next()
+                    .or_else(|| switch_op.default_branch())?;
+                Some((branch.source_count() + 1, branch.target_count()))
+            }
+        }
+    }
+}
+
+impl OpSemantics for FuncOp {
+    fn is_pure(&self) -> bool {
+        false
+    }
+
+    fn is_quantum(&self) -> bool {
+        true
+    }
+
+    fn static_arity(&self) -> Option<(usize, usize)> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{definition, entangled_qs};
+    use crate::types::Type;
+    use crate::Jeff;
+    use rstest::rstest;
+
+    #[rstest]
+    fn gate_and_measurement(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+
+        let h = main.body().operation(5).op_type();
+        assert!(!h.is_pure());
+        assert!(h.is_quantum());
+        assert_eq!(h.static_arity(), Some((1, 1)));
+        assert_eq!(h.output_types(&[Type::Qubit]), Ok(vec![Type::Qubit]));
+
+        let measure = main.body().operation(11).op_type();
+        assert!(!measure.is_pure());
+        assert!(measure.is_quantum());
+        assert_eq!(measure.static_arity(), Some((1, 1)));
+        assert_eq!(measure.output_types(&[Type::Qubit]), Ok(vec![Type::bool()]));
+    }
+
+    #[test]
+    fn int_add() {
+        let add = OpType::IntOp(IntOp::Add);
+        assert!(add.is_pure());
+        assert!(!add.is_quantum());
+        assert_eq!(add.static_arity(), Some((2, 1)));
+        assert_eq!(
+            add.output_types(&[Type::int(32), Type::int(32)]),
+            Ok(vec![Type::int(32)])
+        );
+    }
+}
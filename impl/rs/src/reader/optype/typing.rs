@@ -0,0 +1,514 @@
+//! Typing rules for operations.
+//!
+//! These define the output types of each operation given its input types,
+//! and are shared by program builders and verifiers.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::types::{FloatPrecision, Type, TypeError};
+
+use super::{
+    ControlFlowOp, FloatArrayOp, FloatOp, IntArrayOp, IntOp, OpType, QubitOp, QubitRegisterOp,
+};
+
+impl OpType<'_> {
+    /// Returns the output types of this operation when applied to inputs of
+    /// the given types.
+    ///
+    /// Gates thread their qubits through, measurements produce an `Int1`,
+    /// arithmetic returns the type of its operands, and comparisons return an
+    /// `Int1`. Control flow operations return the types of the loop state or
+    /// branch arguments.
+    ///
+    /// # Errors
+    ///
+    /// - [`TypeError::WrongArity`] if the operation expects a different number
+    ///   of inputs.
+    /// - [`TypeError::InvalidInput`] for the first input whose type is not
+    ///   valid for the operation.
+    /// - [`TypeError::Underdetermined`] for function calls and for array
+    ///   constructors with no inputs.
+    pub fn output_types(&self, inputs: &[Type]) -> Result<Vec<Type>, TypeError> {
+        match self {
+            OpType::QubitOp(op) => qubit_output_types(op, inputs),
+            OpType::QubitRegisterOp(op) => qureg_output_types(*op, inputs),
+            OpType::IntOp(op) => int_output_types(*op, inputs),
+            OpType::IntArrayOp(op) => int_array_output_types(op, inputs),
+            OpType::FloatOp(op) => float_output_types(*op, inputs),
+            OpType::FloatArrayOp(op) => float_array_output_types(op, inputs),
+            OpType::ControlFlowOp(op) => control_flow_output_types(op, inputs),
+            OpType::FuncOp(_) => Err(TypeError::Underdetermined),
+        }
+    }
+}
+
+const INDEX: Type = Type::Int { bits: 32 };
+const BOOL: Type = Type::Int { bits: 1 };
+
+/// Checks that there are exactly `expected` inputs.
+fn arity(inputs: &[Type], expected: usize) -> Result<(), TypeError> {
+    match inputs.len() == expected {
+        true => Ok(()),
+        false => Err(TypeError::WrongArity {
+            expected,
+            found: inputs.len(),
+        }),
+    }
+}
+
+/// Checks that the input at `idx` satisfies `pred`, and returns its type.
+fn expect(inputs: &[Type], idx: usize, pred: impl Fn(&Type) -> bool) -> Result<Type, TypeError> {
+    let ty = inputs[idx];
+    match pred(&ty) {
+        true => Ok(ty),
+        false => Err(TypeError::InvalidInput { idx, ty }),
+    }
+}
+
+/// Checks that all inputs satisfy `pred` and share a single type, and returns it.
+///
+/// Returns `None` if there are no inputs.
+fn uniform(inputs: &[Type], pred: impl Fn(&Type) -> bool) -> Result<Option<Type>, TypeError> {
+    let Some(&first) = inputs.first() else {
+        return Ok(None);
+    };
+    expect(inputs, 0, &pred)?;
+    for (idx, &ty) in inputs.iter().enumerate().skip(1) {
+        if ty != first {
+            return Err(TypeError::InvalidInput { idx, ty });
+        }
+    }
+    Ok(Some(first))
+}
+
+fn is_int(ty: &Type) -> bool {
+    matches!(ty, Type::Int { .. })
+}
+
+fn is_float(ty: &Type) -> bool {
+    matches!(ty, Type::Float { .. })
+}
+
+fn is_qubit(ty: &Type) -> bool {
+    matches!(ty, Type::Qubit)
+}
+
+fn is_qureg(ty: &Type) -> bool {
+    matches!(ty, Type::QubitRegister { .. })
+}
+
+fn is_index(ty: &Type) -> bool {
+    *ty == INDEX
+}
+
+fn qubit_output_types(op: &QubitOp<'_>, inputs: &[Type]) -> Result<Vec<Type>, TypeError> {
+    match op {
+        QubitOp::Alloc => arity(inputs, 0).map(|_| vec![Type::Qubit]),
+        QubitOp::Free | QubitOp::FreeZero => {
+            arity(inputs, 1)?;
+            expect(inputs, 0, is_qubit)?;
+            Ok(vec![])
+        }
+        QubitOp::Measure | QubitOp::MeasureNd | QubitOp::Reset => {
+            arity(inputs, 1)?;
+            expect(inputs, 0, is_qubit)?;
+            Ok(match op {
+                QubitOp::Measure => vec![BOOL],
+                QubitOp::MeasureNd => vec![Type::Qubit, BOOL],
+                _ => vec![Type::Qubit],
+            })
+        }
+        QubitOp::Gate(gate) => {
+            let signature = gate.signature();
+            arity(inputs, signature.inputs.len())?;
+            for (idx, wire) in signature.inputs.iter().enumerate() {
+                expect(inputs, idx, |ty| wire.accepts(ty))?;
+            }
+            Ok(vec![Type::Qubit; signature.outputs.len()])
+        }
+    }
+}
+
+fn qureg_output_types(op: QubitRegisterOp, inputs: &[Type]) -> Result<Vec<Type>, TypeError> {
+    let dynamic = Type::QubitRegister { length: None };
+    match op {
+        QubitRegisterOp::Alloc => {
+            arity(inputs, 1)?;
+            expect(inputs, 0, is_index)?;
+            Ok(vec![dynamic])
+        }
+        QubitRegisterOp::Free | QubitRegisterOp::FreeZero => {
+            arity(inputs, 1)?;
+            expect(inputs, 0, is_qureg)?;
+            Ok(vec![])
+        }
+        QubitRegisterOp::ExtractIndex => {
+            arity(inputs, 2)?;
+            let reg = expect(inputs, 0, is_qureg)?;
+            expect(inputs, 1, is_index)?;
+            Ok(vec![reg, Type::Qubit])
+        }
+        QubitRegisterOp::InsertIndex => {
+            arity(inputs, 3)?;
+            let reg = expect(inputs, 0, is_qureg)?;
+            expect(inputs, 1, is_index)?;
+            expect(inputs, 2, is_qubit)?;
+            Ok(vec![reg])
+        }
+        QubitRegisterOp::ExtractSlice => {
+            arity(inputs, 3)?;
+            let reg = expect(inputs, 0, is_qureg)?;
+            expect(inputs, 1, is_index)?;
+            expect(inputs, 2, is_index)?;
+            Ok(vec![reg, dynamic])
+        }
+        QubitRegisterOp::InsertSlice => {
+            arity(inputs, 3)?;
+            let reg = expect(inputs, 0, is_qureg)?;
+            expect(inputs, 1, is_index)?;
+            expect(inputs, 2, is_qureg)?;
+            Ok(vec![reg])
+        }
+        QubitRegisterOp::Length => {
+            arity(inputs, 1)?;
+            let reg = expect(inputs, 0, is_qureg)?;
+            Ok(vec![reg, INDEX])
+        }
+        QubitRegisterOp::Split => {
+            arity(inputs, 2)?;
+            expect(inputs, 0, is_qureg)?;
+            expect(inputs, 1, is_index)?;
+            Ok(vec![dynamic, dynamic])
+        }
+        QubitRegisterOp::Join => {
+            arity(inputs, 2)?;
+            let Type::QubitRegister { length: left } = expect(inputs, 0, is_qureg)? else {
+                unreachable!()
+            };
+            let Type::QubitRegister { length: right } = expect(inputs, 1, is_qureg)? else {
+                unreachable!()
+            };
+            let length = left.zip(right).and_then(|(l, r)| l.checked_add(r));
+            Ok(vec![Type::QubitRegister { length }])
+        }
+        QubitRegisterOp::Create => {
+            for idx in 0..inputs.len() {
+                expect(inputs, idx, is_qubit)?;
+            }
+            Ok(vec![Type::QubitRegister {
+                length: u32::try_from(inputs.len()).ok(),
+            }])
+        }
+    }
+}
+
+fn int_output_types(op: IntOp, inputs: &[Type]) -> Result<Vec<Type>, TypeError> {
+    match op {
+        IntOp::Const1(_)
+        | IntOp::Const8(_)
+        | IntOp::Const16(_)
+        | IntOp::Const32(_)
+        | IntOp::Const64(_) => {
+            arity(inputs, 0)?;
+            let bits = op.const_width().expect("Constant should have a width");
+            Ok(vec![Type::int(bits)])
+        }
+        IntOp::Not | IntOp::Abs => {
+            arity(inputs, 1)?;
+            Ok(vec![expect(inputs, 0, is_int)?])
+        }
+        IntOp::Eq | IntOp::LtS | IntOp::LteS | IntOp::LtU | IntOp::LteU => {
+            arity(inputs, 2)?;
+            uniform(inputs, is_int)?;
+            Ok(vec![BOOL])
+        }
+        IntOp::Add
+        | IntOp::Sub
+        | IntOp::Mul
+        | IntOp::DivS
+        | IntOp::DivU
+        | IntOp::Pow
+        | IntOp::And
+        | IntOp::Or
+        | IntOp::Xor
+        | IntOp::MinS
+        | IntOp::MinU
+        | IntOp::MaxS
+        | IntOp::MaxU
+        | IntOp::RemS
+        | IntOp::RemU
+        | IntOp::Shl
+        | IntOp::Shr => {
+            arity(inputs, 2)?;
+            let ty = uniform(inputs, is_int)?.expect("Inputs should be present");
+            Ok(vec![ty])
+        }
+    }
+}
+
+fn float_output_types(op: FloatOp, inputs: &[Type]) -> Result<Vec<Type>, TypeError> {
+    match op {
+        FloatOp::Const32(_) => arity(inputs, 0).map(|_| vec![Type::float(FloatPrecision::Float32)]),
+        FloatOp::Const64(_) => arity(inputs, 0).map(|_| vec![Type::float(FloatPrecision::Float64)]),
+        FloatOp::Eq | FloatOp::Lt | FloatOp::Lte => {
+            arity(inputs, 2)?;
+            uniform(inputs, is_float)?;
+            Ok(vec![BOOL])
+        }
+        FloatOp::IsNan | FloatOp::IsInf => {
+            arity(inputs, 1)?;
+            expect(inputs, 0, is_float)?;
+            Ok(vec![BOOL])
+        }
+        FloatOp::Add
+        | FloatOp::Sub
+        | FloatOp::Mul
+        | FloatOp::Pow
+        | FloatOp::Atan2
+        | FloatOp::Max
+        | FloatOp::Min => {
+            arity(inputs, 2)?;
+            let ty = uniform(inputs, is_float)?.expect("Inputs should be present");
+            Ok(vec![ty])
+        }
+        FloatOp::Sqrt
+        | FloatOp::Abs
+        | FloatOp::Ceil
+        | FloatOp::Floor
+        | FloatOp::Exp
+        | FloatOp::Log
+        | FloatOp::Sin
+        | FloatOp::Cos
+        | FloatOp::Tan
+        | FloatOp::Asin
+        | FloatOp::Acos
+        | FloatOp::Atan
+        | FloatOp::Sinh
+        | FloatOp::Cosh
+        | FloatOp::Tanh
+        | FloatOp::Asinh
+        | FloatOp::Acosh
+        | FloatOp::Atanh => {
+            arity(inputs, 1)?;
+            Ok(vec![expect(inputs, 0, is_float)?])
+        }
+    }
+}
+
+fn int_array_output_types(op: &IntArrayOp<'_>, inputs: &[Type]) -> Result<Vec<Type>, TypeError> {
+    let is_array = |ty: &Type| matches!(ty, Type::IntArray { .. });
+    let constant = |bits: u8, len: usize| {
+        arity(inputs, 0)?;
+        Ok(vec![Type::int_array(bits, u32::try_from(len).ok())])
+    };
+    match op {
+        IntArrayOp::ConstArray1(array) => constant(1, array.len()),
+        IntArrayOp::ConstArray8(array) => constant(8, array.len()),
+        IntArrayOp::ConstArray16(array) => constant(16, array.len()),
+        IntArrayOp::ConstArray32(array) => constant(32, array.len()),
+        IntArrayOp::ConstArray64(array) => constant(64, array.len()),
+        IntArrayOp::Zero { bits } => {
+            arity(inputs, 1)?;
+            expect(inputs, 0, is_index)?;
+            Ok(vec![Type::int_array(*bits, None)])
+        }
+        IntArrayOp::GetIndex => {
+            arity(inputs, 2)?;
+            let Type::IntArray { bits, .. } = expect(inputs, 0, is_array)? else {
+                unreachable!()
+            };
+            expect(inputs, 1, is_index)?;
+            Ok(vec![Type::int(bits)])
+        }
+        IntArrayOp::SetIndex => {
+            arity(inputs, 3)?;
+            let array = expect(inputs, 0, is_array)?;
+            let Type::IntArray { bits, .. } = array else {
+                unreachable!()
+            };
+            expect(inputs, 1, is_index)?;
+            expect(inputs, 2, |ty| *ty == Type::int(bits))?;
+            Ok(vec![array])
+        }
+        IntArrayOp::Length => {
+            arity(inputs, 1)?;
+            expect(inputs, 0, is_array)?;
+            Ok(vec![INDEX])
+        }
+        IntArrayOp::Create => match uniform(inputs, is_int)? {
+            Some(Type::Int { bits }) => Ok(vec![Type::int_array(
+                bits,
+                u32::try_from(inputs.len()).ok(),
+            )]),
+            _ => Err(TypeError::Underdetermined),
+        },
+    }
+}
+
+fn float_array_output_types(
+    op: &FloatArrayOp<'_>,
+    inputs: &[Type],
+) -> Result<Vec<Type>, TypeError> {
+    let is_array = |ty: &Type| matches!(ty, Type::FloatArray { .. });
+    let constant = |precision: FloatPrecision, len: usize| {
+        arity(inputs, 0)?;
+        Ok(vec![Type::float_array(precision, u32::try_from(len).ok())])
+    };
+    match op {
+        FloatArrayOp::Const32(array) => constant(FloatPrecision::Float32, array.len()),
+        FloatArrayOp::Const64(array) => constant(FloatPrecision::Float64, array.len()),
+        FloatArrayOp::Zero { precision } => {
+            arity(inputs, 1)?;
+            expect(inputs, 0, is_index)?;
+            Ok(vec![Type::float_array(*precision, None)])
+        }
+        FloatArrayOp::GetIndex => {
+            arity(inputs, 2)?;
+            let Type::FloatArray { precision, .. } = expect(inputs, 0, is_array)? else {
+                unreachable!()
+            };
+            expect(inputs, 1, is_index)?;
+            Ok(vec![Type::float(precision)])
+        }
+        FloatArrayOp::SetIndex => {
+            arity(inputs, 3)?;
+            let array = expect(inputs, 0, is_array)?;
+            let Type::FloatArray { precision, .. } = array else {
+                unreachable!()
+            };
+            expect(inputs, 1, is_index)?;
+            expect(inputs, 2, |ty| *ty == Type::float(precision))?;
+            Ok(vec![array])
+        }
+        FloatArrayOp::Length => {
+            arity(inputs, 1)?;
+            expect(inputs, 0, is_array)?;
+            Ok(vec![INDEX])
+        }
+        FloatArrayOp::Create => match uniform(inputs, is_float)? {
+            Some(Type::Float { precision }) => Ok(vec![Type::float_array(
+                precision,
+                u32::try_from(inputs.len()).ok(),
+            )]),
+            _ => Err(TypeError::Underdetermined),
+        },
+    }
+}
+
+fn control_flow_output_types(
+    op: &ControlFlowOp<'_>,
+    inputs: &[Type],
+) -> Result<Vec<Type>, TypeError> {
+    match op {
+        // Loop bounds and step, followed by the loop state.
+        ControlFlowOp::For { .. } => {
+            if inputs.len() < 3 {
+                return Err(TypeError::WrongArity {
+                    expected: 3,
+                    found: inputs.len(),
+                });
+            }
+            uniform(&inputs[..3], is_int)?;
+            Ok(inputs[3..].to_vec())
+        }
+        ControlFlowOp::While { .. } => Ok(inputs.to_vec()),
+        // Branch selector, followed by the branch arguments.
+        ControlFlowOp::Switch(_) => {
+            if inputs.is_empty() {
+                return Err(TypeError::WrongArity {
+                    expected: 1,
+                    found: 0,
+                });
+            }
+            expect(inputs, 0, is_int)?;
+            Ok(inputs[1..].to_vec())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::optype::{FuncOp, GateOp, GateOpType, WellKnownGate};
+
+    #[test]
+    fn measure() {
+        let op = OpType::QubitOp(QubitOp::Measure);
+        assert_eq!(op.output_types(&[Type::Qubit]), Ok(vec![Type::bool()]));
+        assert_eq!(
+            op.output_types(&[Type::bool()]),
+            Err(TypeError::InvalidInput {
+                idx: 0,
+                ty: Type::bool()
+            })
+        );
+        assert_eq!(
+            OpType::QubitOp(QubitOp::MeasureNd).output_types(&[Type::Qubit]),
+            Ok(vec![Type::Qubit, Type::bool()])
+        );
+    }
+
+    #[test]
+    fn gate() {
+        let op = OpType::QubitOp(QubitOp::Gate(GateOp {
+            gate_type: GateOpType::WellKnown(WellKnownGate::Rz),
+            control_qubits: 1,
+            adjoint: false,
+            power: 1,
+        }));
+        let f64 = Type::float(FloatPrecision::Float64);
+        assert_eq!(
+            op.output_types(&[Type::Qubit, Type::Qubit, f64]),
+            Ok(vec![Type::Qubit, Type::Qubit])
+        );
+        assert_eq!(
+            op.output_types(&[Type::Qubit, Type::Qubit]),
+            Err(TypeError::WrongArity {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            op.output_types(&[Type::Qubit, f64, f64]),
+            Err(TypeError::InvalidInput { idx: 1, ty: f64 })
+        );
+    }
+
+    #[test]
+    fn add() {
+        let op = OpType::IntOp(IntOp::Add);
+        assert_eq!(
+            op.output_types(&[Type::int(32), Type::int(32)]),
+            Ok(vec![Type::int(32)])
+        );
+        assert_eq!(
+            op.output_types(&[Type::int(32), Type::int(8)]),
+            Err(TypeError::InvalidInput {
+                idx: 1,
+                ty: Type::int(8)
+            })
+        );
+        let f32 = Type::float(FloatPrecision::Float32);
+        assert_eq!(
+            OpType::FloatOp(FloatOp::Add).output_types(&[f32, f32]),
+            Ok(vec![f32])
+        );
+        assert_eq!(
+            OpType::FloatOp(FloatOp::Lt).output_types(&[f32, f32]),
+            Ok(vec![Type::bool()])
+        );
+    }
+
+    #[test]
+    fn underdetermined() {
+        assert_eq!(
+            OpType::FuncOp(FuncOp { func_idx: 0 }).output_types(&[]),
+            Err(TypeError::Underdetermined)
+        );
+        assert_eq!(
+            OpType::IntArrayOp(IntArrayOp::Create).output_types(&[]),
+            Err(TypeError::Underdetermined)
+        );
+    }
+}
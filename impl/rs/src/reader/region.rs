@@ -1,14 +1,73 @@
 //! Dataflow region definition in a jeff program.
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+
 use crate::capnp::jeff_capnp;
+use crate::reader::optype::OpType;
 use crate::reader::value::{ValueTable, WireValue};
+use crate::types::Type;
 use crate::Direction;
 
+use super::function::FunctionId;
 use super::metadata::sealed::HasMetadataSealed;
-use super::op::Operation;
+use super::op::{Operation, ResolvedOp};
 use super::string_table::StringTable;
 use super::value::ValueId;
 use super::ReadError;
 
+/// Location of an operation output within a region.
+///
+/// See [`Region::target_producer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OpPath {
+    /// Index of the operation in the region.
+    pub op: usize,
+    /// Index of the output port of the operation.
+    pub port: usize,
+}
+
+/// Location of an operation in a region, possibly inside nested control flow
+/// regions.
+///
+/// See [`Region::nested_operations`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NestedOpPath {
+    /// Enclosing control flow operations, from the region inwards.
+    ///
+    /// Each entry is the index of the operation in its region, and the index
+    /// of the nested region in [`ControlFlowOp::regions`](super::optype::ControlFlowOp::regions).
+    pub regions: Vec<(usize, usize)>,
+    /// Index of the operation in its region.
+    pub op: usize,
+}
+
+/// Plan for rebuilding a region without some of its operations.
+///
+/// The reader is immutable, so transformations such as dead code elimination
+/// rebuild the region with a builder following this plan.
+///
+/// See [`Region::plan_removal`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RebuildPlan {
+    /// Indices of the operations to keep, in order.
+    pub keep: Vec<usize>,
+    /// Values produced by the removed operations.
+    pub dangling: BTreeSet<ValueId>,
+    /// Dangling values that are still consumed by a kept operation or by the
+    /// region targets.
+    pub unresolved: BTreeSet<ValueId>,
+}
+
+impl RebuildPlan {
+    /// Returns `true` if the region can be rebuilt as planned, i.e. no kept
+    /// operation or region target consumes a dangling value.
+    pub fn is_valid(&self) -> bool {
+        self.unresolved.is_empty()
+    }
+}
+
 /// Dataflow region defined in a jeff module.
 #[derive(Clone, Copy, Debug)]
 pub struct Region<'a> {
@@ -70,6 +129,15 @@ impl<'a> Region<'a> {
         self.boundary(Direction::Outgoing)
     }
 
+    /// Returns the sources or targets of this region, collected into a vector.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::ValueOutOfBounds`] if an encoded value references an invalid index in the value table.
+    pub fn boundary_vec(&self, direction: Direction) -> Result<Vec<WireValue<'a>>, ReadError> {
+        self.boundary(direction).collect()
+    }
+
     /// Returns the number of sources or target values in this region.
     pub fn boundary_count(&self, direction: Direction) -> usize {
         match direction {
@@ -112,6 +180,18 @@ impl<'a> Region<'a> {
         Some(self.values.get(values.get(idx as ValueId)))
     }
 
+    /// Returns `true` if the value with the given id is on the boundary of this
+    /// region in the given direction.
+    pub(crate) fn boundary_contains(&self, direction: Direction, id: ValueId) -> bool {
+        match direction {
+            Direction::Incoming => self.region.get_sources(),
+            Direction::Outgoing => self.region.get_targets(),
+        }
+        .expect("Boundary should be present")
+        .iter()
+        .any(|value| value == id)
+    }
+
     /// Returns the source value at the given index, or `None` if the index is
     /// out of bounds.
     ///
@@ -143,6 +223,16 @@ impl<'a> Region<'a> {
             .map(move |op| Operation::read_capnp(op, strings_table, value_table))
     }
 
+    /// Returns an iterator over the operations in this region, with their
+    /// input and output values already resolved.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::ValueOutOfBounds`] if an encoded value references an invalid index in the value table.
+    pub fn operations_with_wires(&self) -> impl Iterator<Item = Result<ResolvedOp<'a>, ReadError>> {
+        self.operations().map(ResolvedOp::resolve)
+    }
+
     /// Returns the number of operations in this region.
     pub fn operation_count(&self) -> usize {
         self.region
@@ -166,6 +256,305 @@ impl<'a> Region<'a> {
             self.values,
         )
     }
+
+    /// Returns the operation output that defines the target value at the
+    /// given index.
+    ///
+    /// Returns `None` if the index is out of bounds, or if the target is not
+    /// produced by an operation in this region, e.g. when a source value is
+    /// passed through directly.
+    pub fn target_producer(&self, idx: usize) -> Option<OpPath> {
+        let targets = self
+            .region
+            .get_targets()
+            .expect("Boundary should be present");
+        if idx >= targets.len() as usize {
+            return None;
+        }
+        let target = targets.get(idx as u32);
+        self.region
+            .get_operations()
+            .expect("Ops should be present")
+            .iter()
+            .enumerate()
+            .find_map(|(op, reader)| {
+                let outputs = reader.get_outputs().expect("Outputs should be present");
+                let port = outputs.iter().position(|value| value == target)?;
+                Some(OpPath { op, port })
+            })
+    }
+
+    /// Plans the removal of the operations at the given indices.
+    ///
+    /// Regions are isolated from their enclosing scope, so only the kept
+    /// operations in this region and its targets can consume a dangling
+    /// value. Indices past the end of the region are ignored.
+    pub fn plan_removal(&self, remove: &BTreeSet<usize>) -> RebuildPlan {
+        let mut plan = RebuildPlan::default();
+        let ops = self.region.get_operations().expect("Ops should be present");
+        for (idx, op) in ops.iter().enumerate() {
+            if remove.contains(&idx) {
+                let outputs = op.get_outputs().expect("Outputs should be present");
+                plan.dangling.extend(outputs.iter());
+            } else {
+                plan.keep.push(idx);
+            }
+        }
+
+        let targets = self
+            .region
+            .get_targets()
+            .expect("Boundary should be present");
+        let kept_inputs = plan.keep.iter().flat_map(|&idx| {
+            let inputs = ops.get(idx as u32).get_inputs();
+            inputs.expect("Inputs should be present").iter()
+        });
+        plan.unresolved = kept_inputs
+            .chain(targets.iter())
+            .filter(|value| plan.dangling.contains(value))
+            .collect();
+        plan
+    }
+
+    /// Returns the dependencies between the operations in this region.
+    ///
+    /// For each operation, lists the indices of the operations in this region
+    /// that produce its inputs, in ascending order and without duplicates.
+    /// Inputs received from the region sources do not add a dependency.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::ValueOutOfBounds`] if an encoded value references an invalid index in the value table.
+    pub fn op_dependencies(&self) -> Result<Vec<Vec<usize>>, ReadError> {
+        let mut producers = BTreeMap::new();
+        for (idx, op) in self.operations().enumerate() {
+            for value in op.outputs() {
+                producers.insert(value?.id(), idx);
+            }
+        }
+
+        self.operations()
+            .map(|op| {
+                let mut deps = Vec::new();
+                for value in op.inputs() {
+                    if let Some(&producer) = producers.get(&value?.id()) {
+                        deps.push(producer);
+                    }
+                }
+                deps.sort_unstable();
+                deps.dedup();
+                Ok(deps)
+            })
+            .collect()
+    }
+
+    /// Returns the indices of the operations in this region that the `op_index`-th
+    /// operation transitively depends on.
+    ///
+    /// This is the dependency cone of the operation, e.g. the gates that
+    /// prepared the qubit consumed by a measurement. The operation itself is
+    /// not included.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::ValueOutOfBounds`] if an encoded value references an invalid index in the value table.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `op_index` is equal or greater than [`Region::operation_count`].
+    pub fn backward_slice(&self, op_index: usize) -> Result<BTreeSet<usize>, ReadError> {
+        let dependencies = self.op_dependencies()?;
+        let mut slice = BTreeSet::new();
+        let mut stack = dependencies[op_index].clone();
+        while let Some(op) = stack.pop() {
+            if slice.insert(op) {
+                stack.extend(&dependencies[op]);
+            }
+        }
+        Ok(slice)
+    }
+
+    /// Returns the indices of the operations in this region in a topological
+    /// order of their dependencies.
+    ///
+    /// Among the operations whose dependencies are satisfied, the one with the
+    /// lowest index comes first, so the order is deterministic and matches the
+    /// encoded order when that is already topological. Operations in a
+    /// dependency cycle, which only occur in invalid programs, are appended in
+    /// index order.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::ValueOutOfBounds`] if an encoded value references an invalid index in the value table.
+    pub fn topological_order(&self) -> Result<Vec<usize>, ReadError> {
+        let dependencies = self.op_dependencies()?;
+        let mut pending: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); dependencies.len()];
+        for (op, deps) in dependencies.iter().enumerate() {
+            for &dep in deps {
+                dependents[dep].push(op);
+            }
+        }
+
+        let mut ready: BTreeSet<usize> =
+            (0..pending.len()).filter(|&op| pending[op] == 0).collect();
+        let mut order = Vec::with_capacity(pending.len());
+        while let Some(op) = ready.pop_first() {
+            order.push(op);
+            for &dependent in &dependents[op] {
+                pending[dependent] -= 1;
+                if pending[dependent] == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+        if order.len() < pending.len() {
+            let placed: BTreeSet<usize> = order.iter().copied().collect();
+            order.extend((0..pending.len()).filter(|op| !placed.contains(op)));
+        }
+        Ok(order)
+    }
+
+    /// Returns an iterator over the functions called by operations in this
+    /// region.
+    ///
+    /// Calls inside nested control flow regions are not included. See
+    /// [`Region::called_functions_recursive`].
+    pub fn called_functions(&self) -> impl Iterator<Item = FunctionId> + 'a {
+        self.operations().filter_map(|op| match op.op_type() {
+            OpType::FuncOp(func_op) => Some(func_op.func_idx as FunctionId),
+            _ => None,
+        })
+    }
+
+    /// Returns the functions called by operations in this region or in any of
+    /// its nested control flow regions.
+    ///
+    /// Calls are listed in the order they appear, with nested regions visited
+    /// where their operation appears. A function called several times is
+    /// listed once per call.
+    pub fn called_functions_recursive(&self) -> Vec<FunctionId> {
+        self.fold_operations(Vec::new(), |mut calls, op| {
+            if let OpType::FuncOp(func_op) = op.op_type() {
+                calls.push(func_op.func_idx as FunctionId);
+            }
+            calls
+        })
+    }
+
+    /// Folds over the operations in this region and its nested control flow
+    /// regions.
+    ///
+    /// Operations are visited in order, and each control flow operation is
+    /// visited before the operations in its nested regions.
+    pub fn fold_operations<B, F>(&self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, &Operation<'a>) -> B,
+    {
+        self.fold_with(init, &mut f)
+    }
+
+    /// Returns every operation in this region and its nested control flow
+    /// regions, along with its path.
+    ///
+    /// Operations are visited in the same order as in
+    /// [`Region::fold_operations`].
+    pub fn nested_operations(&self) -> impl Iterator<Item = (NestedOpPath, Operation<'a>)> {
+        let mut ops = Vec::new();
+        self.collect_nested(&mut Vec::new(), &mut ops);
+        ops.into_iter()
+    }
+
+    /// Recursive helper for [`Region::nested_operations`].
+    fn collect_nested(
+        &self,
+        regions: &mut Vec<(usize, usize)>,
+        ops: &mut Vec<(NestedOpPath, Operation<'a>)>,
+    ) {
+        for (idx, op) in self.operations().enumerate() {
+            let path = NestedOpPath {
+                regions: regions.clone(),
+                op: idx,
+            };
+            ops.push((path, op));
+            if let OpType::ControlFlowOp(cf_op) = op.op_type() {
+                for (nested, nested_region) in cf_op.regions().into_iter().enumerate() {
+                    regions.push((idx, nested));
+                    nested_region.collect_nested(regions, ops);
+                    regions.pop();
+                }
+            }
+        }
+    }
+
+    /// Returns the nested region reached by following `regions` from this
+    /// region, as in [`NestedOpPath::regions`].
+    ///
+    /// Returns `None` if an entry does not refer to a nested region of a
+    /// control flow operation.
+    pub fn nested_region(&self, regions: &[(usize, usize)]) -> Option<Region<'a>> {
+        let mut region = *self;
+        for &(op, nested) in regions {
+            if op >= region.operation_count() {
+                return None;
+            }
+            let OpType::ControlFlowOp(cf_op) = region.operation(op).op_type() else {
+                return None;
+            };
+            region = cf_op.regions().into_iter().nth(nested)?;
+        }
+        Some(region)
+    }
+
+    /// Returns the operation at the given path.
+    ///
+    /// Returns `None` if the path does not refer to an operation in this
+    /// region or its nested regions.
+    pub fn operation_at(&self, path: &NestedOpPath) -> Option<Operation<'a>> {
+        let region = self.nested_region(&path.regions)?;
+        (path.op < region.operation_count()).then(|| region.operation(path.op))
+    }
+
+    /// Recursive helper for [`Region::fold_operations`].
+    fn fold_with<B, F>(&self, init: B, f: &mut F) -> B
+    where
+        F: FnMut(B, &Operation<'a>) -> B,
+    {
+        let mut acc = init;
+        for op in self.operations() {
+            acc = f(acc, &op);
+            if let OpType::ControlFlowOp(cf_op) = op.op_type() {
+                acc = cf_op
+                    .regions()
+                    .into_iter()
+                    .fold(acc, |acc, region| region.fold_with(acc, f));
+            }
+        }
+        acc
+    }
+
+    /// Returns an iterator over the types of the values produced inside this
+    /// region.
+    ///
+    /// These are the outputs of the region's operations, in order. Values
+    /// produced inside nested regions are not included, nor are the region's
+    /// sources.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::ValueOutOfBounds`] if an operation output references an invalid index in the value table.
+    /// - [`ReadError::InvalidValue`] if the type of a value cannot be decoded.
+    pub fn internal_value_types(
+        &self,
+    ) -> impl Iterator<Item = (ValueId, Result<Type, ReadError>)> + 'a {
+        let value_table = self.values;
+        self.region
+            .get_operations()
+            .expect("Ops should be present")
+            .iter()
+            .flat_map(|op| op.get_outputs().expect("Outputs should be present"))
+            .map(move |idx| (idx, value_table.get_type(idx)))
+    }
 }
 
 impl<'a> HasMetadataSealed for Region<'a> {
@@ -179,3 +568,177 @@ impl<'a> HasMetadataSealed for Region<'a> {
             .expect("Metadata should be present")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::OpPath;
+    use crate::reader::optype::{ControlFlowOp, OpType, QubitOp};
+    use crate::reader::ReadJeff;
+    use crate::test::{build_program, definition, entangled_calls, entangled_qs, nested_loop};
+    use crate::types::Type;
+    use crate::{Direction, Jeff};
+    use alloc::collections::BTreeSet;
+    use rstest::rstest;
+
+    #[rstest]
+    fn target_producer(entangled_calls: Jeff<'static>) {
+        let def = definition(&entangled_calls, 2);
+        let body = def.body();
+        assert_eq!(body.target_producer(0), Some(OpPath { op: 0, port: 0 }));
+        assert_eq!(body.target_producer(1), None);
+
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            def.reborrow()
+                .init_values(1)
+                .get(0)
+                .init_type()
+                .set_qubit(());
+            let mut body = def.init_body();
+            body.reborrow().init_sources(1).set(0, 0);
+            body.reborrow().init_targets(1).set(0, 0);
+            body.init_operations(0);
+        });
+        let def = definition(&program, 0);
+        assert_eq!(def.body().target_producer(0), None);
+    }
+
+    #[rstest]
+    fn boundary_vec(entangled_calls: Jeff<'static>) {
+        for (_, def) in entangled_calls.module().definitions() {
+            let body = def.body();
+            for direction in Direction::BOTH {
+                let values = body.boundary_vec(direction).unwrap();
+                assert_eq!(values.len(), body.boundary_count(direction));
+            }
+        }
+    }
+
+    #[rstest]
+    fn operations_with_wires(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+        let body = main.body();
+        let resolved: Vec<_> = body.operations_with_wires().map(Result::unwrap).collect();
+        assert_eq!(resolved.len(), body.operation_count());
+        for op in resolved {
+            let input_types: Vec<_> = op.inputs.iter().map(|v| v.ty()).collect();
+            let output_types: Vec<_> = op.outputs.iter().map(|v| v.ty()).collect();
+            let expected_inputs: Vec<_> = op.operation.input_types().map(Result::unwrap).collect();
+            let expected_outputs: Vec<_> =
+                op.operation.output_types().map(Result::unwrap).collect();
+            assert_eq!(input_types, expected_inputs);
+            assert_eq!(output_types, expected_outputs);
+        }
+    }
+
+    #[rstest]
+    fn op_dependencies(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+        let body = main.body();
+        let deps = body.op_dependencies().unwrap();
+        assert_eq!(deps.len(), body.operation_count());
+
+        // Allocations have no dependencies.
+        assert!(deps[..5].iter().all(Vec::is_empty));
+        // The first CNOT acts on the output of the Hadamard and the second qubit.
+        assert_eq!(deps[6], [1, 5]);
+        // Measuring the first qubit after the CNOT.
+        assert_eq!(deps[11], [6]);
+        // Storing the measurement into the array.
+        assert_eq!(deps[13], [10, 11, 12]);
+    }
+
+    #[rstest]
+    fn backward_slice(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+        let body = main.body();
+        // Measuring the first qubit depends on its allocation, the Hadamard,
+        // and the CNOT entangling it with the second qubit.
+        let slice = body.backward_slice(11).unwrap();
+        assert_eq!(slice, BTreeSet::from([0, 1, 5, 6]));
+        // Allocations depend on nothing.
+        assert!(body.backward_slice(0).unwrap().is_empty());
+    }
+
+    #[rstest]
+    fn called_functions(entangled_calls: Jeff<'static>) {
+        let wrapper = definition(&entangled_calls, 1);
+        let calls: Vec<_> = wrapper.body().called_functions().collect();
+        assert_eq!(calls, [0]);
+        assert_eq!(wrapper.body().called_functions_recursive(), calls);
+
+        let main = definition(&entangled_calls, calls[0]);
+        assert_eq!(main.body().called_functions().count(), 0);
+    }
+
+    #[test]
+    fn fold_operations() {
+        // for i in start..stop by step { q = h(q); q = h(q) }
+        let program = nested_loop(2, 1);
+        let main = definition(&program, 0);
+        let gates = main
+            .body()
+            .fold_operations(0, |count, op| match op.op_type() {
+                OpType::QubitOp(QubitOp::Gate(_)) => count + 1,
+                _ => count,
+            });
+        assert_eq!(gates, 2);
+        assert_eq!(main.body().fold_operations(0, |count, _| count + 1), 3);
+    }
+
+    #[test]
+    fn plan_removal() {
+        // x = const 1; q = alloc; return q
+        let program = build_program(|module| {
+            let function = module.init_functions(1).get(0);
+            let mut def = function.init_definition();
+            let mut values = def.reborrow().init_values(2);
+            values.reborrow().get(0).init_type().set_int(32);
+            values.get(1).init_type().set_qubit(());
+
+            let mut body = def.init_body();
+            body.reborrow().init_targets(1).set(0, 1);
+            let mut ops = body.init_operations(2);
+            let mut constant = ops.reborrow().get(0);
+            constant.reborrow().init_outputs(1).set(0, 0);
+            constant.init_instruction().init_int().set_const32(1);
+            let mut alloc = ops.get(1);
+            alloc.reborrow().init_outputs(1).set(0, 1);
+            alloc.init_instruction().init_qubit().set_alloc(());
+        });
+        let main = definition(&program, 0);
+
+        let plan = main.body().plan_removal(&BTreeSet::from([0]));
+        assert_eq!(plan.keep, [1]);
+        assert_eq!(plan.dangling, BTreeSet::from([0]));
+        assert!(plan.is_valid());
+
+        let plan = main.body().plan_removal(&BTreeSet::from([1]));
+        assert_eq!(plan.keep, [0]);
+        assert_eq!(plan.unresolved, BTreeSet::from([1]));
+        assert!(!plan.is_valid());
+    }
+
+    #[test]
+    fn internal_value_types() {
+        // for i in start..stop by step { q = h(q) }
+        let program = nested_loop(1, 1);
+        let main = definition(&program, 0);
+        let internal = |region: crate::reader::Region<'_>| {
+            region
+                .internal_value_types()
+                .map(|(idx, ty)| (idx, ty.unwrap()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(internal(main.body()), [(7, Type::Qubit)]);
+
+        let OpType::ControlFlowOp(cf) = main.body().operation(0).op_type() else {
+            panic!("Expected a control flow operation");
+        };
+        let ControlFlowOp::For { region } = *cf else {
+            panic!("Expected a for loop");
+        };
+        assert_eq!(internal(region), [(6, Type::Qubit)]);
+    }
+}
@@ -1,5 +1,7 @@
 //! Common string table stored at the module level.
 
+use alloc::vec::Vec;
+
 use super::ReadError;
 
 /// A string table stored at the module level.
@@ -44,8 +46,82 @@ impl<'a> StringTable<'a> {
         Ok(string)
     }
 
+    /// Returns the strings at the given indices, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error for the first index that cannot be resolved.
+    ///
+    /// - [`ReadError::StringOutOfBounds`] if an index is out of bounds.
+    /// - [`ReadError::StringNotUtf8`] if a string is not valid utf8.
+    pub fn get_many(
+        &self,
+        idxs: &[u16],
+        access_context: &'static str,
+    ) -> Result<Vec<&'a str>, ReadError> {
+        idxs.iter()
+            .map(|&idx| self.get(idx, access_context))
+            .collect()
+    }
+
+    /// Checks that every string in this table is valid utf8.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::StringNotUtf8`] for the first string that is not valid utf8.
+    pub fn verify_utf8(&self) -> Result<(), ReadError> {
+        for (idx, string) in self.strings.iter().enumerate() {
+            let string = string.expect("Invalid string table entry");
+            string.to_str().map_err(|e| ReadError::StringNotUtf8 {
+                context: "string table",
+                idx: idx as u32,
+                source: e,
+            })?;
+        }
+        Ok(())
+    }
+
     /// Returns the number of strings in this table.
     pub fn len(&self) -> usize {
         self.strings.len() as usize
     }
+
+    /// Returns `true` if this table has no strings.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::ReadJeff;
+    use crate::test::build_program;
+
+    #[test]
+    fn get_many() {
+        let program = build_program(|module| {
+            let mut strings = module.init_strings(3);
+            for (i, s) in ["main", "h", "cx"].into_iter().enumerate() {
+                strings.set(i as u32, s);
+            }
+        });
+        let strings = program.module().strings();
+
+        assert_eq!(
+            strings.get_many(&[2, 0, 2], "gate").unwrap(),
+            ["cx", "main", "cx"]
+        );
+        assert!(strings.get_many(&[], "gate").unwrap().is_empty());
+
+        let err = strings.get_many(&[1, 3, 4], "gate").unwrap_err();
+        assert!(matches!(
+            err,
+            ReadError::StringOutOfBounds {
+                context: "gate",
+                idx: 3,
+                count: 3
+            }
+        ));
+    }
 }
@@ -15,6 +15,7 @@ pub use function_io::FunctionIOValue;
 pub use wire_value::WireValue;
 
 use crate::capnp::jeff_capnp;
+use crate::types::Type;
 
 use super::string_table::StringTable;
 use super::ReadError;
@@ -67,6 +68,38 @@ impl<'a> ValueTable<'a> {
         })
     }
 
+    /// Returns the type of the wire value at the given index.
+    ///
+    /// Unlike [`ValueTable::get`], this only decodes the value's type and
+    /// skips its metadata.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReadError::ValueOutOfBounds`] if the index is out of bounds.
+    /// - [`ReadError::InvalidValue`] if the value's type cannot be decoded.
+    pub fn get_type(&self, idx: ValueId) -> Result<Type, ReadError> {
+        let value = self
+            .values
+            .try_get(idx)
+            .ok_or_else(|| ReadError::ValueOutOfBounds {
+                idx,
+                count: self.len(),
+            })?;
+
+        read_value_type(idx, value)
+    }
+
+    /// Returns an iterator over the types of the wire values in this table.
+    ///
+    /// This is cheaper than [`ValueTable::iter`] when only the types are
+    /// needed, as the values' metadata is not decoded.
+    pub fn iter_types(&self) -> impl Iterator<Item = (ValueId, Result<Type, ReadError>)> + 'a {
+        self.values.iter().enumerate().map(|(idx, value)| {
+            let idx = idx as ValueId;
+            (idx, read_value_type(idx, value))
+        })
+    }
+
     /// Returns the number of strings in this table.
     pub fn len(&self) -> usize {
         self.values.len() as usize
@@ -77,3 +110,36 @@ impl<'a> ValueTable<'a> {
         self.values.len() == 0
     }
 }
+
+/// Decode the type of a capnp value.
+fn read_value_type(idx: ValueId, value: jeff_capnp::value::Reader<'_>) -> Result<Type, ReadError> {
+    value
+        .get_type()
+        .map(Type::read_capnp)
+        .map_err(|source| ReadError::InvalidValue { idx, source })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{definition, entangled_qs};
+    use crate::Jeff;
+    use rstest::rstest;
+
+    #[rstest]
+    fn iter_types(entangled_qs: Jeff<'static>) {
+        let main = definition(&entangled_qs, 0);
+        let values = main.values();
+
+        let types = values.iter_types().map(|(idx, ty)| (idx, ty.unwrap()));
+        let expected = values.iter().map(|(idx, value)| (idx, value.ty()));
+        assert!(types.eq(expected));
+
+        let idx = values.len() as ValueId;
+        assert_eq!(values.get_type(0).unwrap(), values.get(0).unwrap().ty());
+        assert!(matches!(
+            values.get_type(idx),
+            Err(ReadError::ValueOutOfBounds { .. })
+        ));
+    }
+}
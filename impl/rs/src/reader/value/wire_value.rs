@@ -4,8 +4,9 @@ use super::ValueId;
 use crate::capnp::jeff_capnp;
 use crate::reader::metadata::sealed::HasMetadataSealed;
 use crate::reader::string_table::StringTable;
-
+use crate::reader::{HasMetadata, Region};
 use crate::types::Type;
+use crate::Direction;
 
 /// Wire type and associated metadata.
 ///
@@ -55,6 +56,47 @@ impl<'a> WireValue<'a> {
     pub fn ty(&self) -> Type {
         self.value_type
     }
+
+    /// Returns `true` if this value is one of the sources of `region`.
+    ///
+    /// Values are compared by id, so `region` should belong to the same
+    /// function as this value.
+    pub fn is_source_of(&self, region: &Region<'_>) -> bool {
+        region.boundary_contains(Direction::Incoming, self.id)
+    }
+
+    /// Returns `true` if this value is one of the targets of `region`.
+    ///
+    /// Values are compared by id, so `region` should belong to the same
+    /// function as this value.
+    pub fn is_target_of(&self, region: &Region<'_>) -> bool {
+        region.boundary_contains(Direction::Outgoing, self.id)
+    }
+
+    /// Returns the value of the metadata entry `key` as a string.
+    ///
+    /// Returns `None` if there is no such entry, or its value is not a string.
+    pub fn meta_str(&self, key: &str) -> Option<&str> {
+        self.metadata_by_name(key)?.value_str()
+    }
+
+    /// Returns the text value of the metadata entry `key`, parsed as a signed
+    /// integer.
+    ///
+    /// Returns `None` if there is no such entry, or its value is not text
+    /// holding an integer. See [`Metadata::parse_i64`](crate::reader::Metadata::parse_i64).
+    pub fn meta_i64(&self, key: &str) -> Option<i64> {
+        self.metadata_by_name(key)?.parse_i64()
+    }
+
+    /// Returns the text value of the metadata entry `key`, parsed as a
+    /// floating point number.
+    ///
+    /// Returns `None` if there is no such entry, or its value is not text
+    /// holding a number. See [`Metadata::parse_f64`](crate::reader::Metadata::parse_f64).
+    pub fn meta_f64(&self, key: &str) -> Option<f64> {
+        self.metadata_by_name(key)?.parse_f64()
+    }
 }
 
 impl<'a> HasMetadataSealed for WireValue<'a> {
@@ -66,3 +108,26 @@ impl<'a> HasMetadataSealed for WireValue<'a> {
         self.metadata
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::test::{definition, entangled_calls};
+    use crate::Jeff;
+    use rstest::rstest;
+
+    #[rstest]
+    fn region_boundary(entangled_calls: Jeff<'static>) {
+        let def = definition(&entangled_calls, 3);
+        let source = def.values().get(0).unwrap();
+        assert!(source.is_source_of(&def.body()));
+        assert!(!source.is_target_of(&def.body()));
+
+        let main = definition(&entangled_calls, 0);
+        let body = main.body();
+        let internal = main.values().get(0).unwrap();
+        assert!(!internal.is_source_of(&body));
+        assert!(!internal.is_target_of(&body));
+        let target = body.targets().next().unwrap().unwrap();
+        assert!(target.is_target_of(&body));
+    }
+}
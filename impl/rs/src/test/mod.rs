@@ -3,7 +3,8 @@
 use core::panic;
 use std::path::PathBuf;
 
-use crate::Jeff;
+use crate::reader::{Function, FunctionDefinition, FunctionId, ReadJeff};
+use crate::{jeff_capnp, Jeff, JeffError};
 
 const TEST_PROGRAMS_DIR: &str = "../../examples/";
 
@@ -29,12 +30,149 @@ pub fn entangled_calls() -> Jeff<'static> {
     load_example_program("entangled_calls")
 }
 
+/// A simple program generated by Catalyst, allocating a dynamically-sized qubit register.
+#[rstest::fixture]
+pub fn catalyst_simple() -> Jeff<'static> {
+    load_example_program("catalyst_simple")
+}
+
 /// Load the example program by copying the file to an internal buffer.
 fn load_example_program(name: &str) -> Jeff<'static> {
     let filename = format!("{name}.jeff");
     let path = PathBuf::from(TEST_PROGRAMS_DIR).join(name).join(filename);
 
-    let file = std::fs::File::open(&path).unwrap();
-    let buffer = std::io::BufReader::new(file);
-    Jeff::read(buffer).unwrap_or_else(|e| panic!("Failed to read example program: {}", e))
+    let bytes = std::fs::read(&path).unwrap();
+    read_owned(&bytes).unwrap_or_else(|e| panic!("Failed to read example program: {}", e))
+}
+
+/// Read a jeff program by copying `bytes` into an owned buffer.
+///
+/// Unlike [`Jeff::read`], this is available without the `std` feature.
+pub fn read_owned(bytes: &[u8]) -> Result<Jeff<'static>, JeffError> {
+    let message = capnp::serialize::read_message(bytes, capnp::message::ReaderOptions::new())?;
+    Jeff::from_message(message)
+}
+
+/// Build a jeff program in memory, encoded with the current schema version.
+///
+/// The closure receives the root module builder to populate. Empty
+/// `functions` and `strings` lists are added if the closure leaves them unset.
+pub fn build_program(init: impl FnOnce(jeff_capnp::module::Builder<'_>)) -> Jeff<'static> {
+    let mut message = capnp::message::Builder::new_default();
+    let mut module = message.init_root::<jeff_capnp::module::Builder<'_>>();
+    module.set_version(crate::SCHEMA_VERSION.major as u32);
+    module.set_version_minor(crate::SCHEMA_VERSION.minor as u32);
+    module.set_version_patch(crate::SCHEMA_VERSION.patch as u32);
+    init(module.reborrow());
+    if !module.has_functions() {
+        module.reborrow().init_functions(0);
+    }
+    if !module.has_strings() {
+        module.reborrow().init_strings(0);
+    }
+
+    let mut buffer = Vec::new();
+    capnp::serialize::write_message(&mut buffer, &message).unwrap();
+    read_owned(&buffer).unwrap_or_else(|e| panic!("Failed to read built program: {}", e))
+}
+
+/// A function `main(start, stop, step, q) -> q` whose body is the loop
+/// `for i in start..stop by step { q = h(q) }`, with `gates` Hadamard gates of
+/// the given power in the loop body.
+///
+/// Values `0..=2` are the loop bounds, `3` is the input qubit, `4` the
+/// iteration index, and `5` the qubit entering the loop body. Gate `k`
+/// produces value `6 + k`, and the loop produces value `6 + gates`.
+pub fn nested_loop(gates: u32, power: u8) -> Jeff<'static> {
+    build_program(|mut module| {
+        module.reborrow().init_strings(1).set(0, "main");
+        let function = module.init_functions(1).get(0);
+        let mut def = function.init_definition();
+        let mut values = def.reborrow().init_values(7 + gates);
+        for i in 0..7 + gates {
+            let mut ty = values.reborrow().get(i).init_type();
+            match i {
+                0..=2 | 4 => ty.set_int(32),
+                _ => ty.set_qubit(()),
+            }
+        }
+
+        let mut body = def.init_body();
+        let mut sources = body.reborrow().init_sources(4);
+        for i in 0..4 {
+            sources.set(i, i);
+        }
+        body.reborrow().init_targets(1).set(0, 6 + gates);
+        let mut for_op = body.init_operations(1).get(0);
+        let mut inputs = for_op.reborrow().init_inputs(4);
+        for i in 0..4 {
+            inputs.set(i, i);
+        }
+        for_op.reborrow().init_outputs(1).set(0, 6 + gates);
+
+        let mut loop_body = for_op.init_instruction().init_scf().init_for();
+        let mut sources = loop_body.reborrow().init_sources(2);
+        sources.set(0, 4);
+        sources.set(1, 5);
+        loop_body.reborrow().init_targets(1).set(0, 5 + gates);
+        let mut ops = loop_body.init_operations(gates);
+        for k in 0..gates {
+            let mut h = ops.reborrow().get(k);
+            h.reborrow().init_inputs(1).set(0, 5 + k);
+            h.reborrow().init_outputs(1).set(0, 6 + k);
+            let mut gate = h.init_instruction().init_qubit().init_gate();
+            gate.set_power(power);
+            gate.set_well_known(jeff_capnp::WellKnownGate::H);
+        }
+    })
+}
+
+/// Returns the definition of the `idx`-th function in `jeff`.
+///
+/// # Panics
+///
+/// Panics if the function is a declaration.
+pub fn definition<'j>(jeff: &'j Jeff<'_>, idx: FunctionId) -> FunctionDefinition<'j> {
+    match jeff.module().function(idx) {
+        Function::Definition(def) => def,
+        Function::Declaration(_) => panic!("Expected function {idx} to be a definition"),
+    }
+}
+
+/// Copies a jeff program, applying `edit` to the copied module.
+pub fn edited(
+    jeff: &Jeff<'_>,
+    edit: impl FnOnce(jeff_capnp::module::Builder<'_>),
+) -> Jeff<'static> {
+    let bytes = jeff.as_bytes();
+    let reader =
+        capnp::serialize::read_message(bytes.as_ref(), capnp::message::ReaderOptions::new())
+            .unwrap();
+    let mut message = capnp::message::Builder::new_default();
+    message
+        .set_root(reader.get_root::<jeff_capnp::module::Reader<'_>>().unwrap())
+        .unwrap();
+    edit(message.get_root().unwrap());
+
+    let mut buffer = Vec::new();
+    capnp::serialize::write_message(&mut buffer, &message).unwrap();
+    read_owned(&buffer).unwrap()
+}
+
+/// Sets the power of the `n`-th operation of the first function, which must
+/// be a gate.
+pub fn set_gate_power(module: jeff_capnp::module::Builder<'_>, n: u32, power: u8) {
+    let function = module.get_functions().unwrap().get(0);
+    let jeff_capnp::function::Which::Definition(def) = function.which().unwrap() else {
+        panic!("Expected a function definition");
+    };
+    let op = def.get_body().unwrap().get_operations().unwrap().get(n);
+    let jeff_capnp::op::instruction::Which::Qubit(qubit) = op.get_instruction().which().unwrap()
+    else {
+        panic!("Expected a qubit operation");
+    };
+    let jeff_capnp::qubit_op::Which::Gate(gate) = qubit.unwrap().which().unwrap() else {
+        panic!("Expected a gate");
+    };
+    gate.unwrap().set_power(power);
 }
@@ -3,11 +3,18 @@
 //! Internally, these are coalesced into a single array at the function
 //! definition and each port contains an index into this array.
 
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
 use crate::capnp::jeff_capnp;
 use derive_more::Display;
+use itertools::Itertools;
 
 /// Value type.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+///
+/// Types are ordered by variant, in declaration order, and then by their
+/// fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
 pub enum Type {
     /// Quantum bit.
     ///
@@ -103,6 +110,30 @@ impl Type {
         Self::FloatArray { precision, length }
     }
 
+    /// Create a new dynamic-length array type holding values of the given
+    /// scalar type.
+    ///
+    /// Returns `None` for qubits, qubit registers, and arrays, as there are no
+    /// nested arrays. See [`Type::element_type`] for the inverse.
+    pub fn array_of(scalar: Type) -> Option<Self> {
+        match scalar {
+            Self::Int { bits } => Some(Self::int_array(bits, None)),
+            Self::Float { precision } => Some(Self::float_array(precision, None)),
+            _ => None,
+        }
+    }
+
+    /// Returns the type of the elements of an array type.
+    ///
+    /// Returns `None` if the type is not an integer or float array.
+    pub fn element_type(&self) -> Option<Self> {
+        match *self {
+            Self::IntArray { bits, .. } => Some(Self::int(bits)),
+            Self::FloatArray { precision, .. } => Some(Self::float(precision)),
+            _ => None,
+        }
+    }
+
     /// Parse a type from a capnp reader.
     pub(crate) fn read_capnp(reader: jeff_capnp::type_::Reader<'_>) -> Self {
         use jeff_capnp::type_::Which;
@@ -184,8 +215,50 @@ impl Type {
     }
 }
 
+/// Errors produced when computing the value types of an operation.
+///
+/// See [`OpType::output_types`](crate::reader::optype::OpType::output_types).
+#[derive(Clone, Debug, PartialEq, Eq, Display, derive_more::Error)]
+#[non_exhaustive]
+pub enum TypeError {
+    /// The operation received the wrong number of inputs.
+    #[display("Expected {expected} inputs, but found {found}")]
+    WrongArity {
+        /// The number of inputs required by the operation.
+        expected: usize,
+        /// The number of inputs provided.
+        found: usize,
+    },
+    /// An input has a type that is not valid for the operation.
+    #[display("Input {idx} has unexpected type {ty}")]
+    InvalidInput {
+        /// The position of the offending input.
+        idx: usize,
+        /// The type of the offending input.
+        ty: Type,
+    },
+    /// The output types cannot be inferred from the input types alone.
+    ///
+    /// This is the case for function calls, whose outputs depend on the
+    /// callee signature, and for empty array constructors.
+    #[display("Output types cannot be inferred from the input types")]
+    Underdetermined,
+}
+
+/// Owned input and output types of a function.
+///
+/// See [`Function::signature`](crate::reader::Function::signature).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Display)]
+#[display("({}) -> ({})", inputs.iter().join(", "), outputs.iter().join(", "))]
+pub struct FunctionType {
+    /// Input types of the function.
+    pub inputs: Vec<Type>,
+    /// Output types of the function.
+    pub outputs: Vec<Type>,
+}
+
 /// Precision of floating point number.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
 pub enum FloatPrecision {
     /// 32-bit floating point number.
     Float32,
@@ -218,3 +291,27 @@ impl FloatPrecision {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn array_of() {
+        let f64 = FloatPrecision::Float64;
+        assert_eq!(Type::array_of(Type::int(8)), Some(Type::int_array(8, None)));
+        assert_eq!(
+            Type::array_of(Type::float(f64)),
+            Some(Type::float_array(f64, None))
+        );
+        assert_eq!(Type::array_of(Type::Qubit), None);
+        assert_eq!(Type::array_of(Type::QubitRegister { length: None }), None);
+        assert_eq!(Type::array_of(Type::int_array(8, Some(2))), None);
+
+        for scalar in [Type::bool(), Type::float(f64)] {
+            let array = Type::array_of(scalar).unwrap();
+            assert_eq!(array.element_type(), Some(scalar));
+        }
+        assert_eq!(Type::int(32).element_type(), None);
+    }
+}
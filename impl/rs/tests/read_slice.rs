@@ -0,0 +1,32 @@
+//! Smoke test for zero-copy loading from byte slices.
+//!
+//! This is the only loading method available without the `std` feature, so CI
+//! also runs this test against a `no_std` build of the library.
+
+use jeff::reader::{Function, ReadJeff};
+use jeff::Jeff;
+
+#[test]
+fn read_slice() {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../examples/entangled_qs/entangled_qs.jeff"
+    );
+    let bytes = std::fs::read(path).unwrap();
+
+    // Cap'n Proto requires the buffer to be 8-byte aligned.
+    let mut words = capnp::Word::allocate_zeroed_vec(bytes.len().div_ceil(8));
+    capnp::Word::words_to_bytes_mut(&mut words)[..bytes.len()].copy_from_slice(&bytes);
+    let mut slice = capnp::Word::words_to_bytes(&words);
+
+    let jeff = Jeff::read_slice(&mut slice).unwrap();
+    assert!(slice.iter().all(|&b| b == 0));
+
+    let module = jeff.module();
+    assert_eq!(module.function_count(), 1);
+    let Function::Definition(main) = module.entrypoint() else {
+        panic!("Expected a function definition");
+    };
+    assert_eq!(main.name(), "qarray");
+    assert_eq!(main.body().operation_count(), 26);
+}
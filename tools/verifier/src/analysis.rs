@@ -3,8 +3,12 @@
 //! This module traverses a function's dataflow graph to gather statistics
 //! used by the verification passes.
 
-use jeff::reader::optype::{ControlFlowOp, OpType};
-use jeff::reader::{ReadError, Region};
+use std::collections::HashMap;
+use std::fmt;
+
+use jeff::reader::optype::{ControlFlowOp, OpType, QubitRegisterOp};
+use jeff::reader::{FunctionDefinition, Operation, ReadError, Region, ValueId, WireValue};
+use jeff::types::Type;
 
 /// Producer and consumer counts for a single value.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -77,3 +81,110 @@ pub fn build_value_stats(
     collect_value_stats(region, &mut stats)?;
     Ok(stats)
 }
+
+/// A qubit register index that is statically known to be out of range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundsWarning {
+    /// The name of the register operation using the index.
+    pub operation: &'static str,
+    /// The register accessed by the operation.
+    pub register: ValueId,
+    /// The constant index.
+    pub index: u64,
+    /// The constant size of the register.
+    pub size: u64,
+}
+
+impl fmt::Display for BoundsWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} index {} is out of range for qubit register {} of size {}",
+            self.operation, self.index, self.register, self.size
+        )
+    }
+}
+
+/// Flag qubit register extractions and insertions whose index is statically out of range.
+///
+/// Register sizes are known when the register has a static length, or when it
+/// was allocated from an integer constant and only passed through index
+/// operations since. Registers or indices that cannot be traced back to
+/// constants are skipped.
+pub fn analyze_qureg_bounds(function: &FunctionDefinition<'_>) -> Vec<BoundsWarning> {
+    let mut bounds = QuregBounds::default();
+    bounds.walk(function.body());
+    bounds.warnings
+}
+
+/// State of the [`analyze_qureg_bounds`] pass.
+#[derive(Default)]
+struct QuregBounds {
+    /// Values defined by integer constants.
+    consts: HashMap<ValueId, u64>,
+    /// Registers with a known size.
+    sizes: HashMap<ValueId, u64>,
+    /// Detected out-of-range accesses.
+    warnings: Vec<BoundsWarning>,
+}
+
+impl QuregBounds {
+    fn walk(&mut self, region: Region<'_>) {
+        region.fold_operations((), |(), op| match op.op_type() {
+            OpType::IntOp(int_op) => {
+                if let (Some(value), Some(Ok(out))) = (int_op.const_value_u64(), op.output(0)) {
+                    self.consts.insert(out.id(), value);
+                }
+            }
+            OpType::QubitRegisterOp(qureg_op) => self.qureg_op(qureg_op, op),
+            _ => {}
+        });
+    }
+
+    fn qureg_op(&mut self, qureg_op: QubitRegisterOp, op: &Operation<'_>) {
+        let value = |idx| op.input(idx).and_then(Result::ok);
+        match qureg_op {
+            QubitRegisterOp::Alloc => {
+                let size = value(0).and_then(|v| self.consts.get(&v.id()).copied());
+                if let (Some(size), Some(Ok(out))) = (size, op.output(0)) {
+                    self.sizes.insert(out.id(), size);
+                }
+            }
+            QubitRegisterOp::ExtractIndex | QubitRegisterOp::InsertIndex => {
+                let (Some(register), Some(index)) = (value(0), value(1)) else {
+                    return;
+                };
+                let Some(size) = self.size(register) else {
+                    return;
+                };
+                if let Some(&index) = self.consts.get(&index.id()) {
+                    if index >= size {
+                        self.warnings.push(BoundsWarning {
+                            operation: match qureg_op {
+                                QubitRegisterOp::ExtractIndex => "ExtractIndex",
+                                _ => "InsertIndex",
+                            },
+                            register: register.id(),
+                            index,
+                            size,
+                        });
+                    }
+                }
+                if let Some(Ok(out)) = op.output(0) {
+                    self.sizes.insert(out.id(), size);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the size of a register, if it is statically known.
+    fn size(&self, register: WireValue<'_>) -> Option<u64> {
+        match register.ty() {
+            Type::QubitRegister {
+                length: Some(length),
+            } => Some(length as u64),
+            _ => self.sizes.get(&register.id()).copied(),
+        }
+    }
+}
@@ -1,6 +1,62 @@
 use std::fmt;
 
+use jeff::reader::{FunctionId, NestedOpPath};
+
+/// Location of an error in a function definition.
+///
+/// Locations are displayed as ``function `name` (#index)``, followed by the
+/// path of the operation if present. Operation paths list the operation
+/// indices from the function body inwards, with the index of the nested region
+/// in brackets. For example, `1[0].3` is the fourth operation in the first
+/// region of the second operation in the body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// The index of the function in the module.
+    pub function: FunctionId,
+    /// The name of the function.
+    pub name: String,
+    /// The path of the operation in the function body, or `None` if the error
+    /// concerns the boundary of the function body.
+    pub op: Option<NestedOpPath>,
+}
+
+impl Location {
+    /// Returns the location of the body of a function.
+    pub fn new(function: FunctionId, name: impl Into<String>) -> Self {
+        Self {
+            function,
+            name: name.into(),
+            op: None,
+        }
+    }
+
+    /// Returns the location of the operation at `op` in the same function.
+    pub fn at(&self, op: NestedOpPath) -> Self {
+        Self {
+            op: Some(op),
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "function `{}` (#{})", self.name, self.function)?;
+        if let Some(path) = &self.op {
+            write!(f, ", operation ")?;
+            for (op, region) in &path.regions {
+                write!(f, "{op}[{region}].")?;
+            }
+            write!(f, "{}", path.op)?;
+        }
+        Ok(())
+    }
+}
+
 /// An error detected during verification of a jeff module.
+///
+/// Errors found in a function definition carry their [`Location`], which is
+/// displayed before the error message.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum VerificationError {
     /// The module version is set but does not match the version supported by this verifier.
@@ -15,12 +71,16 @@ pub enum VerificationError {
         value_id: u32,
         /// The number of values in the table.
         value_count: usize,
+        /// Where the error was found.
+        location: Location,
     },
 
     /// An operation consumes a value before any operation that produces it.
     UsedBeforeDefined {
         /// The value used out of order.
         value_id: u32,
+        /// Where the error was found.
+        location: Location,
     },
 
     /// A value is produced by more than one operation.
@@ -30,6 +90,8 @@ pub enum VerificationError {
         value_id: u32,
         /// The number of producing operations.
         producers: u32,
+        /// Where the error was found.
+        location: Location,
     },
 
     /// A linear value (qubit or qureg) is consumed by more than one operation.
@@ -38,42 +100,81 @@ pub enum VerificationError {
         value_id: u32,
         /// The number of consuming operations.
         consumers: u32,
+        /// Where the error was found.
+        location: Location,
     },
 
     /// A linear value (qubit or qureg) is produced but never consumed.
     LinearValueNeverConsumed {
         /// The value that is never consumed.
         value_id: u32,
+        /// Where the error was found.
+        location: Location,
     },
 
     /// The input and output types of an int or float operation are not all the same bitwidth or precision.
     TypeMismatch {
         /// The name of the operation with mismatched types.
         operation: &'static str,
+        /// Where the error was found.
+        location: Location,
     },
 
     /// An input value has a type that is not valid for the operation.
     InvalidInputType {
         /// The name of the operation with the invalid input.
         operation: &'static str,
+        /// Where the error was found.
+        location: Location,
     },
 
     /// An output value has a type that is not valid for the operation.
     InvalidOutputType {
         /// The name of the operation with the invalid output.
         operation: &'static str,
+        /// Where the error was found.
+        location: Location,
     },
 
     /// An operation has the wrong number of inputs or outputs for its declared arity.
     WrongArity {
         /// The name of the operation with the wrong arity.
         operation: &'static str,
+        /// Where the error was found.
+        location: Location,
     },
 
     /// The source and target types of a region are not consistent.
     RegionTypeMismatch {
         /// The name of the operation whose region has inconsistent types.
         operation: &'static str,
+        /// Where the error was found.
+        location: Location,
+    },
+
+    /// A loop body produces a state whose types differ from the state it receives.
+    LoopStateTypeMismatch {
+        /// The name of the loop operation.
+        operation: &'static str,
+        /// Where the error was found.
+        location: Location,
+    },
+
+    /// A switch branch has a different signature from the first branch.
+    SwitchBranchMismatch {
+        /// The index of the mismatched branch, where the default branch comes
+        /// after all other branches.
+        branch: usize,
+        /// Where the error was found.
+        location: Location,
+    },
+
+    /// A function call passes or returns values whose types differ from the callee's signature.
+    CallTypeMismatch {
+        /// The index of the called function.
+        callee: u32,
+        /// Where the error was found.
+        location: Location,
     },
 
     /// An operation inside a nested region directly references a value from an outer scope
@@ -81,11 +182,39 @@ pub enum VerificationError {
     IsolationViolation {
         /// The outer-scope value referenced directly.
         value_id: u32,
+        /// Where the error was found.
+        location: Location,
     },
 }
 
+impl VerificationError {
+    /// Returns where the error was found, or `None` for module-level errors.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Self::IncompatibleVersion | Self::InvalidEntrypoint => None,
+            Self::ValueOutOfBounds { location, .. }
+            | Self::UsedBeforeDefined { location, .. }
+            | Self::ValueProducedMultipleTimes { location, .. }
+            | Self::LinearValueConsumedMultipleTimes { location, .. }
+            | Self::LinearValueNeverConsumed { location, .. }
+            | Self::TypeMismatch { location, .. }
+            | Self::InvalidInputType { location, .. }
+            | Self::InvalidOutputType { location, .. }
+            | Self::WrongArity { location, .. }
+            | Self::RegionTypeMismatch { location, .. }
+            | Self::LoopStateTypeMismatch { location, .. }
+            | Self::SwitchBranchMismatch { location, .. }
+            | Self::CallTypeMismatch { location, .. }
+            | Self::IsolationViolation { location, .. } => Some(location),
+        }
+    }
+}
+
 impl fmt::Display for VerificationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = self.location() {
+            write!(f, "{location}: ")?;
+        }
         match self {
             Self::IncompatibleVersion => {
                 write!(f, "module version is incompatible with the jeff program")
@@ -99,18 +228,20 @@ impl fmt::Display for VerificationError {
             Self::ValueOutOfBounds {
                 value_id,
                 value_count,
+                ..
             } => {
                 write!(
                     f,
                     "value {value_id} is out of bounds (function has {value_count} values)"
                 )
             }
-            Self::UsedBeforeDefined { value_id } => {
+            Self::UsedBeforeDefined { value_id, .. } => {
                 write!(f, "value {value_id} is used before it is defined")
             }
             Self::ValueProducedMultipleTimes {
                 value_id,
                 producers,
+                ..
             } => {
                 write!(
                     f,
@@ -120,34 +251,53 @@ impl fmt::Display for VerificationError {
             Self::LinearValueConsumedMultipleTimes {
                 value_id,
                 consumers,
+                ..
             } => {
                 write!(
                     f,
                     "linear value {value_id} is consumed {consumers} times (must be exactly once)"
                 )
             }
-            Self::LinearValueNeverConsumed { value_id } => {
+            Self::LinearValueNeverConsumed { value_id, .. } => {
                 write!(f, "linear value {value_id} is produced but never consumed")
             }
-            Self::TypeMismatch { operation } => {
+            Self::TypeMismatch { operation, .. } => {
                 write!(
                     f,
                     "'{operation}' has inputs and outputs with mismatched types"
                 )
             }
-            Self::InvalidInputType { operation } => {
+            Self::InvalidInputType { operation, .. } => {
                 write!(f, "'{operation}' has an input of an unexpected type")
             }
-            Self::InvalidOutputType { operation } => {
+            Self::InvalidOutputType { operation, .. } => {
                 write!(f, "'{operation}' has an output of an unexpected type")
             }
-            Self::WrongArity { operation } => {
+            Self::WrongArity { operation, .. } => {
                 write!(f, "'{operation}' has the wrong number of inputs or outputs for its declared arity")
             }
-            Self::RegionTypeMismatch { operation } => {
+            Self::RegionTypeMismatch { operation, .. } => {
                 write!(f, "'{operation}' has a region with inconsistent types")
             }
-            Self::IsolationViolation { value_id } => {
+            Self::LoopStateTypeMismatch { operation, .. } => {
+                write!(
+                    f,
+                    "'{operation}' has a body whose output state types differ from its input state types"
+                )
+            }
+            Self::SwitchBranchMismatch { branch, .. } => {
+                write!(
+                    f,
+                    "switch branch {branch} has a different signature from the first branch"
+                )
+            }
+            Self::CallTypeMismatch { callee, .. } => {
+                write!(
+                    f,
+                    "call to function {callee} does not match the callee signature"
+                )
+            }
+            Self::IsolationViolation { value_id, .. } => {
                 write!(
                     f,
                     "value {value_id} from an outer scope is used inside a nested region without being passed in via sources"
@@ -156,3 +306,21 @@ impl fmt::Display for VerificationError {
         }
     }
 }
+
+/// Renders a list of verification errors as a multi-line report.
+///
+/// Each error is written on its own line, prefixed with `error: `, followed
+/// by a summary line with the number of errors. Returns an empty string if
+/// there are no errors.
+pub fn report(errors: &[VerificationError]) -> String {
+    if errors.is_empty() {
+        return String::new();
+    }
+    let mut report = String::new();
+    for error in errors {
+        report.push_str(&format!("error: {error}\n"));
+    }
+    let plural = if errors.len() == 1 { "" } else { "s" };
+    report.push_str(&format!("{} verification error{plural}", errors.len()));
+    report
+}
@@ -13,11 +13,11 @@ use jeff::reader::{Function, FunctionDefinition, Module, ReadJeff};
 use jeff::Jeff;
 use jeff::JeffError;
 
-pub use errors::VerificationError;
+pub use errors::{report, Location, VerificationError};
 
 use passes::isolation::verify_isolation;
 use passes::module_attributes::verify_module_attributes;
-use passes::type_checks::verify_operation_types;
+use passes::type_checks::verify_definition_types;
 use passes::value_checks::verify_value_checks;
 
 /// Verify a jeff file and return all detected errors.
@@ -56,17 +56,23 @@ pub fn verify_module(module: Module<'_>) -> Vec<VerificationError> {
 
     verify_module_attributes(module, &mut errors);
 
-    for function in module.functions() {
+    for (id, function) in module.functions_indexed() {
         if let Function::Definition(def) = function {
-            verify_definition(def, &mut errors);
+            let location = Location::new(id, def.name());
+            verify_definition(&module, def, &location, &mut errors);
         }
     }
 
     errors
 }
 
-fn verify_definition(def: FunctionDefinition<'_>, errors: &mut Vec<VerificationError>) {
-    verify_value_checks(def, errors);
-    verify_operation_types(def.body(), errors);
-    verify_isolation(def, errors);
+fn verify_definition(
+    module: &Module<'_>,
+    def: FunctionDefinition<'_>,
+    location: &Location,
+    errors: &mut Vec<VerificationError>,
+) {
+    verify_value_checks(def, location, errors);
+    verify_definition_types(module, &def, location, errors);
+    verify_isolation(def, location, errors);
 }
@@ -2,18 +2,31 @@
 
 use std::collections::HashSet;
 
-use jeff::reader::optype::{ControlFlowOp, OpType};
+use jeff::reader::optype::OpType;
 use jeff::reader::{FunctionDefinition, Region, ValueId};
 
-use crate::VerificationError;
+use super::RegionScope;
+use crate::{Location, VerificationError};
 
 /// Check that no operation inside a nested region directly references a value from an outer scope.
-pub fn verify_isolation(def: FunctionDefinition<'_>, errors: &mut Vec<VerificationError>) {
-    check_region_isolation(def.body(), &HashSet::new(), errors);
+///
+/// `location` is the location of the function definition.
+pub fn verify_isolation(
+    def: FunctionDefinition<'_>,
+    location: &Location,
+    errors: &mut Vec<VerificationError>,
+) {
+    check_region_isolation(
+        def.body(),
+        &RegionScope::body(location),
+        &HashSet::new(),
+        errors,
+    );
 }
 
 fn check_region_isolation(
     region: Region<'_>,
+    scope: &RegionScope<'_>,
     outer_values: &HashSet<ValueId>,
     errors: &mut Vec<VerificationError>,
 ) {
@@ -23,11 +36,12 @@ fn check_region_isolation(
         .map(|v| v.id())
         .collect();
 
-    for op in region.operations() {
+    for (idx, op) in region.operations().enumerate() {
         for input in op.inputs().filter_map(|r| r.ok()) {
             if outer_values.contains(&input.id()) {
                 errors.push(VerificationError::IsolationViolation {
                     value_id: input.id(),
+                    location: scope.op(idx),
                 });
             }
         }
@@ -42,7 +56,10 @@ fn check_region_isolation(
                 .chain(locally_defined.iter())
                 .cloned()
                 .collect();
-            check_cf_isolation(cf_op.as_ref(), &new_outer, errors);
+            for (region_idx, nested) in cf_op.regions().into_iter().enumerate() {
+                let nested_scope = scope.nested(idx, region_idx);
+                check_region_isolation(nested, &nested_scope, &new_outer, errors);
+            }
         }
     }
 
@@ -50,31 +67,8 @@ fn check_region_isolation(
         if outer_values.contains(&target.id()) {
             errors.push(VerificationError::IsolationViolation {
                 value_id: target.id(),
+                location: scope.location(),
             });
         }
     }
 }
-
-fn check_cf_isolation(
-    cf_op: &ControlFlowOp<'_>,
-    outer_values: &HashSet<ValueId>,
-    errors: &mut Vec<VerificationError>,
-) {
-    match cf_op {
-        ControlFlowOp::For { region } => {
-            check_region_isolation(*region, outer_values, errors);
-        }
-        ControlFlowOp::While { before, after } => {
-            check_region_isolation(*before, outer_values, errors);
-            check_region_isolation(*after, outer_values, errors);
-        }
-        ControlFlowOp::Switch(switch_op) => {
-            for branch in switch_op.branches() {
-                check_region_isolation(branch, outer_values, errors);
-            }
-            if let Some(default) = switch_op.default_branch() {
-                check_region_isolation(default, outer_values, errors);
-            }
-        }
-    }
-}
@@ -4,3 +4,57 @@ pub mod isolation;
 pub mod module_attributes;
 pub mod type_checks;
 pub mod value_checks;
+
+use jeff::reader::NestedOpPath;
+
+use crate::Location;
+
+/// Position of a region in a function body, used to locate errors found while
+/// walking it.
+#[derive(Debug, Clone)]
+pub(crate) struct RegionScope<'l> {
+    /// Location of the function containing the region.
+    function: &'l Location,
+    /// Enclosing control flow operations, as in [`NestedOpPath::regions`].
+    regions: Vec<(usize, usize)>,
+}
+
+impl<'l> RegionScope<'l> {
+    /// Returns the scope of the body of the function at `function`.
+    pub(crate) fn body(function: &'l Location) -> Self {
+        Self {
+            function,
+            regions: Vec::new(),
+        }
+    }
+
+    /// Returns the location of the `op`-th operation in the region.
+    pub(crate) fn op(&self, op: usize) -> Location {
+        self.function.at(NestedOpPath {
+            regions: self.regions.clone(),
+            op,
+        })
+    }
+
+    /// Returns the scope of the `region`-th region of the `op`-th operation.
+    pub(crate) fn nested(&self, op: usize, region: usize) -> Self {
+        let mut regions = self.regions.clone();
+        regions.push((op, region));
+        Self {
+            function: self.function,
+            regions,
+        }
+    }
+
+    /// Returns the location of the region itself, i.e. the operation it is
+    /// nested in, or the function for the function body.
+    pub(crate) fn location(&self) -> Location {
+        match self.regions.split_last() {
+            Some((&(op, _), outer)) => self.function.at(NestedOpPath {
+                regions: outer.to_vec(),
+                op,
+            }),
+            None => self.function.clone(),
+        }
+    }
+}
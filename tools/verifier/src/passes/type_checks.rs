@@ -2,25 +2,88 @@
 
 use jeff::reader::optype::{
     ControlFlowOp, FloatArrayOp, FloatOp, IntArrayOp, IntOp, OpType, QubitOp, QubitRegisterOp,
+    SwitchOp,
 };
-use jeff::reader::{Operation, Region};
+use jeff::reader::{Function, FunctionDefinition, Module, Operation, Region};
 use jeff::types::{FloatPrecision, Type};
 
-use crate::VerificationError;
+use super::RegionScope;
+use crate::{Location, VerificationError};
+
+/// Errors found while checking a single operation.
+struct OpErrors<'a> {
+    /// Location of the operation.
+    location: Location,
+    errors: &'a mut Vec<VerificationError>,
+}
+
+impl OpErrors<'_> {
+    /// Reports an error at the operation.
+    fn push(&mut self, error: impl FnOnce(Location) -> VerificationError) {
+        self.errors.push(error(self.location.clone()));
+    }
+}
+
+/// Run all type checks over the function definitions in `module`.
+///
+/// This covers the scalar and array operations, gate arities, loop states,
+/// switch signatures, and function calls. All errors are accumulated.
+pub fn verify_types(module: &Module<'_>) -> Result<(), Vec<VerificationError>> {
+    let mut errors = Vec::new();
+    for (id, function) in module.functions_indexed() {
+        if let Function::Definition(def) = function {
+            let location = Location::new(id, def.name());
+            verify_definition_types(module, &def, &location, &mut errors);
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Run all type checks over a single function definition.
+pub(crate) fn verify_definition_types(
+    module: &Module<'_>,
+    def: &FunctionDefinition<'_>,
+    location: &Location,
+    errors: &mut Vec<VerificationError>,
+) {
+    verify_operation_types(def.body(), location, errors);
+    if let Err(call_errors) = check_call_types(module, def, location) {
+        errors.extend(call_errors);
+    }
+}
 
 /// Check that all operations in `region` (and its nested regions) have correctly typed inputs and outputs.
-pub fn verify_operation_types(region: Region<'_>, errors: &mut Vec<VerificationError>) {
-    check_region_types(region, errors);
+///
+/// `location` is the location of the function containing `region`. Errors are
+/// located by their operation path relative to `region`.
+pub fn verify_operation_types(
+    region: Region<'_>,
+    location: &Location,
+    errors: &mut Vec<VerificationError>,
+) {
+    check_region_types(region, &RegionScope::body(location), errors);
 }
 
-fn check_region_types(region: Region<'_>, errors: &mut Vec<VerificationError>) {
-    for op in region.operations() {
+fn check_region_types(
+    region: Region<'_>,
+    scope: &RegionScope<'_>,
+    errors: &mut Vec<VerificationError>,
+) {
+    for (idx, op) in region.operations().enumerate() {
         let inputs: Vec<Type> = op.inputs().filter_map(|r| r.ok()).map(|v| v.ty()).collect();
         let outputs: Vec<Type> = op
             .outputs()
             .filter_map(|r| r.ok())
             .map(|v| v.ty())
             .collect();
+        let errors = &mut OpErrors {
+            location: scope.op(idx),
+            errors,
+        };
         match op.op_type() {
             OpType::IntOp(int_op) => check_int_op(int_op, &inputs, &outputs, errors),
             OpType::FloatOp(float_op) => check_float_op(float_op, &inputs, &outputs, errors),
@@ -28,37 +91,182 @@ fn check_region_types(region: Region<'_>, errors: &mut Vec<VerificationError>) {
             OpType::QubitRegisterOp(qureg_op) => {
                 check_qureg_op(qureg_op, &inputs, &outputs, errors);
             }
-            OpType::ControlFlowOp(cf_op) => check_cf_region_types(cf_op.as_ref(), op, errors),
+            OpType::ControlFlowOp(cf_op) => {
+                check_cf_region_types(cf_op.as_ref(), op, scope, idx, errors.errors);
+            }
             OpType::IntArrayOp(int_array_op) => {
                 check_int_array_op(int_array_op, &inputs, &outputs, errors);
             }
             OpType::FloatArrayOp(float_array_op) => {
                 check_float_array_op(float_array_op, &inputs, &outputs, errors);
             }
-            // FuncOp only has a function index so type checking calls require to find the
-            // callee signature at Module level, but this pass is only scoped to a Region.
-            // TODO: Scope Module into this pass / add a helper pass to build a
-            // function table for a signature lookup and check out of bounds index
+            // FuncOp only has a function index, so calls are checked against the
+            // callee signature at module level by `check_call_types`.
+            // TODO: Check for out of bounds function indices.
             OpType::FuncOp(_) => {}
             _ => panic!("Unknown optype"),
         }
     }
 }
 
+/// Check the control flow operation `op`, the `idx`-th operation in the
+/// region at `scope`, and its nested regions.
 fn check_cf_region_types(
     cf_op: &ControlFlowOp<'_>,
     op: Operation<'_>,
+    scope: &RegionScope<'_>,
+    idx: usize,
     errors: &mut Vec<VerificationError>,
 ) {
+    let location = scope.op(idx);
+    if let Err(loop_errors) = check_loop_state(cf_op, &location) {
+        errors.extend(loop_errors);
+    }
+
     match cf_op {
         ControlFlowOp::For { region } => {
-            check_for_op(*region, op, errors);
+            check_for_op(*region, op, scope, idx, errors);
         }
         ControlFlowOp::While { before, after } => {
-            check_while_op(*before, *after, op, errors);
+            check_while_op(*before, *after, op, scope, idx, errors);
         }
         ControlFlowOp::Switch(switch_op) => {
-            check_switch_op(switch_op, op, errors);
+            if let Err(switch_errors) = check_switch_signatures(switch_op, &location) {
+                errors.extend(switch_errors);
+            }
+            check_switch_op(switch_op, op, scope, idx, errors);
+        }
+    }
+}
+
+/// Check that a loop body preserves the types of the loop state.
+///
+/// For `for` loops, the first source of the body is the iteration index and is
+/// not part of the state. For `while` loops, the state received by the `before`
+/// region must match the state returned by the `after` region. Switch
+/// statements carry no loop state and are always accepted.
+///
+/// `location` is the location of the operation.
+pub fn check_loop_state(
+    op: &ControlFlowOp<'_>,
+    location: &Location,
+) -> Result<(), Vec<VerificationError>> {
+    let (operation, state_in, state_out) = match op {
+        ControlFlowOp::For { region } => {
+            let sources = get_source_types(*region);
+            let state_in = sources.get(1..).unwrap_or_default().to_vec();
+            ("for", state_in, get_target_types(*region))
+        }
+        ControlFlowOp::While { before, after } => {
+            ("while", get_source_types(*before), get_target_types(*after))
+        }
+        ControlFlowOp::Switch(_) => return Ok(()),
+    };
+
+    if state_in != state_out {
+        return Err(vec![VerificationError::LoopStateTypeMismatch {
+            operation,
+            location: location.clone(),
+        }]);
+    }
+    Ok(())
+}
+
+/// Check that all branches of a switch share the signature of the first branch.
+///
+/// The default branch, if present, is compared as well and is reported with
+/// the index following the last regular branch. `location` is the location of
+/// the switch operation.
+pub fn check_switch_signatures(
+    switch: &SwitchOp<'_>,
+    location: &Location,
+) -> Result<(), Vec<VerificationError>> {
+    let signature = |region: Region<'_>| (get_source_types(region), get_target_types(region));
+
+    let mut signatures = switch
+        .branches()
+        .chain(switch.default_branch())
+        .map(signature);
+    let Some(first) = signatures.next() else {
+        return Ok(());
+    };
+
+    let errors: Vec<_> = signatures
+        .enumerate()
+        .filter(|(_, other)| *other != first)
+        .map(|(idx, _)| VerificationError::SwitchBranchMismatch {
+            branch: idx + 1,
+            location: location.clone(),
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Check that every call in `caller` (and its nested regions) matches the
+/// signature of the called function.
+///
+/// Calls to functions that are not in `module` are ignored. `location` is the
+/// location of `caller`.
+pub fn check_call_types(
+    module: &Module<'_>,
+    caller: &FunctionDefinition<'_>,
+    location: &Location,
+) -> Result<(), Vec<VerificationError>> {
+    let mut errors = Vec::new();
+    check_region_calls(
+        module,
+        caller.body(),
+        &RegionScope::body(location),
+        &mut errors,
+    );
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn check_region_calls(
+    module: &Module<'_>,
+    region: Region<'_>,
+    scope: &RegionScope<'_>,
+    errors: &mut Vec<VerificationError>,
+) {
+    for (idx, op) in region.operations().enumerate() {
+        match op.op_type() {
+            OpType::FuncOp(func_op) => {
+                let callee_idx = u32::from(func_op.func_idx);
+                let Some(callee) = module.try_function(callee_idx) else {
+                    continue;
+                };
+                let callee_inputs: Vec<Type> = callee
+                    .input_types()
+                    .filter_map(|r| r.ok())
+                    .map(|v| v.ty())
+                    .collect();
+                let callee_outputs: Vec<Type> = callee
+                    .output_types()
+                    .filter_map(|r| r.ok())
+                    .map(|v| v.ty())
+                    .collect();
+
+                if get_input_types(op) != callee_inputs || get_output_types(op) != callee_outputs {
+                    errors.push(VerificationError::CallTypeMismatch {
+                        callee: callee_idx,
+                        location: scope.op(idx),
+                    });
+                }
+            }
+            OpType::ControlFlowOp(cf_op) => {
+                for (region_idx, nested) in cf_op.regions().into_iter().enumerate() {
+                    check_region_calls(module, nested, &scope.nested(idx, region_idx), errors);
+                }
+            }
+            _ => {}
         }
     }
 }
@@ -90,7 +298,18 @@ fn get_target_types(region: Region<'_>) -> Vec<Type> {
         .collect()
 }
 
-fn check_for_op(region: Region<'_>, op: Operation<'_>, errors: &mut Vec<VerificationError>) {
+/// Check that the boundary of a `for` loop body matches the operation's inputs
+/// and outputs.
+///
+/// Whether the body preserves the loop state is checked separately by
+/// [`check_loop_state`].
+fn check_for_op(
+    region: Region<'_>,
+    op: Operation<'_>,
+    scope: &RegionScope<'_>,
+    idx: usize,
+    errors: &mut Vec<VerificationError>,
+) {
     let input_types: Vec<Type> = get_input_types(op);
     let output_types: Vec<Type> = get_output_types(op);
 
@@ -99,19 +318,28 @@ fn check_for_op(region: Region<'_>, op: Operation<'_>, errors: &mut Vec<Verifica
 
     if !matches!(region_source_types.first(), Some(Type::Int { bits: 32 }))
         || region_source_types.get(1..) != input_types.get(3..)
-        || region_source_types.get(1..) != Some(region_target_types.as_slice())
         || region_target_types != output_types
     {
-        errors.push(VerificationError::RegionTypeMismatch { operation: "for" });
+        errors.push(VerificationError::RegionTypeMismatch {
+            operation: "for",
+            location: scope.op(idx),
+        });
     }
 
-    check_region_types(region, errors);
+    check_region_types(region, &scope.nested(idx, 0), errors);
 }
 
+/// Check that the boundaries of a `while` loop's regions match each other and
+/// the operation's inputs and outputs.
+///
+/// Whether the `after` region preserves the loop state is checked separately
+/// by [`check_loop_state`].
 fn check_while_op(
     before: Region<'_>,
     after: Region<'_>,
     op: Operation<'_>,
+    scope: &RegionScope<'_>,
+    idx: usize,
     errors: &mut Vec<VerificationError>,
 ) {
     let input_types: Vec<Type> = get_input_types(op);
@@ -120,57 +348,49 @@ fn check_while_op(
     let before_source_types: Vec<Type> = get_source_types(before);
     let before_target_types: Vec<Type> = get_target_types(before);
     let after_source_types: Vec<Type> = get_source_types(after);
-    let after_target_types: Vec<Type> = get_target_types(after);
 
     if before_source_types != input_types
-        || before_source_types != after_target_types
         || !matches!(before_target_types.first(), Some(Type::Int { bits: 1 }))
         || before_target_types.get(1..) != Some(output_types.as_slice())
         || before_target_types.get(1..) != Some(after_source_types.as_slice())
     {
-        errors.push(VerificationError::RegionTypeMismatch { operation: "while" });
+        errors.push(VerificationError::RegionTypeMismatch {
+            operation: "while",
+            location: scope.op(idx),
+        });
     }
 
-    check_region_types(before, errors);
-    check_region_types(after, errors);
+    check_region_types(before, &scope.nested(idx, 0), errors);
+    check_region_types(after, &scope.nested(idx, 1), errors);
 }
 
+/// Check that the boundary of a switch's first branch matches the operation's
+/// inputs and outputs, and check the nested regions of every branch.
+///
+/// The remaining branches are compared against the first one by
+/// [`check_switch_signatures`].
 fn check_switch_op(
-    switch_op: &jeff::reader::optype::SwitchOp<'_>,
+    switch_op: &SwitchOp<'_>,
     op: Operation<'_>,
+    scope: &RegionScope<'_>,
+    idx: usize,
     errors: &mut Vec<VerificationError>,
 ) {
     let input_types: Vec<Type> = get_input_types(op);
     let output_types: Vec<Type> = get_output_types(op);
 
-    for branch in switch_op.branches() {
-        let branch_source_types: Vec<Type> = get_source_types(branch);
-        let branch_target_types: Vec<Type> = get_target_types(branch);
-
-        if Some(branch_source_types.as_slice()) != input_types.get(1..)
-            || branch_target_types != output_types
+    let branches = switch_op.branches().chain(switch_op.default_branch());
+    for (branch_idx, branch) in branches.enumerate() {
+        if branch_idx == 0
+            && (Some(get_source_types(branch).as_slice()) != input_types.get(1..)
+                || get_target_types(branch) != output_types)
         {
             errors.push(VerificationError::RegionTypeMismatch {
                 operation: "switch",
+                location: scope.op(idx),
             });
         }
-
-        check_region_types(branch, errors);
-    }
-
-    if let Some(default) = switch_op.default_branch() {
-        let default_source_types: Vec<Type> = get_source_types(default);
-        let default_target_types: Vec<Type> = get_target_types(default);
-
-        if Some(default_source_types.as_slice()) != input_types.get(1..)
-            || default_target_types != output_types
-        {
-            errors.push(VerificationError::RegionTypeMismatch {
-                operation: "switch",
-            });
-        }
-
-        check_region_types(default, errors);
+        check_region_types(branch, &scope.nested(idx, branch_idx), errors);
     }
 }
 
@@ -209,10 +429,13 @@ fn expect_input(
     idx: usize,
     pred: impl Fn(&Type) -> bool,
     op: &'static str,
-    errors: &mut Vec<VerificationError>,
+    errors: &mut OpErrors<'_>,
 ) {
     if inputs.get(idx).is_some_and(|ty| !pred(ty)) {
-        errors.push(VerificationError::InvalidInputType { operation: op });
+        errors.push(|location| VerificationError::InvalidInputType {
+            operation: op,
+            location,
+        });
     }
 }
 
@@ -221,10 +444,13 @@ fn expect_output(
     idx: usize,
     pred: impl Fn(&Type) -> bool,
     op: &'static str,
-    errors: &mut Vec<VerificationError>,
+    errors: &mut OpErrors<'_>,
 ) {
     if outputs.get(idx).is_some_and(|ty| !pred(ty)) {
-        errors.push(VerificationError::InvalidOutputType { operation: op });
+        errors.push(|location| VerificationError::InvalidOutputType {
+            operation: op,
+            location,
+        });
     }
 }
 
@@ -234,10 +460,13 @@ fn check_arity(
     outputs: &[Type],
     expected_outputs: usize,
     op: &'static str,
-    errors: &mut Vec<VerificationError>,
+    errors: &mut OpErrors<'_>,
 ) {
     if inputs.len() != expected_inputs || outputs.len() != expected_outputs {
-        errors.push(VerificationError::WrongArity { operation: op });
+        errors.push(|location| VerificationError::WrongArity {
+            operation: op,
+            location,
+        });
     }
 }
 
@@ -245,17 +474,23 @@ fn check_uniform_int(
     inputs: &[Type],
     outputs: &[Type],
     name: &'static str,
-    errors: &mut Vec<VerificationError>,
+    errors: &mut OpErrors<'_>,
 ) {
     for ty in inputs {
         if !is_int(ty, None) {
-            errors.push(VerificationError::InvalidInputType { operation: name });
+            errors.push(|location| VerificationError::InvalidInputType {
+                operation: name,
+                location,
+            });
             return;
         }
     }
     for ty in outputs {
         if !is_int(ty, None) {
-            errors.push(VerificationError::InvalidOutputType { operation: name });
+            errors.push(|location| VerificationError::InvalidOutputType {
+                operation: name,
+                location,
+            });
             return;
         }
     }
@@ -265,7 +500,10 @@ fn check_uniform_int(
             match bits {
                 None => bits = Some(*b),
                 Some(existing) if existing != *b => {
-                    errors.push(VerificationError::TypeMismatch { operation: name });
+                    errors.push(|location| VerificationError::TypeMismatch {
+                        operation: name,
+                        location,
+                    });
                     return;
                 }
                 _ => {}
@@ -278,17 +516,23 @@ fn check_uniform_float(
     inputs: &[Type],
     outputs: &[Type],
     name: &'static str,
-    errors: &mut Vec<VerificationError>,
+    errors: &mut OpErrors<'_>,
 ) {
     for ty in inputs {
         if !is_float(ty, None) {
-            errors.push(VerificationError::InvalidInputType { operation: name });
+            errors.push(|location| VerificationError::InvalidInputType {
+                operation: name,
+                location,
+            });
             return;
         }
     }
     for ty in outputs {
         if !is_float(ty, None) {
-            errors.push(VerificationError::InvalidOutputType { operation: name });
+            errors.push(|location| VerificationError::InvalidOutputType {
+                operation: name,
+                location,
+            });
             return;
         }
     }
@@ -298,7 +542,10 @@ fn check_uniform_float(
             match precision {
                 None => precision = Some(*p),
                 Some(existing) if existing != *p => {
-                    errors.push(VerificationError::TypeMismatch { operation: name });
+                    errors.push(|location| VerificationError::TypeMismatch {
+                        operation: name,
+                        location,
+                    });
                     return;
                 }
                 _ => {}
@@ -307,32 +554,16 @@ fn check_uniform_float(
     }
 }
 
-fn check_int_op(
-    int_op: IntOp,
-    inputs: &[Type],
-    outputs: &[Type],
-    errors: &mut Vec<VerificationError>,
-) {
+fn check_int_op(int_op: IntOp, inputs: &[Type], outputs: &[Type], errors: &mut OpErrors<'_>) {
     match int_op {
-        IntOp::Const1(_) => {
+        IntOp::Const1(_)
+        | IntOp::Const8(_)
+        | IntOp::Const16(_)
+        | IntOp::Const32(_)
+        | IntOp::Const64(_) => {
+            let bits = int_op.const_width();
             check_arity(inputs, 0, outputs, 1, "int const", errors);
-            expect_output(outputs, 0, |ty| is_int(ty, 1), "int const", errors);
-        }
-        IntOp::Const8(_) => {
-            check_arity(inputs, 0, outputs, 1, "int const", errors);
-            expect_output(outputs, 0, |ty| is_int(ty, 8), "int const", errors);
-        }
-        IntOp::Const16(_) => {
-            check_arity(inputs, 0, outputs, 1, "int const", errors);
-            expect_output(outputs, 0, |ty| is_int(ty, 16), "int const", errors);
-        }
-        IntOp::Const32(_) => {
-            check_arity(inputs, 0, outputs, 1, "int const", errors);
-            expect_output(outputs, 0, |ty| is_int(ty, 32), "int const", errors);
-        }
-        IntOp::Const64(_) => {
-            check_arity(inputs, 0, outputs, 1, "int const", errors);
-            expect_output(outputs, 0, |ty| is_int(ty, 64), "int const", errors);
+            expect_output(outputs, 0, |ty| is_int(ty, bits), "int const", errors);
         }
         IntOp::Add
         | IntOp::Sub
@@ -367,12 +598,7 @@ fn check_int_op(
     }
 }
 
-fn check_float_op(
-    float_op: FloatOp,
-    inputs: &[Type],
-    outputs: &[Type],
-    errors: &mut Vec<VerificationError>,
-) {
+fn check_float_op(float_op: FloatOp, inputs: &[Type], outputs: &[Type], errors: &mut OpErrors<'_>) {
     match float_op {
         FloatOp::Const32(_) => {
             check_arity(inputs, 0, outputs, 1, "float const", errors);
@@ -443,7 +669,7 @@ fn check_qubit_op(
     qubit_op: QubitOp<'_>,
     inputs: &[Type],
     outputs: &[Type],
-    errors: &mut Vec<VerificationError>,
+    errors: &mut OpErrors<'_>,
 ) {
     match qubit_op {
         QubitOp::Alloc => {
@@ -471,26 +697,33 @@ fn check_qubit_op(
             expect_output(outputs, 1, |ty| is_int(ty, 1), "MeasureNd", errors);
         }
         QubitOp::Gate(gate) => {
-            let num_qubits = gate.num_qubits();
-            let num_params = gate.num_params();
-            if inputs.len() != num_qubits + num_params {
-                errors.push(VerificationError::WrongArity { operation: "Gate" });
+            let signature = gate.signature();
+            if inputs.len() != signature.inputs.len() {
+                errors.push(|location| VerificationError::WrongArity {
+                    operation: "Gate",
+                    location,
+                });
             }
-            if outputs.len() != num_qubits {
-                errors.push(VerificationError::WrongArity { operation: "Gate" });
+            if outputs.len() != signature.outputs.len() {
+                errors.push(|location| VerificationError::WrongArity {
+                    operation: "Gate",
+                    location,
+                });
             }
-            for (i, ty) in inputs.iter().enumerate() {
-                if i < num_qubits {
-                    if !is_qubit(ty) {
-                        errors.push(VerificationError::InvalidInputType { operation: "Gate" });
-                    }
-                } else if i < num_qubits + num_params && !is_float(ty, None) {
-                    errors.push(VerificationError::InvalidInputType { operation: "Gate" });
+            for (wire, ty) in signature.inputs.iter().zip(inputs) {
+                if !wire.accepts(ty) {
+                    errors.push(|location| VerificationError::InvalidInputType {
+                        operation: "Gate",
+                        location,
+                    });
                 }
             }
-            for ty in outputs.iter().take(num_qubits) {
-                if !is_qubit(ty) {
-                    errors.push(VerificationError::InvalidOutputType { operation: "Gate" });
+            for (wire, ty) in signature.outputs.iter().zip(outputs) {
+                if !wire.accepts(ty) {
+                    errors.push(|location| VerificationError::InvalidOutputType {
+                        operation: "Gate",
+                        location,
+                    });
                 }
             }
         }
@@ -502,7 +735,7 @@ fn check_qureg_op(
     qureg_op: QubitRegisterOp,
     inputs: &[Type],
     outputs: &[Type],
-    errors: &mut Vec<VerificationError>,
+    errors: &mut OpErrors<'_>,
 ) {
     match qureg_op {
         QubitRegisterOp::Alloc => {
@@ -564,14 +797,16 @@ fn check_qureg_op(
         }
         QubitRegisterOp::Create => {
             if outputs.len() != 1 {
-                errors.push(VerificationError::WrongArity {
+                errors.push(|location| VerificationError::WrongArity {
                     operation: "qureg create",
+                    location,
                 });
             }
             for ty in inputs.iter() {
                 if !is_qubit(ty) {
-                    errors.push(VerificationError::InvalidInputType {
+                    errors.push(|location| VerificationError::InvalidInputType {
                         operation: "qureg create",
+                        location,
                     });
                 }
             }
@@ -585,7 +820,7 @@ fn check_int_array_op(
     int_array_op: IntArrayOp<'_>,
     inputs: &[Type],
     outputs: &[Type],
-    errors: &mut Vec<VerificationError>,
+    errors: &mut OpErrors<'_>,
 ) {
     match int_array_op {
         IntArrayOp::ConstArray1(_) => {
@@ -657,8 +892,9 @@ fn check_int_array_op(
                 (inputs.first(), outputs.first())
             {
                 if !is_int(out_ty, *bits) {
-                    errors.push(VerificationError::TypeMismatch {
+                    errors.push(|location| VerificationError::TypeMismatch {
                         operation: "int array getIndex",
+                        location,
                     });
                 }
             }
@@ -670,16 +906,18 @@ fn check_int_array_op(
             if let Some(Type::IntArray { bits, .. }) = inputs.first() {
                 let bits = *bits;
                 if inputs.get(2).is_some_and(|ty| !is_int(ty, bits)) {
-                    errors.push(VerificationError::TypeMismatch {
+                    errors.push(|location| VerificationError::TypeMismatch {
                         operation: "int array setIndex",
+                        location,
                     });
                 }
                 if outputs
                     .first()
                     .is_some_and(|ty| !matches!(ty, Type::IntArray { bits: b, .. } if *b == bits))
                 {
-                    errors.push(VerificationError::TypeMismatch {
+                    errors.push(|location| VerificationError::TypeMismatch {
                         operation: "int array setIndex",
+                        location,
                     });
                 }
             }
@@ -691,8 +929,9 @@ fn check_int_array_op(
         }
         IntArrayOp::Create => {
             if outputs.len() != 1 {
-                errors.push(VerificationError::WrongArity {
+                errors.push(|location| VerificationError::WrongArity {
                     operation: "int array create",
+                    location,
                 });
             }
             expect_output(outputs, 0, is_int_array, "int array create", errors);
@@ -700,8 +939,9 @@ fn check_int_array_op(
                 let bits = *bits;
                 for ty in inputs.iter() {
                     if !is_int(ty, bits) {
-                        errors.push(VerificationError::TypeMismatch {
+                        errors.push(|location| VerificationError::TypeMismatch {
                             operation: "int array create",
+                            location,
                         });
                         break;
                     }
@@ -716,7 +956,7 @@ fn check_float_array_op(
     float_array_op: FloatArrayOp<'_>,
     inputs: &[Type],
     outputs: &[Type],
-    errors: &mut Vec<VerificationError>,
+    errors: &mut OpErrors<'_>,
 ) {
     match float_array_op {
         FloatArrayOp::Const32(_) => {
@@ -780,8 +1020,9 @@ fn check_float_array_op(
                 (inputs.first(), outputs.first())
             {
                 if !is_float(out_ty, *precision) {
-                    errors.push(VerificationError::TypeMismatch {
+                    errors.push(|location| VerificationError::TypeMismatch {
                         operation: "float array getIndex",
+                        location,
                     });
                 }
             }
@@ -799,15 +1040,17 @@ fn check_float_array_op(
             if let Some(Type::FloatArray { precision, .. }) = inputs.first() {
                 let precision = *precision;
                 if inputs.get(2).is_some_and(|ty| !is_float(ty, precision)) {
-                    errors.push(VerificationError::TypeMismatch {
+                    errors.push(|location| VerificationError::TypeMismatch {
                         operation: "float array setIndex",
+                        location,
                     });
                 }
                 if outputs.first().is_some_and(
                     |ty| !matches!(ty, Type::FloatArray { precision: p, .. } if *p == precision),
                 ) {
-                    errors.push(VerificationError::TypeMismatch {
+                    errors.push(|location| VerificationError::TypeMismatch {
                         operation: "float array setIndex",
+                        location,
                     });
                 }
             }
@@ -825,8 +1068,9 @@ fn check_float_array_op(
         }
         FloatArrayOp::Create => {
             if outputs.len() != 1 {
-                errors.push(VerificationError::WrongArity {
+                errors.push(|location| VerificationError::WrongArity {
                     operation: "float array create",
+                    location,
                 });
             }
             expect_output(outputs, 0, is_float_array, "float array create", errors);
@@ -834,8 +1078,9 @@ fn check_float_array_op(
                 let precision = *precision;
                 for ty in inputs.iter() {
                     if !is_float(ty, precision) {
-                        errors.push(VerificationError::TypeMismatch {
+                        errors.push(|location| VerificationError::TypeMismatch {
                             operation: "float array create",
+                            location,
                         });
                         break;
                     }
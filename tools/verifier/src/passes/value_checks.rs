@@ -2,19 +2,31 @@
 
 use std::collections::HashSet;
 
-use jeff::reader::optype::{ControlFlowOp, OpType};
-use jeff::reader::{FunctionDefinition, ReadError, Region, ValueId};
+use jeff::reader::optype::OpType;
+use jeff::reader::{FunctionDefinition, Operation, ReadError, Region, ValueId};
 use jeff::types::Type;
 
+use super::RegionScope;
 use crate::analysis::build_value_stats;
-use crate::VerificationError;
+use crate::{Location, VerificationError};
 
 /// Check value ordering, out-of-bounds references, and linearity for a function definition.
-pub fn verify_value_checks(def: FunctionDefinition<'_>, errors: &mut Vec<VerificationError>) {
+///
+/// `location` is the location of the function definition.
+pub fn verify_value_checks(
+    def: FunctionDefinition<'_>,
+    location: &Location,
+    errors: &mut Vec<VerificationError>,
+) {
     let values = def.values();
     let num_values = values.len();
 
-    check_region_ordering(def.body(), &HashSet::new(), errors);
+    check_region_ordering(
+        def.body(),
+        &RegionScope::body(location),
+        &HashSet::new(),
+        errors,
+    );
 
     match build_value_stats(def.body(), num_values) {
         Ok(stats) => {
@@ -24,6 +36,7 @@ pub fn verify_value_checks(def: FunctionDefinition<'_>, errors: &mut Vec<Verific
                     errors.push(VerificationError::ValueProducedMultipleTimes {
                         value_id: id,
                         producers: stat.producers,
+                        location: last_op(def, location, |op| produces(op, id)),
                     });
                 }
                 if is_linear(value.ty()) {
@@ -31,15 +44,19 @@ pub fn verify_value_checks(def: FunctionDefinition<'_>, errors: &mut Vec<Verific
                         errors.push(VerificationError::LinearValueConsumedMultipleTimes {
                             value_id: id,
                             consumers: stat.consumers,
+                            location: last_op(def, location, |op| consumes(op, id)),
                         });
                     }
                     if stat.consumers == 0 && stat.producers > 0 {
-                        errors.push(VerificationError::LinearValueNeverConsumed { value_id: id });
+                        errors.push(VerificationError::LinearValueNeverConsumed {
+                            value_id: id,
+                            location: last_op(def, location, |op| produces(op, id)),
+                        });
                     }
                 }
             }
         }
-        Err(e) => push_oob(e, errors),
+        Err(e) => push_oob(e, location.clone(), errors),
     }
 }
 
@@ -47,17 +64,42 @@ fn is_linear(ty: Type) -> bool {
     matches!(ty, Type::Qubit | Type::QubitRegister { .. })
 }
 
-fn push_oob(e: ReadError, errors: &mut Vec<VerificationError>) {
+fn produces(op: &Operation<'_>, id: ValueId) -> bool {
+    op.outputs().any(|v| v.is_ok_and(|v| v.id() == id))
+}
+
+fn consumes(op: &Operation<'_>, id: ValueId) -> bool {
+    op.inputs().any(|v| v.is_ok_and(|v| v.id() == id))
+}
+
+/// Returns the location of the last operation in `def` matching `pred`, or
+/// the location of the function if there is none, e.g. for values produced
+/// only by region sources.
+fn last_op(
+    def: FunctionDefinition<'_>,
+    location: &Location,
+    pred: impl Fn(&Operation<'_>) -> bool,
+) -> Location {
+    def.body()
+        .nested_operations()
+        .filter(|(_, op)| pred(op))
+        .last()
+        .map_or_else(|| location.clone(), |(path, _)| location.at(path))
+}
+
+fn push_oob(e: ReadError, location: Location, errors: &mut Vec<VerificationError>) {
     if let ReadError::ValueOutOfBounds { idx, count } = e {
         errors.push(VerificationError::ValueOutOfBounds {
             value_id: idx,
             value_count: count,
+            location,
         });
     }
 }
 
 fn check_region_ordering(
     region: Region<'_>,
+    scope: &RegionScope<'_>,
     outer_defined: &HashSet<ValueId>,
     errors: &mut Vec<VerificationError>,
 ) {
@@ -68,18 +110,21 @@ fn check_region_ordering(
             Ok(v) => {
                 defined.insert(v.id());
             }
-            Err(e) => push_oob(e, errors),
+            Err(e) => push_oob(e, scope.location(), errors),
         }
     }
 
-    for op in region.operations() {
+    for (idx, op) in region.operations().enumerate() {
         for result in op.inputs() {
             match result {
                 Ok(v) if !defined.contains(&v.id()) => {
-                    errors.push(VerificationError::UsedBeforeDefined { value_id: v.id() });
+                    errors.push(VerificationError::UsedBeforeDefined {
+                        value_id: v.id(),
+                        location: scope.op(idx),
+                    });
                 }
                 Ok(_) => {}
-                Err(e) => push_oob(e, errors),
+                Err(e) => push_oob(e, scope.op(idx), errors),
             }
         }
 
@@ -88,44 +133,28 @@ fn check_region_ordering(
                 Ok(v) => {
                     defined.insert(v.id());
                 }
-                Err(e) => push_oob(e, errors),
+                Err(e) => push_oob(e, scope.op(idx), errors),
             }
         }
 
         if let OpType::ControlFlowOp(cf_op) = op.op_type() {
-            check_cf_ordering(cf_op.as_ref(), &defined, errors);
+            for (region_idx, nested) in cf_op.regions().into_iter().enumerate() {
+                let nested_scope = scope.nested(idx, region_idx);
+                check_region_ordering(nested, &nested_scope, &defined, errors);
+            }
         }
     }
 
     for result in region.targets() {
         match result {
             Ok(v) if !defined.contains(&v.id()) => {
-                errors.push(VerificationError::UsedBeforeDefined { value_id: v.id() });
+                errors.push(VerificationError::UsedBeforeDefined {
+                    value_id: v.id(),
+                    location: scope.location(),
+                });
             }
             Ok(_) => {}
-            Err(e) => push_oob(e, errors),
-        }
-    }
-}
-
-fn check_cf_ordering(
-    cf_op: &ControlFlowOp<'_>,
-    outer_defined: &HashSet<ValueId>,
-    errors: &mut Vec<VerificationError>,
-) {
-    match cf_op {
-        ControlFlowOp::For { region } => check_region_ordering(*region, outer_defined, errors),
-        ControlFlowOp::While { before, after } => {
-            check_region_ordering(*before, outer_defined, errors);
-            check_region_ordering(*after, outer_defined, errors);
-        }
-        ControlFlowOp::Switch(switch_op) => {
-            for branch in switch_op.branches() {
-                check_region_ordering(branch, outer_defined, errors);
-            }
-            if let Some(default) = switch_op.default_branch() {
-                check_region_ordering(default, outer_defined, errors);
-            }
+            Err(e) => push_oob(e, scope.location(), errors),
         }
     }
 }
@@ -0,0 +1,39 @@
+#![allow(missing_docs)]
+mod common;
+
+use common::{load, path};
+use jeff::reader::{Function, ReadJeff};
+use jeff::Jeff;
+use verifier::analysis::{analyze_qureg_bounds, BoundsWarning};
+use verifier::verify_file;
+
+fn entrypoint_bounds(jeff: &Jeff<'_>) -> Vec<BoundsWarning> {
+    let Function::Definition(main) = jeff.module().entrypoint() else {
+        panic!("Expected a function definition");
+    };
+    analyze_qureg_bounds(&main)
+}
+
+#[test]
+fn qureg_index_out_of_bounds() {
+    let rel = "tests/analysis/qureg_index_out_of_bounds.jeff";
+    // The module is well-formed; only the bounds analysis flags it.
+    assert_eq!(verify_file(path(rel)), []);
+
+    let jeff = load(rel);
+    assert_eq!(
+        entrypoint_bounds(&jeff),
+        [BoundsWarning {
+            operation: "InsertIndex",
+            register: 3,
+            index: 5,
+            size: 2,
+        }]
+    );
+}
+
+#[test]
+fn qureg_index_in_bounds() {
+    let jeff = load("../../examples/catalyst_simple/catalyst_simple.jeff");
+    assert!(entrypoint_bounds(&jeff).is_empty());
+}
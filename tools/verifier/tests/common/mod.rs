@@ -0,0 +1,30 @@
+//! Helpers shared by the verifier integration tests.
+
+// Each test crate only uses some of the helpers.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use jeff::reader::NestedOpPath;
+use jeff::Jeff;
+use verifier::Location;
+
+/// Returns the path of a file relative to the verifier crate root.
+pub fn path(rel: impl AsRef<Path>) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(rel)
+}
+
+/// Reads a jeff program relative to the verifier crate root.
+pub fn load(rel: impl AsRef<Path>) -> Jeff<'static> {
+    let file = std::fs::File::open(path(rel)).unwrap();
+    Jeff::read(std::io::BufReader::new(file)).unwrap()
+}
+
+/// Returns the location of the `op`-th operation in the body of the first
+/// function, named `main` in the test programs.
+pub fn main_op(op: usize) -> Location {
+    Location::new(0, "main").at(NestedOpPath {
+        regions: Vec::new(),
+        op,
+    })
+}
@@ -0,0 +1,79 @@
+#![allow(missing_docs)]
+mod common;
+
+use common::{load, main_op};
+use jeff::reader::optype::{ControlFlowOp, OpType};
+use jeff::reader::{Function, ReadJeff};
+use jeff::Jeff;
+use verifier::passes::type_checks::{check_loop_state, check_switch_signatures};
+use verifier::{Location, VerificationError};
+
+/// Runs `check` on the first control flow operation in the entrypoint body,
+/// which must be the first function of the module.
+fn with_cf_op<T>(jeff: &Jeff<'_>, check: impl FnOnce(&ControlFlowOp<'_>, &Location) -> T) -> T {
+    let Function::Definition(main) = jeff.module().entrypoint() else {
+        panic!("Expected a function definition");
+    };
+    let (idx, cf_op) = main
+        .body()
+        .operations()
+        .enumerate()
+        .find_map(|(idx, op)| match op.op_type() {
+            OpType::ControlFlowOp(cf_op) => Some((idx, cf_op)),
+            _ => None,
+        })
+        .expect("Expected a control flow operation");
+    check(&cf_op, &main_op(idx))
+}
+
+#[test]
+fn for_loop_state_preserved() {
+    let jeff = load("tests/positive/valid_for_qubit_isolation.jeff");
+    assert_eq!(with_cf_op(&jeff, check_loop_state), Ok(()));
+}
+
+#[test]
+fn while_loop_state_preserved() {
+    let jeff = load("tests/positive/valid_while_isolation.jeff");
+    assert_eq!(with_cf_op(&jeff, check_loop_state), Ok(()));
+}
+
+#[test]
+fn for_loop_state_shifted() {
+    let jeff = load("tests/negative/loop_state_type_mismatch.jeff");
+    assert_eq!(
+        with_cf_op(&jeff, check_loop_state),
+        Err(vec![VerificationError::LoopStateTypeMismatch {
+            operation: "for",
+            location: main_op(4),
+        }])
+    );
+}
+
+fn switch_signatures(
+    op: &ControlFlowOp<'_>,
+    location: &Location,
+) -> Result<(), Vec<VerificationError>> {
+    let ControlFlowOp::Switch(switch) = op else {
+        panic!("Expected a switch operation");
+    };
+    check_switch_signatures(switch, location)
+}
+
+#[test]
+fn switch_branches_uniform() {
+    let jeff = load("tests/positive/valid_switch_uniform.jeff");
+    assert_eq!(with_cf_op(&jeff, switch_signatures), Ok(()));
+}
+
+#[test]
+fn switch_branch_mismatched() {
+    let jeff = load("tests/negative/switch_branch_mismatch.jeff");
+    assert_eq!(
+        with_cf_op(&jeff, switch_signatures),
+        Err(vec![VerificationError::SwitchBranchMismatch {
+            branch: 1,
+            location: main_op(2),
+        }])
+    );
+}
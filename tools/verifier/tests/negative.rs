@@ -1,4 +1,7 @@
 #![allow(missing_docs)]
+mod common;
+
+use common::main_op;
 use std::path::Path;
 use verifier::{verify_file, VerificationError};
 
@@ -141,3 +144,40 @@ fn isolation_violation() {
         "expected IsolationViolation, got: {errors:?}"
     );
 }
+
+#[test]
+fn loop_state_type_mismatch() {
+    let errors = load_negative("loop_state_type_mismatch.jeff");
+    assert_eq!(
+        errors,
+        [VerificationError::LoopStateTypeMismatch {
+            operation: "for",
+            location: main_op(4),
+        }]
+    );
+}
+
+#[test]
+fn switch_branch_mismatch() {
+    let errors = load_negative("switch_branch_mismatch.jeff");
+    assert_eq!(
+        errors,
+        [VerificationError::SwitchBranchMismatch {
+            branch: 1,
+            location: main_op(2),
+        }]
+    );
+}
+
+#[test]
+fn call_type_mismatch() {
+    let errors = load_negative("call_type_mismatch.jeff");
+    assert_eq!(
+        errors,
+        vec![VerificationError::CallTypeMismatch {
+            callee: 1,
+            location: main_op(1),
+        }],
+        "expected CallTypeMismatch, got: {errors:?}"
+    );
+}
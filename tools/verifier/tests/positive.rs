@@ -1,6 +1,11 @@
 #![allow(missing_docs)]
+mod common;
+
+use common::load;
+use jeff::reader::{Function, ReadJeff};
 use std::path::Path;
-use verifier::{verify_file, VerificationError};
+use verifier::passes::type_checks::check_call_types;
+use verifier::{verify_file, Location, VerificationError};
 
 fn load_positive(name: &str) -> Vec<VerificationError> {
     let path = Path::new(env!("CARGO_MANIFEST_DIR"))
@@ -90,3 +95,21 @@ fn example_python_optimization() {
     let errors = load_example("python_optimization/python_optimization.jeff");
     assert!(errors.is_empty(), "expected no errors, got: {errors:?}");
 }
+
+#[test]
+fn valid_switch_uniform() {
+    let errors = load_positive("valid_switch_uniform.jeff");
+    assert!(errors.is_empty(), "expected no errors, got: {errors:?}");
+}
+
+#[test]
+fn example_entangled_calls_call_types() {
+    let jeff = load("../../examples/entangled_calls/entangled_calls.jeff");
+    let module = jeff.module();
+    for (id, function) in module.functions_indexed() {
+        if let Function::Definition(def) = function {
+            let location = Location::new(id, def.name());
+            assert_eq!(check_call_types(&module, &def, &location), Ok(()));
+        }
+    }
+}
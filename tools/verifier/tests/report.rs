@@ -0,0 +1,61 @@
+#![allow(missing_docs)]
+mod common;
+
+use common::{main_op, path};
+use jeff::reader::NestedOpPath;
+use verifier::{report, verify_file, Location, VerificationError};
+
+#[test]
+fn report_single_error() {
+    assert_eq!(
+        report(&verify_file(path(
+            "tests/negative/used_before_defined.jeff"
+        ))),
+        "error: function `main` (#0), operation 0: value 1 is used before it is defined\n\
+         1 verification error"
+    );
+}
+
+#[test]
+fn report_nested_error() {
+    assert_eq!(
+        report(&verify_file(path(
+            "tests/negative/isolation_violation.jeff"
+        ))),
+        "error: function `main` (#0), operation 2[0].0: value 0 from an outer scope is used \
+         inside a nested region without being passed in via sources\n\
+         1 verification error"
+    );
+}
+
+#[test]
+fn report_multiple_errors() {
+    let errors = [
+        VerificationError::InvalidEntrypoint,
+        VerificationError::CallTypeMismatch {
+            callee: 1,
+            location: main_op(3),
+        },
+        VerificationError::LinearValueConsumedMultipleTimes {
+            value_id: 4,
+            consumers: 2,
+            location: Location::new(2, "helper").at(NestedOpPath {
+                regions: vec![(1, 0), (0, 2)],
+                op: 5,
+            }),
+        },
+        VerificationError::UsedBeforeDefined {
+            value_id: 7,
+            location: Location::new(1, "body"),
+        },
+    ];
+    assert_eq!(
+        report(&errors),
+        "error: module entrypoint does not refer to a function definition\n\
+         error: function `main` (#0), operation 3: call to function 1 does not match the callee signature\n\
+         error: function `helper` (#2), operation 1[0].0[2].5: linear value 4 is consumed 2 times (must be exactly once)\n\
+         error: function `body` (#1): value 7 is used before it is defined\n\
+         4 verification errors"
+    );
+    assert_eq!(report(&[]), "");
+}
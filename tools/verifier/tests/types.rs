@@ -0,0 +1,45 @@
+#![allow(missing_docs)]
+mod common;
+
+use common::{load, main_op};
+use jeff::reader::ReadJeff;
+use verifier::passes::type_checks::verify_types;
+use verifier::VerificationError;
+
+#[test]
+fn examples_are_well_typed() {
+    for example in [
+        "qubits",
+        "entangled_qs",
+        "entangled_calls",
+        "catalyst_simple",
+        "catalyst_tket_opt",
+        "python_optimization",
+    ] {
+        let jeff = load(format!("../../examples/{example}/{example}.jeff"));
+        assert_eq!(verify_types(&jeff.module()), Ok(()), "{example}");
+    }
+}
+
+#[test]
+fn type_errors_accumulate() {
+    let jeff = load("tests/negative/multiple_type_errors.jeff");
+    let errors = verify_types(&jeff.module()).unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            VerificationError::TypeMismatch {
+                operation: "int arithmetic",
+                location: main_op(2),
+            },
+            VerificationError::InvalidOutputType {
+                operation: "Measure",
+                location: main_op(5),
+            },
+            VerificationError::CallTypeMismatch {
+                callee: 1,
+                location: main_op(3),
+            },
+        ]
+    );
+}
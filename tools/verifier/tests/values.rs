@@ -0,0 +1,41 @@
+#![allow(missing_docs)]
+mod common;
+
+use common::{load, main_op, path};
+use jeff::reader::{Function, ReadJeff};
+use verifier::analysis::{build_value_stats, ValueStats};
+use verifier::{verify_file, VerificationError};
+
+#[test]
+fn duplicate_producer_counted() {
+    let jeff = load("tests/negative/region_duplicate_producer.jeff");
+    let Function::Definition(main) = jeff.module().entrypoint() else {
+        panic!("Expected a function definition");
+    };
+    let stats = build_value_stats(main.body(), main.values().len()).unwrap();
+    assert_eq!(
+        stats,
+        [
+            ValueStats {
+                producers: 2,
+                consumers: 1
+            },
+            ValueStats {
+                producers: 1,
+                consumers: 1
+            },
+        ]
+    );
+}
+
+#[test]
+fn duplicate_producer_reported_once() {
+    assert_eq!(
+        verify_file(path("tests/negative/region_duplicate_producer.jeff")),
+        [VerificationError::ValueProducedMultipleTimes {
+            value_id: 0,
+            producers: 2,
+            location: main_op(2),
+        }]
+    );
+}